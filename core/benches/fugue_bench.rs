@@ -1,5 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
-use synckit_core::crdt::FugueText;
+use std::time::{Duration, Instant};
+use synckit_core::crdt::{FugueOp, FugueText};
 
 /// Benchmark single character insert (target: <1ms)
 fn bench_single_insert(c: &mut Criterion) {
@@ -109,6 +110,123 @@ fn bench_merge(c: &mut Criterion) {
     });
 }
 
+/// Build a document of `len` chars out of many small blocks
+///
+/// Words are typed at scattered positions, so the document has roughly
+/// `len / 8` blocks instead of one long run.
+fn build_document(len: usize) -> FugueText {
+    let mut text = FugueText::new("client1".to_string());
+    let mut seed: u64 = 42;
+
+    while text.len() < len {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        let position = (seed >> 33) as usize % (text.len() + 1);
+        text.insert(position, "lorem ip").unwrap();
+    }
+
+    text
+}
+
+/// Benchmark integrating a remote edit into documents of growing length
+///
+/// The cost should stay flat as the document grows: remote blocks and
+/// deletions are placed in the rope without rebuilding it. Each iteration a
+/// peer types a word in the middle and deletes another, so the document
+/// keeps its length.
+fn bench_merge_by_document_length(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fugue_merge_remote_edit");
+
+    for len in [1_000, 10_000, 100_000].iter() {
+        let mut text = build_document(*len);
+        let mut peer = FugueText::new("client2".to_string());
+        peer.merge(&text).unwrap();
+
+        let edit = |peer: &mut FugueText| -> Vec<FugueOp> {
+            vec![
+                peer.insert(len / 2, "hello").unwrap(),
+                peer.delete(len / 4, 5).unwrap(),
+            ]
+        };
+
+        group.bench_function(BenchmarkId::new("apply_op", len), |b| {
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let ops = edit(&mut peer);
+
+                    let start = Instant::now();
+                    for op in &ops {
+                        text.apply_op(op).unwrap();
+                    }
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("encode_diff", len), |b| {
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    edit(&mut peer);
+
+                    let start = Instant::now();
+                    for op in peer.encode_diff(&text.state_vector()) {
+                        text.apply_op(&op).unwrap();
+                    }
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            });
+        });
+
+        assert_eq!(text.to_string(), peer.to_string());
+    }
+
+    group.finish();
+}
+
+/// Benchmark merging two replicas that diverged from a shared document
+///
+/// Both replicas start from the same document and make 100 edits each
+/// (a word typed and a word deleted at scattered positions) before one
+/// merges the other's full state.
+fn bench_merge_diverged_replicas(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fugue_merge_diverged");
+    group.sample_size(20);
+
+    for len in [1_000, 10_000, 100_000].iter() {
+        let base = build_document(*len);
+        let mut left = base.fork("client2".to_string());
+        let mut right = base.fork("client3".to_string());
+
+        let mut seed: u64 = 7;
+        for _ in 0..100 {
+            for replica in [&mut left, &mut right] {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let position = (seed >> 33) as usize % (replica.len() - 5);
+                replica.insert(position, "edit ").unwrap();
+                replica
+                    .delete((position + len / 3) % (replica.len() - 5), 5)
+                    .unwrap();
+            }
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(len), len, |b, _| {
+            b.iter_batched(
+                || left.clone(),
+                |mut merged| {
+                    merged.merge(&right).unwrap();
+                    black_box(merged);
+                },
+                criterion::BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
 /// Benchmark concurrent edits convergence
 fn bench_concurrent_convergence(c: &mut Criterion) {
     c.bench_function("fugue_concurrent_3way_convergence", |b| {
@@ -190,6 +308,8 @@ criterion_group!(
     bench_delete,
     bench_yjs_260k_ops,
     bench_merge,
+    bench_merge_by_document_length,
+    bench_merge_diverged_replicas,
     bench_concurrent_convergence,
     bench_serialization,
     bench_deserialization,
@@ -13,6 +13,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             "../protocol/specs/types.proto",
             "../protocol/specs/messages.proto",
             "../protocol/specs/sync.proto",
+            "../protocol/specs/auth.proto",
         ];
 
         // Configure prost to generate code
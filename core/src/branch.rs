@@ -0,0 +1,367 @@
+//! Branches: fork a document, edit the copy, merge it back
+//!
+//! A [`Branch`] is a copy of a document's [`History`] that is edited on its
+//! own, under a client ID of its own, and later merged back into the main
+//! line like a git branch. The branch remembers the main-line version it
+//! was forked or last merged at, so it can tell:
+//! - how far the two lines have diverged ([`Divergence`]);
+//! - which fields were changed on both lines since that version, and which
+//!   write a merge will keep ([`MergePreview`]).
+//!
+//! Merging goes through `Document::merge`, so merging a branch is the same
+//! as syncing with a replica that made the branch's edits, and can be
+//! repeated as both lines move on.
+//!
+//! Text is branched the same way: `FugueText::fork` copies a text under a
+//! new client ID, `FugueText::merge` merges it back, and
+//! [`Divergence::new`] compares their state vectors.
+
+use crate::document::{Document, Field};
+use crate::history::{History, HistoryEntry};
+use crate::path;
+use crate::sync::{MergeReport, VectorClock};
+use crate::{ClientID, FieldPath};
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Where two lines stand relative to each other: the version of each
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Version of the main line
+    pub main: VectorClock,
+
+    /// Version of the branch
+    pub branch: VectorClock,
+}
+
+impl Divergence {
+    /// Compare two versions
+    pub fn new(main: VectorClock, branch: VectorClock) -> Self {
+        Self { main, branch }
+    }
+
+    /// Check if the branch has changes the main line has not seen
+    pub fn is_ahead(&self) -> bool {
+        !self.main.dominates(&self.branch)
+    }
+
+    /// Check if the main line has changes the branch has not seen
+    pub fn is_behind(&self) -> bool {
+        !self.branch.dominates(&self.main)
+    }
+
+    /// Check if both lines have changes the other has not seen
+    pub fn is_diverged(&self) -> bool {
+        self.is_ahead() && self.is_behind()
+    }
+
+    /// Check if both lines have seen the same changes
+    pub fn is_even(&self) -> bool {
+        !self.is_ahead() && !self.is_behind()
+    }
+}
+
+/// A field changed on both lines since the last merge
+///
+/// The two paths are equal, or one is a parent of the other: writing
+/// `user` on one line and `user.name` on the other conflicts too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Latest write on the main line
+    pub main: HistoryEntry,
+
+    /// Latest write on the branch
+    pub branch: HistoryEntry,
+
+    /// Whether the branch write survives the merge
+    pub branch_wins: bool,
+}
+
+/// What merging a branch would do, without doing it
+#[derive(Debug, Clone)]
+pub struct MergePreview {
+    /// Main document after the merge
+    pub document: Document,
+
+    /// What the merge did, field by field
+    pub report: MergeReport,
+
+    /// Fields changed on both lines since the last merge
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A fork of a document, edited apart from the main line
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::branch::Branch;
+/// use synckit_core::history::History;
+/// use synckit_core::Document;
+/// use serde_json::json;
+///
+/// let mut main = History::new(Document::new("post-1".to_string()));
+/// main.set_field("title", json!("Draft"), 1, "alice");
+///
+/// // Try a new title and body on a branch
+/// let mut branch = Branch::fork(&main, "rewrite", "alice-rewrite".to_string());
+/// branch.set_field("title", json!("Final"), 3);
+/// branch.set_field("body", json!("..."), 4);
+///
+/// // Meanwhile the title changes on the main line too
+/// main.set_field("title", json!("Draft 2"), 2, "alice");
+/// assert!(branch.divergence(&main).is_diverged());
+///
+/// let preview = branch.preview_merge(&main);
+/// assert_eq!(preview.conflicts.len(), 1);
+/// assert!(preview.conflicts[0].branch_wins);
+///
+/// branch.merge_into(&mut main);
+/// assert_eq!(main.document().to_json(), json!({ "title": "Final", "body": "..." }));
+/// assert!(branch.divergence(&main).is_behind());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Branch {
+    /// Name of the branch
+    name: String,
+
+    /// Client ID of writes made on the branch
+    client_id: ClientID,
+
+    /// Version of the main line when the branch was forked or last merged
+    base: VectorClock,
+
+    /// Branch state and change log
+    history: History,
+}
+
+impl Branch {
+    /// Fork a document with its history
+    ///
+    /// `client_id` signs the writes made on the branch. It must not be used
+    /// on the main line, or writes made on both lines get the same version
+    /// and their divergence is lost.
+    pub fn fork(main: &History, name: &str, client_id: ClientID) -> Self {
+        Self {
+            name: name.to_string(),
+            client_id,
+            base: main.document().version().clone(),
+            history: main.clone(),
+        }
+    }
+
+    /// Name of the branch
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Client ID of writes made on the branch
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Version of the main line when the branch was forked or last merged
+    ///
+    /// Writes made on either line after it are the ones checked for
+    /// conflicts.
+    pub fn base(&self) -> &VectorClock {
+        &self.base
+    }
+
+    /// Branch state and change log
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Current state of the branch
+    pub fn document(&self) -> &Document {
+        self.history.document()
+    }
+
+    /// Set a field on the branch
+    pub fn set_field(&mut self, field_path: &str, value: JsonValue, clock: u64) -> bool {
+        self.history
+            .set_field(field_path, value, clock, &self.client_id)
+    }
+
+    /// Delete a field on the branch
+    pub fn delete_field(&mut self, field_path: &str, clock: u64) -> bool {
+        self.history
+            .delete_field(field_path, clock, &self.client_id)
+    }
+
+    /// Compare the branch with the main line
+    pub fn divergence(&self, main: &History) -> Divergence {
+        Divergence::new(
+            main.document().version().clone(),
+            self.document().version().clone(),
+        )
+    }
+
+    /// Fields changed on both lines since the last merge
+    pub fn conflicts(&self, main: &History) -> Vec<Conflict> {
+        let main_writes = latest_writes(main, &self.base);
+        let branch_writes = latest_writes(&self.history, &self.base);
+
+        let mut conflicts = Vec::new();
+        for (branch_path, branch) in &branch_writes {
+            for (main_path, main) in &main_writes {
+                let overlaps = main_path == branch_path
+                    || path::is_ancestor(main_path, branch_path)
+                    || path::is_ancestor(branch_path, main_path);
+
+                // The same write reached both lines: nothing to resolve
+                if !overlaps || main.field == branch.field {
+                    continue;
+                }
+
+                let branch_wins = newer(&branch.field, &main.field);
+                conflicts.push(Conflict {
+                    main: (*main).clone(),
+                    branch: (*branch).clone(),
+                    branch_wins,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Show what merging the branch into the main line would do
+    pub fn preview_merge(&self, main: &History) -> MergePreview {
+        let mut document = main.document().clone();
+        let report = document.merge_with_report(self.document());
+
+        MergePreview {
+            document,
+            report,
+            conflicts: self.conflicts(main),
+        }
+    }
+
+    /// Merge the branch into the main line
+    ///
+    /// The writes that win are logged in the main history. The branch stays
+    /// usable: later edits can be merged again, and only conflict with
+    /// main-line writes made after this merge.
+    pub fn merge_into(&mut self, main: &mut History) -> MergeReport {
+        let report = main.merge(self.document());
+        self.base = main.document().version().clone();
+        report
+    }
+
+    /// Bring changes made on the main line into the branch
+    pub fn update_from(&mut self, main: &History) -> MergeReport {
+        let report = self.history.merge(main.document());
+        self.base = main.document().version().clone();
+        report
+    }
+}
+
+/// The newest write to each path since `base`
+fn latest_writes<'a>(
+    history: &'a History,
+    base: &'a VectorClock,
+) -> BTreeMap<&'a FieldPath, &'a HistoryEntry> {
+    let mut writes: BTreeMap<&FieldPath, &HistoryEntry> = BTreeMap::new();
+    for entry in history.changes_between(base, history.document().version()) {
+        let latest = writes.entry(&entry.path).or_insert(entry);
+        if newer(&entry.field, &latest.field) {
+            *latest = entry;
+        }
+    }
+    writes
+}
+
+fn newer(a: &Field, b: &Field) -> bool {
+    a.timestamp.compare_lww(&b.timestamp) == Ordering::Greater
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn main_line() -> History {
+        let mut main = History::new(Document::new("doc-1".to_string()));
+        main.set_field("user", json!({ "name": "Ann", "age": 30 }), 1, "main");
+        main
+    }
+
+    #[test]
+    fn test_divergence() {
+        let mut main = main_line();
+        let mut branch = Branch::fork(&main, "b", "branch".to_string());
+        assert!(branch.divergence(&main).is_even());
+
+        branch.set_field("tags", json!(["a"]), 2);
+        assert!(branch.divergence(&main).is_ahead());
+        assert!(!branch.divergence(&main).is_behind());
+
+        main.set_field("title", json!("T"), 3, "main");
+        assert!(branch.divergence(&main).is_diverged());
+
+        branch.merge_into(&mut main);
+        branch.update_from(&main);
+        assert!(branch.divergence(&main).is_even());
+        assert_eq!(main.document().to_json(), branch.document().to_json());
+    }
+
+    #[test]
+    fn test_preview_lists_conflicts_without_merging() {
+        let mut main = main_line();
+        let mut branch = Branch::fork(&main, "b", "branch".to_string());
+
+        // Parent and child written on different lines
+        main.set_field("user.age", json!(31), 5, "main");
+        branch.set_field("user", json!({ "name": "Bo" }), 4);
+        // Only on the branch: no conflict
+        branch.set_field("title", json!("T"), 6);
+
+        let before = main.document().to_json();
+        let preview = branch.preview_merge(&main);
+        assert_eq!(main.document().to_json(), before);
+
+        assert_eq!(preview.conflicts.len(), 1);
+        let conflict = &preview.conflicts[0];
+        assert_eq!(conflict.main.path, "user.age");
+        assert_eq!(conflict.branch.path, "user");
+        assert!(!conflict.branch_wins);
+
+        assert_eq!(
+            preview.document.to_json(),
+            json!({ "user": { "name": "Bo", "age": 31 }, "title": "T" })
+        );
+
+        branch.merge_into(&mut main);
+        assert_eq!(main.document().to_json(), preview.document.to_json());
+    }
+
+    #[test]
+    fn test_repeated_merges_only_compare_new_writes() {
+        let mut main = main_line();
+        let mut branch = Branch::fork(&main, "b", "branch".to_string());
+
+        main.set_field("title", json!("A"), 2, "main");
+        branch.set_field("title", json!("B"), 3);
+        assert_eq!(branch.conflicts(&main).len(), 1);
+        branch.merge_into(&mut main);
+        assert_eq!(main.document().version(), branch.base());
+
+        // The branch edits its own earlier write again
+        branch.set_field("title", json!("C"), 4);
+        assert!(branch.conflicts(&main).is_empty());
+        branch.merge_into(&mut main);
+        assert_eq!(main.document().to_json()["title"], json!("C"));
+
+        // A main-line write after the last merge still conflicts
+        main.set_field("title", json!("D"), 5, "main");
+        branch.set_field("title", json!("E"), 6);
+        let conflicts = branch.conflicts(&main);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].main.field.value, json!("D"));
+
+        branch.update_from(&main);
+        assert!(branch.conflicts(&main).is_empty());
+    }
+}
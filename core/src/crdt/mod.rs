@@ -7,8 +7,13 @@
 //!
 //! - **PN-Counter:** Positive-Negative Counter (`feature = "counters"`)
 //! - **OR-Set:** Observed-Remove Set (`feature = "sets"`)
+//! - **OR-Map:** Observed-Remove Map of nested CRDTs (`feature = "maps"`)
 //! - **Fractional Index:** Position-based ordering (`feature = "fractional-index"`)
 //! - **Text CRDT:** Fugue-based collaborative text with maximal non-interleaving (`feature = "text-crdt"`)
+//! - **Rich Text:** Peritext formatting on top of the text CRDT (`feature = "rich-text"`)
+//!
+//! All of them, and LWW registers, implement the [`Crdt`] trait for
+//! merging, serialization and delta extraction.
 //!
 //! # Usage
 //!
@@ -23,6 +28,8 @@
 //! - "Conflict-free Replicated Data Types" (INRIA Research Report 7687)
 //! - "Fugue: A CRDT for Shared Text Editing" by Weihai Yu et al.
 
+pub mod traits;
+
 // Conditionally compile each CRDT based on features
 #[cfg(feature = "counters")]
 pub mod pn_counter;
@@ -30,21 +37,34 @@ pub mod pn_counter;
 #[cfg(feature = "sets")]
 pub mod or_set;
 
+#[cfg(feature = "maps")]
+pub mod or_map;
+
 #[cfg(feature = "fractional-index")]
 pub mod fractional_index;
 
 #[cfg(feature = "text-crdt")]
 pub mod text_fugue;
 
+pub use traits::Crdt;
+
 // Re-exports (only if features enabled)
 #[cfg(feature = "counters")]
 pub use pn_counter::PNCounter;
 
 #[cfg(feature = "sets")]
-pub use or_set::ORSet;
+pub use or_set::{ORSet, UniqueTag};
+
+#[cfg(feature = "maps")]
+pub use or_map::{ORMap, ORMapDelta, ORMapVersion};
 
 #[cfg(feature = "fractional-index")]
 pub use fractional_index::FractionalIndex;
 
 #[cfg(feature = "text-crdt")]
-pub use text_fugue::{FugueBlock, FugueText, LamportClock, NodeId, TextError};
+pub use text_fugue::{
+    FugueBlock, FugueOp, FugueText, LamportClock, NodeId, StateVector, TextError,
+};
+
+#[cfg(feature = "rich-text")]
+pub use text_fugue::RichText;
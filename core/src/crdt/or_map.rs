@@ -0,0 +1,422 @@
+//! OR-Map: Observed-Remove Map CRDT
+//!
+//! A map whose values are CRDTs themselves: counters, sets, text, LWW
+//! registers or further maps. Keys live in an OR-Set and values merge
+//! recursively, so concurrent edits under one key combine instead of one
+//! replica's value replacing the other's.
+//!
+//! # Properties
+//!
+//! - **Add wins:** A concurrent update and remove of a key → the key stays
+//! - **Recursive merge:** Values under the same key merge with their own
+//!   [`Crdt::merge`]
+//! - **Removes do not reset:** Removing a key hides it, value included.
+//!   Updating it again continues from the old value, merged with what
+//!   every replica had, so removed state comes back with the key
+//!
+//! Every update tags the key again, so the key set grows with the number
+//! of updates rather than the number of keys, and removed keys keep their
+//! values.
+//!
+//! # Example
+//!
+//! ```
+//! use synckit_core::crdt::{Crdt, ORMap, ORSet};
+//!
+//! let mut map1: ORMap<String, ORSet<String>> = ORMap::new("replica1".to_string());
+//! let mut map2: ORMap<String, ORSet<String>> = ORMap::new("replica2".to_string());
+//!
+//! // Both replicas tag the same post
+//! map1.update("post-1".to_string(), |tags| tags.add("rust".to_string()));
+//! map2.update("post-1".to_string(), |tags| tags.add("crdt".to_string()));
+//!
+//! // Merge
+//! map1.merge(&map2);
+//!
+//! assert_eq!(map1.get(&"post-1".to_string()).unwrap().len(), 2);
+//! ```
+
+use super::or_set::ORSet;
+use super::traits::Crdt;
+use crate::error::Result;
+use crate::sync::VectorClock;
+use crate::ClientID;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+/// Observed-Remove Map CRDT
+///
+/// Maps keys (add-wins) to CRDT values that merge recursively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ORMap<K, V>
+where
+    K: Clone + Ord + Hash + Serialize,
+{
+    /// Replica identifier
+    replica_id: ClientID,
+
+    /// Keys in the map, tagged once per update
+    keys: ORSet<K>,
+
+    /// Values of the keys in the map, and of the removed ones
+    values: BTreeMap<K, V>,
+}
+
+/// What a replica of an [`ORMap`] has seen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ORMapVersion<K: Ord, V> {
+    /// Version of the key set
+    pub keys: VectorClock,
+
+    /// Version of each value in the map
+    pub values: BTreeMap<K, V>,
+}
+
+impl<K: Ord, V> Default for ORMapVersion<K, V> {
+    fn default() -> Self {
+        Self {
+            keys: VectorClock::new(),
+            values: BTreeMap::new(),
+        }
+    }
+}
+
+/// Changes of an [`ORMap`] a replica is missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ORMapDelta<K, D>
+where
+    K: Clone + Ord + Hash + Serialize,
+{
+    /// Unseen updates and removes of keys
+    pub keys: ORSet<K>,
+
+    /// Unseen changes to values, by key
+    pub values: BTreeMap<K, D>,
+}
+
+impl<K, V> ORMap<K, V>
+where
+    K: Clone + Ord + Hash + Serialize + DeserializeOwned,
+    V: Crdt,
+{
+    /// Create a new OR-Map for the given replica
+    pub fn new(replica_id: ClientID) -> Self {
+        Self {
+            replica_id: replica_id.clone(),
+            keys: ORSet::new(replica_id),
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Update the value of a key
+    ///
+    /// Adds the key with an empty value if it is not in the map. Values
+    /// are written as this map's replica.
+    pub fn update<F>(&mut self, key: K, f: F)
+    where
+        F: FnOnce(&mut V),
+    {
+        self.keys.add(key.clone());
+        let replica_id = &self.replica_id;
+        let value = self
+            .values
+            .entry(key)
+            .or_insert_with(|| V::new_replica(replica_id.clone()));
+        f(value);
+    }
+
+    /// Remove a key and its value
+    ///
+    /// Only removes the updates this replica has seen: a concurrent
+    /// update keeps the key. The value is kept hidden, and updating the
+    /// key again continues from it.
+    pub fn remove(&mut self, key: &K) {
+        self.keys.remove(key);
+    }
+
+    /// Get the value of a key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(key).filter(|_| self.keys.contains(key))
+    }
+
+    /// Check if a key is in the map
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Get all keys in the map, in order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Get all entries in the map, in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.values
+            .iter()
+            .filter(|(key, _)| self.keys.contains(key))
+    }
+
+    /// Get the number of keys in the map
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Get the replica ID
+    pub fn replica_id(&self) -> &ClientID {
+        &self.replica_id
+    }
+
+    /// Merge a value into the one under `key`, creating it if needed
+    fn merge_value(&mut self, key: &K, other: &V) {
+        match self.values.get_mut(key) {
+            Some(value) => value.merge(other),
+            None => {
+                let mut value = V::new_replica(self.replica_id.clone());
+                value.merge(other);
+                self.values.insert(key.clone(), value);
+            }
+        }
+    }
+
+    /// Start the values of keys added without value changes
+    ///
+    /// A key added with an empty value has no value changes to send.
+    fn align_values(&mut self) {
+        let added: Vec<K> = self
+            .keys
+            .iter()
+            .filter(|key| !self.values.contains_key(key))
+            .cloned()
+            .collect();
+        for key in added {
+            let value = V::new_replica(self.replica_id.clone());
+            self.values.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> Crdt for ORMap<K, V>
+where
+    K: Clone + Ord + Hash + Serialize + DeserializeOwned,
+    V: Crdt,
+{
+    type Version = ORMapVersion<K, V::Version>;
+    type Delta = ORMapDelta<K, V::Delta>;
+
+    fn new_replica(replica_id: ClientID) -> Self {
+        ORMap::new(replica_id)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.keys.merge(&other.keys);
+        for (key, value) in &other.values {
+            self.merge_value(key, value);
+        }
+        self.align_values();
+    }
+
+    fn version(&self) -> Self::Version {
+        ORMapVersion {
+            keys: self.keys.version(),
+            values: self
+                .values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.version()))
+                .collect(),
+        }
+    }
+
+    fn delta_since(&self, since: &Self::Version) -> Self::Delta {
+        let mut values = BTreeMap::new();
+        for (key, value) in &self.values {
+            let seen = since.values.get(key).cloned().unwrap_or_default();
+            if seen != value.version() {
+                values.insert(key.clone(), value.delta_since(&seen));
+            }
+        }
+
+        ORMapDelta {
+            keys: self.keys.delta_since(&since.keys),
+            values,
+        }
+    }
+
+    fn apply_delta(&mut self, delta: &Self::Delta) -> Result<()> {
+        self.keys.merge(&delta.keys);
+        for (key, changes) in &delta.values {
+            match self.values.get_mut(key) {
+                Some(value) => value.apply_delta(changes)?,
+                None => {
+                    let mut value = V::new_replica(self.replica_id.clone());
+                    value.apply_delta(changes)?;
+                    self.values.insert(key.clone(), value);
+                }
+            }
+        }
+        self.align_values();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{LWWField, Timestamp};
+    use serde_json::json;
+
+    type Tags = ORMap<String, ORSet<String>>;
+
+    #[test]
+    fn test_concurrent_update_wins_over_remove() {
+        let mut map1: Tags = ORMap::new("replica1".to_string());
+        map1.update("doc".to_string(), |tags| tags.add("draft".to_string()));
+
+        let mut map2: Tags = ORMap::new("replica2".to_string());
+        map2.merge(&map1);
+
+        // Concurrent remove and update of the same key
+        map1.remove(&"doc".to_string());
+        map2.update("doc".to_string(), |tags| tags.add("urgent".to_string()));
+
+        map1.merge(&map2);
+        map2.merge(&map1);
+
+        assert_eq!(map1.version(), map2.version());
+        let tags = map1.get(&"doc".to_string()).unwrap();
+        assert!(tags.contains(&"draft".to_string()));
+        assert!(tags.contains(&"urgent".to_string()));
+
+        // A remove that has seen every update sticks
+        map2.remove(&"doc".to_string());
+        map1.merge(&map2);
+        assert!(map1.is_empty());
+
+        let bytes = map1.to_bytes().unwrap();
+        assert_eq!(Tags::from_bytes(&bytes).unwrap(), map1);
+    }
+
+    #[test]
+    fn test_removed_value_comes_back_from_stale_replica() {
+        let mut map1: Tags = ORMap::new("replica1".to_string());
+        map1.update("doc".to_string(), |tags| tags.add("draft".to_string()));
+        let mut map2: Tags = ORMap::new("replica2".to_string());
+        map2.merge(&map1);
+
+        // Remove, then update again, before map2 hears of the remove
+        map1.remove(&"doc".to_string());
+        assert!(map1.get(&"doc".to_string()).is_none());
+        map1.update("doc".to_string(), |tags| tags.add("final".to_string()));
+
+        let mut by_delta = map1.clone();
+        by_delta
+            .apply_delta(&map2.delta_since(&by_delta.version()))
+            .unwrap();
+        map1.merge(&map2);
+
+        assert_eq!(by_delta, map1);
+        let tags = map1.get(&"doc".to_string()).unwrap();
+        assert!(tags.contains(&"draft".to_string()));
+        assert!(tags.contains(&"final".to_string()));
+    }
+
+    #[test]
+    fn test_nested_maps_sync_by_delta() {
+        type Profiles = ORMap<String, ORMap<String, LWWField>>;
+
+        let mut server: Profiles = ORMap::new("server".to_string());
+        server.update("alice".to_string(), |profile| {
+            profile.update("name".to_string(), |name| {
+                *name = LWWField::new(json!("Alice"), Timestamp::new(1, "server".to_string()));
+            });
+        });
+
+        let mut client: Profiles = ORMap::new("client".to_string());
+        client.merge(&server);
+        let synced = client.version();
+
+        server.update("alice".to_string(), |profile| {
+            profile.update("city".to_string(), |city| {
+                *city = LWWField::new(json!("Oslo"), Timestamp::new(2, "server".to_string()));
+            });
+        });
+        server.update("bob".to_string(), |_| {});
+
+        let delta = server.delta_since(&synced);
+        assert!(delta.keys.contains(&"bob".to_string()));
+        let alice = &delta.values["alice"];
+        assert!(alice.values.contains_key("city"));
+        assert!(!alice.values.contains_key("name"));
+
+        client.apply_delta(&delta).unwrap();
+        let mut full = ORMap::new("client".to_string());
+        full.merge(&server);
+        assert_eq!(client.version(), full.version());
+        assert!(client.contains_key(&"bob".to_string()));
+        assert_eq!(
+            client
+                .get(&"alice".to_string())
+                .unwrap()
+                .get(&"city".to_string())
+                .unwrap()
+                .value,
+            json!("Oslo")
+        );
+
+        let bytes = client.to_bytes().unwrap();
+        assert_eq!(Profiles::from_bytes(&bytes).unwrap(), client);
+    }
+
+    #[cfg(feature = "counters")]
+    #[test]
+    fn test_counter_values_merge() {
+        use crate::crdt::PNCounter;
+
+        let mut map1: ORMap<String, PNCounter> = ORMap::new("replica1".to_string());
+        let mut map2: ORMap<String, PNCounter> = ORMap::new("replica2".to_string());
+        map1.update("votes".to_string(), |votes| votes.increment(2));
+        map2.update("votes".to_string(), |votes| votes.decrement(1));
+
+        let delta = map2.delta_since(&map1.version());
+        map1.apply_delta(&delta).unwrap();
+        map2.merge(&map1);
+
+        assert_eq!(map1.get(&"votes".to_string()).unwrap().value(), 1);
+        assert_eq!(map1.version(), map2.version());
+    }
+
+    #[cfg(feature = "text-crdt")]
+    #[test]
+    fn test_text_values_merge() {
+        use crate::crdt::FugueText;
+
+        let mut map1: ORMap<String, FugueText> = ORMap::new("replica1".to_string());
+        map1.update("title".to_string(), |text| {
+            text.insert(0, "Hello").unwrap();
+        });
+        let mut map2: ORMap<String, FugueText> = ORMap::new("replica2".to_string());
+        map2.merge(&map1);
+
+        map1.update("title".to_string(), |text| {
+            text.insert(5, "!").unwrap();
+        });
+        map2.update("title".to_string(), |text| {
+            text.insert(0, ">").unwrap();
+        });
+
+        let delta = map2.delta_since(&map1.version());
+        map1.apply_delta(&delta).unwrap();
+        map2.merge(&map1);
+
+        let title =
+            |map: &ORMap<String, FugueText>| map.get(&"title".to_string()).unwrap().to_string();
+        assert_eq!(title(&map1), ">Hello!");
+        assert_eq!(title(&map2), ">Hello!");
+    }
+}
@@ -28,9 +28,14 @@
 //! assert!(set1.contains(&"banana".to_string()));
 //! ```
 
+use super::traits::Crdt;
+use crate::error::SyncError;
+use crate::sync::VectorClock;
 use crate::ClientID;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -40,16 +45,23 @@ use js_sys::Date;
 
 /// Unique identifier for an element in the set
 ///
-/// Combines replica ID and timestamp to ensure global uniqueness
+/// Combines replica ID and timestamp to ensure global uniqueness. Removes
+/// are tagged too, so a replica's operations can be listed in order.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-struct UniqueTag {
-    replica_id: ClientID,
-    timestamp: u64,
-    sequence: u64, // For same-timestamp operations
+pub struct UniqueTag {
+    /// Replica that made the operation
+    pub replica_id: ClientID,
+
+    /// Wall-clock time of the operation, in microseconds
+    pub timestamp: u64,
+
+    /// Number of operations the replica made, this one included
+    pub sequence: u64,
 }
 
 impl UniqueTag {
-    fn new(replica_id: ClientID, timestamp: u64, sequence: u64) -> Self {
+    /// Create a new tag
+    pub fn new(replica_id: ClientID, timestamp: u64, sequence: u64) -> Self {
         Self {
             replica_id,
             timestamp,
@@ -58,6 +70,37 @@ impl UniqueTag {
     }
 }
 
+impl std::fmt::Display for UniqueTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}@{}:{}",
+            self.replica_id, self.timestamp, self.sequence
+        )
+    }
+}
+
+/// Parse the `replica@timestamp:sequence` form produced by `Display`
+///
+/// Replica IDs may themselves contain `@`, so the timestamp is taken from
+/// after the last one.
+impl FromStr for UniqueTag {
+    type Err = SyncError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SyncError::InvalidOperation(format!("Invalid set tag: {}", s));
+
+        let (replica_id, rest) = s.rsplit_once('@').ok_or_else(invalid)?;
+        let (timestamp, sequence) = rest.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self::new(
+            replica_id.to_string(),
+            timestamp.parse().map_err(|_| invalid())?,
+            sequence.parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
 /// Observed-Remove Set CRDT
 ///
 /// Maintains a set of elements where each add operation is tagged uniquely.
@@ -78,6 +121,14 @@ where
     /// An element is in the set if it has at least one tag not in this set
     removed_tags: HashSet<UniqueTag>,
 
+    /// Removes, by the tag of the remove, with the tags each one removed
+    ///
+    /// Sets saved before removes were tagged have removed tags missing
+    /// from here. Serialized keyed by the tag's string form, since JSON
+    /// object keys must be strings.
+    #[serde(default, with = "tag_keys")]
+    removals: HashMap<UniqueTag, HashSet<UniqueTag>>,
+
     /// Sequence counter for this replica (for same-timestamp operations)
     sequence: u64,
 }
@@ -92,6 +143,7 @@ where
             replica_id,
             elements: HashMap::new(),
             removed_tags: HashSet::new(),
+            removals: HashMap::new(),
             sequence: 0,
         }
     }
@@ -100,18 +152,7 @@ where
     ///
     /// Creates a unique tag for this add operation.
     pub fn add(&mut self, element: T) {
-        #[cfg(not(target_arch = "wasm32"))]
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_micros() as u64;
-
-        #[cfg(target_arch = "wasm32")]
-        let timestamp = (Date::now() * 1000.0) as u64; // Convert ms to microseconds
-
-        self.sequence += 1;
-        let tag = UniqueTag::new(self.replica_id.clone(), timestamp, self.sequence);
-
+        let tag = self.next_tag();
         self.elements.entry(element).or_default().insert(tag);
     }
 
@@ -120,12 +161,11 @@ where
     /// Marks all current tags for this element as removed.
     /// If the element is added again later, it will get a new tag.
     pub fn remove(&mut self, element: &T) {
-        if let Some(tags) = self.elements.get(element) {
-            // Mark all tags for this element as removed
-            for tag in tags {
-                self.removed_tags.insert(tag.clone());
-            }
-        }
+        let tags: HashSet<UniqueTag> = match self.elements.get(element) {
+            Some(tags) => tags.difference(&self.removed_tags).cloned().collect(),
+            None => return,
+        };
+        self.record_remove(tags);
     }
 
     /// Check if an element is in the set
@@ -163,10 +203,15 @@ where
 
     /// Merge another OR-Set's state into this one
     ///
-    /// Takes the union of all elements and removed tags.
+    /// Takes the union of all elements and removed tags. A replica that
+    /// gets its own operations back this way (restoring from a peer)
+    /// continues numbering after them.
     pub fn merge(&mut self, other: &ORSet<T>) {
         // Merge elements (union of tags)
         for (element, tags) in &other.elements {
+            for tag in tags {
+                self.observe(tag);
+            }
             self.elements
                 .entry(element.clone())
                 .or_default()
@@ -174,17 +219,188 @@ where
         }
 
         // Merge removed tags (union)
+        for tag in &other.removed_tags {
+            self.observe(tag);
+        }
         self.removed_tags.extend(other.removed_tags.clone());
+        for (remove, tags) in &other.removals {
+            self.observe(remove);
+            self.removals
+                .entry(remove.clone())
+                .or_default()
+                .extend(tags.iter().cloned());
+        }
     }
 
     /// Clear all elements from the set
     pub fn clear(&mut self) {
         // Mark all current tags as removed
-        for tags in self.elements.values() {
-            for tag in tags {
-                self.removed_tags.insert(tag.clone());
+        let tags: HashSet<UniqueTag> = self
+            .elements
+            .values()
+            .flatten()
+            .filter(|tag| !self.removed_tags.contains(tag))
+            .cloned()
+            .collect();
+        self.record_remove(tags);
+    }
+
+    /// Highest operation sequence seen per replica, adds and removes
+    ///
+    /// A peer at this version has seen every operation of this replica.
+    pub fn version(&self) -> VectorClock {
+        let mut version = VectorClock::new();
+        let tags = self.elements.values().flatten().chain(self.removals.keys());
+        for tag in tags {
+            if tag.sequence > version.get(&tag.replica_id) {
+                version.update(&tag.replica_id, tag.sequence);
             }
         }
+        version
+    }
+
+    /// The adds and removes a peer at `since` has not seen, as a set
+    ///
+    /// `since` is the peer's [`ORSet::version`]. Removed tags without a
+    /// tagged remove cannot be placed against a version, so they are always
+    /// included.
+    pub fn delta_since(&self, since: &VectorClock) -> ORSet<T> {
+        let unseen = |tag: &UniqueTag| tag.sequence > since.get(&tag.replica_id);
+
+        let mut delta = ORSet::new(self.replica_id.clone());
+        for (element, tag) in self.adds().filter(|(_, tag)| unseen(tag)) {
+            delta.apply_add(element.clone(), tag.clone());
+        }
+        for (remove, tags) in self.removes().filter(|(remove, _)| unseen(remove)) {
+            delta.apply_remove(Some(remove.clone()), tags.clone());
+        }
+        let untagged: HashSet<UniqueTag> = self.untagged_removes().cloned().collect();
+        delta.apply_remove(None, untagged);
+        delta
+    }
+
+    /// Add operations, as element and tag
+    pub fn adds(&self) -> impl Iterator<Item = (&T, &UniqueTag)> {
+        self.elements
+            .iter()
+            .flat_map(|(element, tags)| tags.iter().map(move |tag| (element, tag)))
+    }
+
+    /// Remove operations, as the tag of the remove and the tags removed
+    pub fn removes(&self) -> impl Iterator<Item = (&UniqueTag, &HashSet<UniqueTag>)> {
+        self.removals.iter()
+    }
+
+    /// Removed tags that no tagged remove covers (sets saved before
+    /// removes were tagged)
+    pub fn untagged_removes(&self) -> impl Iterator<Item = &UniqueTag> {
+        let tagged: HashSet<&UniqueTag> = self.removals.values().flatten().collect();
+        self.removed_tags
+            .iter()
+            .filter(move |tag| !tagged.contains(tag))
+    }
+
+    /// Apply an add made elsewhere
+    pub fn apply_add(&mut self, element: T, tag: UniqueTag) {
+        self.observe(&tag);
+        self.elements.entry(element).or_default().insert(tag);
+    }
+
+    /// Apply a remove made elsewhere
+    ///
+    /// `remove` is the tag of the remove, if it has one.
+    pub fn apply_remove(&mut self, remove: Option<UniqueTag>, tags: HashSet<UniqueTag>) {
+        self.removed_tags.extend(tags.iter().cloned());
+        if let Some(remove) = remove {
+            self.observe(&remove);
+            self.removals.entry(remove).or_default().extend(tags);
+        }
+    }
+
+    fn record_remove(&mut self, tags: HashSet<UniqueTag>) {
+        if tags.is_empty() {
+            return;
+        }
+        let remove = self.next_tag();
+        self.removed_tags.extend(tags.iter().cloned());
+        self.removals.insert(remove, tags);
+    }
+
+    fn next_tag(&mut self) -> UniqueTag {
+        #[cfg(not(target_arch = "wasm32"))]
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64;
+
+        #[cfg(target_arch = "wasm32")]
+        let timestamp = (Date::now() * 1000.0) as u64; // Convert ms to microseconds
+
+        self.sequence += 1;
+        UniqueTag::new(self.replica_id.clone(), timestamp, self.sequence)
+    }
+
+    /// Keep our own sequence ahead of our operations received back
+    fn observe(&mut self, tag: &UniqueTag) {
+        if tag.replica_id == self.replica_id {
+            self.sequence = self.sequence.max(tag.sequence);
+        }
+    }
+}
+
+/// (De)serialize a map keyed by [`UniqueTag`] with the keys as strings
+mod tag_keys {
+    use super::UniqueTag;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    type Removals = HashMap<UniqueTag, HashSet<UniqueTag>>;
+
+    pub fn serialize<S: Serializer>(removals: &Removals, serializer: S) -> Result<S::Ok, S::Error> {
+        let keyed: BTreeMap<String, &HashSet<UniqueTag>> = removals
+            .iter()
+            .map(|(remove, tags)| (remove.to_string(), tags))
+            .collect();
+        keyed.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Removals, D::Error> {
+        HashMap::<String, HashSet<UniqueTag>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(remove, tags)| Ok((remove.parse().map_err(D::Error::custom)?, tags)))
+            .collect()
+    }
+}
+
+/// The version is [`ORSet::version`]; the delta is a set holding only the
+/// adds and removes a peer has not seen.
+impl<T> Crdt for ORSet<T>
+where
+    T: Clone + Eq + std::hash::Hash + Serialize + DeserializeOwned,
+{
+    type Version = VectorClock;
+    type Delta = ORSet<T>;
+
+    fn new_replica(replica_id: ClientID) -> Self {
+        ORSet::new(replica_id)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        ORSet::merge(self, other);
+    }
+
+    fn version(&self) -> VectorClock {
+        ORSet::version(self)
+    }
+
+    fn delta_since(&self, since: &VectorClock) -> ORSet<T> {
+        ORSet::delta_since(self, since)
+    }
+
+    fn apply_delta(&mut self, delta: &ORSet<T>) -> crate::error::Result<()> {
+        ORSet::merge(self, delta);
+        Ok(())
     }
 }
 
@@ -352,4 +568,37 @@ mod tests {
 
         assert_eq!(items, vec!["apple", "banana", "cherry"]);
     }
+
+    #[test]
+    fn test_json_roundtrip_after_remove() {
+        let mut set = ORSet::new("replica@1".to_string());
+        set.add("apple".to_string());
+        set.add("banana".to_string());
+        set.remove(&"apple".to_string());
+
+        let bytes = set.to_bytes().unwrap();
+        let restored = ORSet::<String>::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, set);
+        assert!(!restored.contains(&"apple".to_string()));
+        assert_eq!(restored.removes().count(), 1);
+    }
+
+    #[test]
+    fn test_restored_replica_continues_its_sequence() {
+        let mut set = ORSet::new("replica1".to_string());
+        set.add("apple".to_string());
+        set.remove(&"apple".to_string());
+        let mut peer = ORSet::new("replica2".to_string());
+        peer.merge(&set);
+
+        // replica1 lost its state and restores it from the peer
+        let mut restored = ORSet::new("replica1".to_string());
+        restored.merge(&peer);
+        restored.add("banana".to_string());
+
+        let delta = restored.delta_since(&peer.version());
+        assert!(delta.contains(&"banana".to_string()));
+        peer.merge(&delta);
+        assert!(peer.contains(&"banana".to_string()));
+    }
 }
@@ -27,6 +27,9 @@
 //! assert_eq!(counter1.value(), 8);
 //! ```
 
+use super::traits::Crdt;
+use crate::error::Result;
+use crate::sync::VectorClock;
 use crate::ClientID;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -130,6 +133,35 @@ impl PNCounter {
         &self.replica_id
     }
 
+    /// Rebuild a counter from its per-replica increments and decrements
+    ///
+    /// `replica_id` is the replica the rebuilt counter will write as; it
+    /// keeps any counts the maps already hold for it.
+    pub fn from_parts(
+        replica_id: ClientID,
+        mut positive: HashMap<ClientID, i64>,
+        mut negative: HashMap<ClientID, i64>,
+    ) -> Self {
+        positive.entry(replica_id.clone()).or_insert(0);
+        negative.entry(replica_id.clone()).or_insert(0);
+
+        Self {
+            replica_id,
+            positive,
+            negative,
+        }
+    }
+
+    /// Total increments per replica
+    pub fn positive(&self) -> &HashMap<ClientID, i64> {
+        &self.positive
+    }
+
+    /// Total decrements per replica
+    pub fn negative(&self) -> &HashMap<ClientID, i64> {
+        &self.negative
+    }
+
     /// Reset the counter to zero
     ///
     /// Note: This is a local operation and won't affect other replicas.
@@ -140,6 +172,55 @@ impl PNCounter {
         self.positive.insert(self.replica_id.clone(), 0);
         self.negative.insert(self.replica_id.clone(), 0);
     }
+
+    /// Increments plus decrements of a replica
+    fn total(&self, replica: &ClientID) -> u64 {
+        let count = |counts: &HashMap<ClientID, i64>| counts.get(replica).copied().unwrap_or(0);
+        (count(&self.positive) + count(&self.negative)) as u64
+    }
+}
+
+/// The version holds each replica's increments plus decrements, which only
+/// grow; the delta is a counter with the replicas that grew since.
+impl Crdt for PNCounter {
+    type Version = VectorClock;
+    type Delta = PNCounter;
+
+    fn new_replica(replica_id: ClientID) -> Self {
+        PNCounter::new(replica_id)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        PNCounter::merge(self, other);
+    }
+
+    fn version(&self) -> VectorClock {
+        let mut version = VectorClock::new();
+        for replica in self.positive.keys().chain(self.negative.keys()) {
+            version.update(replica, self.total(replica));
+        }
+        version
+    }
+
+    fn delta_since(&self, since: &VectorClock) -> PNCounter {
+        let unseen = |counts: &HashMap<ClientID, i64>| {
+            counts
+                .iter()
+                .filter(|(replica, _)| self.total(replica) > since.get(replica))
+                .map(|(replica, &count)| (replica.clone(), count))
+                .collect()
+        };
+        PNCounter::from_parts(
+            self.replica_id.clone(),
+            unseen(&self.positive),
+            unseen(&self.negative),
+        )
+    }
+
+    fn apply_delta(&mut self, delta: &PNCounter) -> Result<()> {
+        PNCounter::merge(self, delta);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -23,7 +23,7 @@ use unicode_segmentation::UnicodeSegmentation;
 /// - **id**: Unique identifier for this block
 /// - **left_origin/right_origin**: Fugue's two-phase conflict resolution
 /// - **deleted**: Tombstone flag (blocks are never removed, only marked deleted)
-/// - **rope_start**: Cached position in rope (invalidated on edits)
+/// - **collected**: Length kept after garbage collection drops a tombstone's text
 ///
 /// # Memory Layout
 ///
@@ -31,7 +31,7 @@ use unicode_segmentation::UnicodeSegmentation;
 /// - NodeId: ~24 bytes
 /// - String: ~30 bytes (10 chars + overhead)
 /// - Origins: ~48 bytes (2 × Option<NodeId>)
-/// - Flags + cache: ~9 bytes
+/// - Flags + collected length: ~9 bytes
 /// - **Total: ~135 bytes/block = ~13.5 bytes/char**
 ///
 /// Without RLE (1 char/block):
@@ -84,25 +84,12 @@ pub struct FugueBlock {
     /// replicas couldn't properly merge concurrent operations.
     pub deleted: bool,
 
-    /// Cached rope position (private, invalidated on any edit)
+    /// Number of graphemes whose text was dropped by garbage collection
     ///
-    /// This cache helps avoid recomputing rope position on every access.
-    /// Set to usize::MAX when invalid.
-    #[serde(skip)]
-    rope_start: usize,
-
-    /// Cached grapheme start position (Phase 1.5 optimization)
-    ///
-    /// This stores the cumulative grapheme position where this block starts
-    /// in the document. Enables O(log n) binary search in find_origins().
-    /// Set to usize::MAX when invalid (cache invalidated on any edit).
-    ///
-    /// **Performance Impact:**
-    /// - Without cache: O(n) linear scan → O(n²) for n sequential ops
-    /// - With cache: O(log n) binary search → O(n log n) for n sequential ops
-    /// - Expected: 260K ops from ~40 min → <500ms (4,800x faster!)
-    #[serde(skip)]
-    cached_start_pos: usize,
+    /// A collected tombstone keeps its clock range, so IDs inside it still
+    /// resolve, but not its characters. Zero for every other block.
+    #[serde(skip_serializing_if = "is_zero")]
+    collected: usize,
 }
 
 impl Default for FugueBlock {
@@ -113,8 +100,7 @@ impl Default for FugueBlock {
             left_origin: None,
             right_origin: None,
             deleted: false,
-            rope_start: usize::MAX,       // Invalid until computed
-            cached_start_pos: usize::MAX, // Invalid until computed
+            collected: 0,
         }
     }
 }
@@ -149,8 +135,7 @@ impl FugueBlock {
             left_origin,
             right_origin,
             deleted: false,
-            rope_start: usize::MAX,       // Invalid until computed
-            cached_start_pos: usize::MAX, // Invalid until computed
+            collected: 0,
         }
     }
 
@@ -170,6 +155,60 @@ impl FugueBlock {
         self.deleted = true;
     }
 
+    /// Check if garbage collection dropped this tombstone's text
+    pub fn is_collected(&self) -> bool {
+        self.collected > 0
+    }
+
+    /// Drop a tombstone's text, keeping only its length
+    ///
+    /// Returns the number of bytes freed. Visible blocks are left alone.
+    pub(crate) fn collect(&mut self) -> usize {
+        if !self.deleted || self.text.is_empty() {
+            return 0;
+        }
+
+        self.collected = self.len();
+        std::mem::take(&mut self.text).capacity()
+    }
+
+    /// Absorb a collected tombstone that directly precedes this one
+    pub(crate) fn absorb_collected(&mut self, before: &FugueBlock) {
+        debug_assert!(self.is_collected() && before.is_collected());
+        self.collected += before.collected;
+    }
+
+    /// Copy the graphemes `from..to` into a block of their own
+    ///
+    /// The piece keeps the tombstone flag, and a collected block yields a
+    /// collected piece.
+    #[cfg(feature = "text-crdt")]
+    pub(crate) fn piece(
+        &self,
+        id: NodeId,
+        from: usize,
+        to: usize,
+        left_origin: Option<NodeId>,
+        right_origin: Option<NodeId>,
+    ) -> FugueBlock {
+        let mut piece = if self.is_collected() {
+            let mut piece = FugueBlock::new(id, String::new(), left_origin, right_origin);
+            piece.collected = to - from;
+            piece
+        } else {
+            let start = grapheme_byte_offset(&self.text, from);
+            let end = grapheme_byte_offset(&self.text, to);
+            FugueBlock::new(
+                id,
+                self.text[start..end].to_string(),
+                left_origin,
+                right_origin,
+            )
+        };
+        piece.deleted = self.deleted;
+        piece
+    }
+
     /// Get the number of grapheme clusters in this block
     ///
     /// Uses Unicode segmentation to count user-perceived characters, not
@@ -187,13 +226,13 @@ impl FugueBlock {
     /// ```
     #[cfg(feature = "text-crdt")]
     pub fn len(&self) -> usize {
-        self.text.graphemes(true).count()
+        self.collected + self.text.graphemes(true).count()
     }
 
     /// Get the number of grapheme clusters (fallback without unicode-segmentation)
     #[cfg(not(feature = "text-crdt"))]
     pub fn len(&self) -> usize {
-        self.text.chars().count()
+        self.collected + self.text.chars().count()
     }
 
     /// Get the number of UTF-8 bytes in this block
@@ -216,58 +255,20 @@ impl FugueBlock {
 
     /// Check if this block is empty
     pub fn is_empty(&self) -> bool {
-        self.text.is_empty()
-    }
-
-    /// Get the cached rope position (private, for internal use)
-    ///
-    /// Returns None if cache is invalid (usize::MAX).
-    #[inline]
-    pub(crate) fn rope_position(&self) -> Option<usize> {
-        if self.rope_start == usize::MAX {
-            None
-        } else {
-            Some(self.rope_start)
-        }
-    }
-
-    /// Set the cached rope position (private, for internal use)
-    #[inline]
-    #[allow(dead_code)]
-    pub(crate) fn set_rope_position(&mut self, pos: usize) {
-        self.rope_start = pos;
-    }
-
-    /// Invalidate the cached rope position (private, for internal use)
-    #[inline]
-    pub(crate) fn invalidate_rope_position(&mut self) {
-        self.rope_start = usize::MAX;
-    }
-
-    /// Get the cached grapheme start position (Phase 1.5 optimization)
-    ///
-    /// Returns None if cache is invalid (usize::MAX).
-    #[inline]
-    pub(crate) fn cached_position(&self) -> Option<usize> {
-        if self.cached_start_pos == usize::MAX {
-            None
-        } else {
-            Some(self.cached_start_pos)
-        }
+        self.text.is_empty() && self.collected == 0
     }
+}
 
-    /// Set the cached grapheme start position (Phase 1.5 optimization)
-    #[inline]
-    pub(crate) fn set_cached_position(&mut self, pos: usize) {
-        self.cached_start_pos = pos;
-    }
+/// Byte offset of the `n`th grapheme in `text` (its length if past the end)
+#[cfg(feature = "text-crdt")]
+pub(crate) fn grapheme_byte_offset(text: &str, n: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(n)
+        .map_or(text.len(), |(offset, _)| offset)
+}
 
-    /// Invalidate the cached grapheme start position (Phase 1.5 optimization)
-    #[inline]
-    #[allow(dead_code)]
-    pub(crate) fn invalidate_cached_position(&mut self) {
-        self.cached_start_pos = usize::MAX;
-    }
+fn is_zero(n: &usize) -> bool {
+    *n == 0
 }
 
 #[cfg(test)]
@@ -345,30 +346,39 @@ mod tests {
     }
 
     #[test]
-    fn test_rope_position_cache() {
-        let id = NodeId::new("client1".to_string(), 1, 0);
-        let mut block = FugueBlock::new(id, "test".to_string(), None, None);
+    #[cfg(feature = "text-crdt")]
+    fn test_collect_keeps_length() {
+        let id = NodeId::new("client1".to_string(), 5, 0);
+        let mut block = FugueBlock::new(id.clone(), "Hé👋lo".to_string(), None, None);
+
+        // Only tombstones can be collected
+        assert_eq!(block.collect(), 0);
 
-        // Initially invalid
-        assert_eq!(block.rope_position(), None);
+        block.mark_deleted();
+        assert!(block.collect() >= 8);
+        assert!(block.is_collected());
+        assert_eq!(block.text, "");
+        assert_eq!(block.len(), 5);
+        assert!(!block.is_empty());
 
-        // Set position
-        block.set_rope_position(42);
-        assert_eq!(block.rope_position(), Some(42));
+        let piece = block.piece(id, 1, 3, None, None);
+        assert!(piece.is_collected() && piece.is_deleted());
+        assert_eq!(piece.len(), 2);
 
-        // Invalidate
-        block.invalidate_rope_position();
-        assert_eq!(block.rope_position(), None);
+        // Collected blocks serialize their length, others don't mention it
+        let json = serde_json::to_string(&block).unwrap();
+        assert_eq!(serde_json::from_str::<FugueBlock>(&json).unwrap().len(), 5);
+        let visible = FugueBlock::new(NodeId::new("c".to_string(), 1, 0), "a".into(), None, None);
+        assert!(!serde_json::to_string(&visible)
+            .unwrap()
+            .contains("collected"));
     }
 
     #[test]
     fn test_serialization() {
         let id = NodeId::new("client1".to_string(), 1, 0);
         let left = Some(NodeId::new("client1".to_string(), 0, 0));
-        let mut block = FugueBlock::new(id, "test".to_string(), left, None);
-
-        // Set rope position before serialization
-        block.set_rope_position(42);
+        let block = FugueBlock::new(id, "test".to_string(), left, None);
 
         let json = serde_json::to_string(&block).unwrap();
         let deserialized: FugueBlock = serde_json::from_str(&json).unwrap();
@@ -378,8 +388,6 @@ mod tests {
         assert_eq!(block.left_origin, deserialized.left_origin);
         assert_eq!(block.right_origin, deserialized.right_origin);
         assert_eq!(block.deleted, deserialized.deleted);
-
-        // rope_start is skipped in serialization, so should be None after deserialize
-        assert_eq!(deserialized.rope_position(), None);
+        assert_eq!(block, deserialized);
     }
 }
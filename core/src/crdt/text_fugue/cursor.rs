@@ -0,0 +1,166 @@
+//! Cursor: Stable positions in a FugueText
+//!
+//! A grapheme index goes stale as soon as anyone edits text before it. A
+//! `Cursor` instead names the character next to the gap, plus which side of
+//! the gap it sits on, so it keeps pointing at the same spot while replicas
+//! insert and delete around it. Cursors and selections in awareness payloads
+//! should be sent as cursors, not indices.
+
+use super::node::NodeId;
+use super::text::TextError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// Which character a cursor sticks to
+///
+/// Decides where the cursor ends up when text is inserted right at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Assoc {
+    /// Stick to the character before the gap; text inserted at the cursor
+    /// lands after it
+    Before,
+
+    /// Stick to the character after the gap; text inserted at the cursor
+    /// lands before it
+    #[default]
+    After,
+}
+
+/// A position in the text that survives concurrent edits
+///
+/// A cursor holds the NodeId of the character it sticks to, or nothing if
+/// it sits at the start (`Assoc::Before`) or end (`Assoc::After`) of the
+/// document. If that character is deleted, the cursor resolves to the gap
+/// it was deleted from, between its nearest visible neighbours.
+///
+/// Cursors serialize to a short string: `<` or `>` for the association,
+/// followed by the character's `client@clock:offset` ID, if any.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::{Assoc, FugueText};
+///
+/// let mut text = FugueText::new("client1".to_string());
+/// text.insert(0, "Hello world").unwrap();
+///
+/// let cursor = text.cursor_at(6, Assoc::After).unwrap();
+/// text.insert(0, ">> ").unwrap();
+/// assert_eq!(text.resolve_cursor(&cursor), Some(9));
+///
+/// // Deleting the character keeps the cursor where it was
+/// text.delete(9, 1).unwrap();
+/// assert_eq!(text.resolve_cursor(&cursor), Some(9));
+///
+/// assert_eq!(cursor.to_string(), ">client1@7:0");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    /// Character the cursor sticks to; None at the document edge
+    pub id: Option<NodeId>,
+
+    /// Side of the gap the character is on
+    pub assoc: Assoc,
+}
+
+impl Cursor {
+    /// Cursor at the start of the document
+    pub fn start() -> Self {
+        Self {
+            id: None,
+            assoc: Assoc::Before,
+        }
+    }
+
+    /// Cursor at the end of the document
+    pub fn end() -> Self {
+        Self {
+            id: None,
+            assoc: Assoc::After,
+        }
+    }
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = match self.assoc {
+            Assoc::Before => '<',
+            Assoc::After => '>',
+        };
+
+        match &self.id {
+            Some(id) => write!(f, "{}{}", side, id),
+            None => write!(f, "{}", side),
+        }
+    }
+}
+
+/// Parse the compact form produced by `Display`
+impl FromStr for Cursor {
+    type Err = TextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let assoc = match s.chars().next() {
+            Some('<') => Assoc::Before,
+            Some('>') => Assoc::After,
+            _ => return Err(TextError::InvalidCursor(s.to_string())),
+        };
+
+        let id = match &s[1..] {
+            "" => None,
+            id => Some(
+                id.parse()
+                    .map_err(|_| TextError::InvalidCursor(s.to_string()))?,
+            ),
+        };
+
+        Ok(Self { id, assoc })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let cursors = [
+            Cursor::start(),
+            Cursor::end(),
+            Cursor {
+                id: Some(NodeId::new("user@example.com".to_string(), 42, 0)),
+                assoc: Assoc::Before,
+            },
+        ];
+
+        for cursor in cursors {
+            assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+
+            let json = serde_json::to_string(&cursor).unwrap();
+            assert_eq!(serde_json::from_str::<Cursor>(&json).unwrap(), cursor);
+        }
+
+        assert_eq!(Cursor::start().to_string(), "<");
+        assert!("".parse::<Cursor>().is_err());
+        assert!("<client1".parse::<Cursor>().is_err());
+    }
+}
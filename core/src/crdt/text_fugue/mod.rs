@@ -9,6 +9,14 @@
 //! - **Rope-based storage**: O(log n) insertions/deletions via ropey crate
 //! - **Grapheme-aware**: Proper Unicode handling (no mid-emoji splits)
 //! - **Deterministic**: Concurrent operations always converge to same state
+//! - **Operation-based sync**: Edits return `FugueOp`s that other replicas
+//!   apply one at a time, buffering any that arrive before their origins
+//! - **State vectors**: Peers exchange only the operations the other lacks
+//! - **Tombstone GC**: Deletions every replica has seen drop their text
+//! - **Stable cursors**: Positions that follow their character through
+//!   concurrent edits and deletion
+//! - **Rich text**: Peritext formatting marks anchored to characters
+//!   (`feature = "rich-text"`)
 //!
 //! # Algorithm
 //!
@@ -23,7 +31,9 @@
 //!
 //! The implementation uses a hybrid architecture:
 //! - **Rope**: Efficient text storage (ropey crate)
-//! - **BTreeMap**: CRDT metadata maintaining Fugue ordering
+//! - **BTreeMap**: CRDT metadata, keyed by each block's last character
+//! - **Order-statistic tree**: Blocks in Fugue document order, mapping
+//!   positions to blocks and back in O(log n)
 //! - **RLE**: Consecutive chars from same operation stored in single block
 //!
 //! # Example
@@ -52,9 +62,18 @@
 //! - **Loro CRDT**: Production implementation using Fugue
 
 mod block;
+mod cursor;
 mod node;
+mod op;
+mod order;
+#[cfg(feature = "rich-text")]
+mod rich_text;
 mod text;
 
 pub use block::FugueBlock;
+pub use cursor::{Assoc, Cursor};
 pub use node::NodeId;
-pub use text::{FugueText, LamportClock, TextError};
+pub use op::{FugueOp, NodeIdRange};
+#[cfg(feature = "rich-text")]
+pub use rich_text::{Anchor, Expand, FormatMark, RichText, RichTextOp, TextSpan};
+pub use text::{FugueText, GcStats, LamportClock, StateVector, TextError};
@@ -4,8 +4,10 @@
 //! The Ord implementation defines how blocks are ordered in the BTreeMap,
 //! which maintains the Fugue CRDT structure.
 
+use super::text::TextError;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::str::FromStr;
 
 /// Unique identifier for a Fugue block
 ///
@@ -120,6 +122,36 @@ impl std::fmt::Display for NodeId {
     }
 }
 
+/// Parse the `client@clock:offset` form produced by `Display`
+///
+/// Client IDs may themselves contain `@`, so the clock is taken from after
+/// the last one.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::NodeId;
+///
+/// let id: NodeId = "user@example.com@42:0".parse().unwrap();
+/// assert_eq!(id, NodeId::new("user@example.com".to_string(), 42, 0));
+/// ```
+impl FromStr for NodeId {
+    type Err = TextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TextError::InvalidNodeId(s.to_string());
+
+        let (client_id, rest) = s.rsplit_once('@').ok_or_else(invalid)?;
+        let (clock, offset) = rest.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self::new(
+            client_id.to_string(),
+            clock.parse().map_err(|_| invalid())?,
+            offset.parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,6 +214,16 @@ mod tests {
         assert_eq!(format!("{}", id), "client1@42:5");
     }
 
+    #[test]
+    fn test_parse_roundtrip() {
+        let id = NodeId::new("client1".to_string(), 42, 5);
+        assert_eq!(id.to_string().parse::<NodeId>().unwrap(), id);
+
+        assert!("client1".parse::<NodeId>().is_err());
+        assert!("client1@42".parse::<NodeId>().is_err());
+        assert!("client1@x:0".parse::<NodeId>().is_err());
+    }
+
     #[test]
     fn test_serialization() {
         let id = NodeId::new("client1".to_string(), 42, 5);
@@ -0,0 +1,192 @@
+//! FugueOp: Serializable text operations
+//!
+//! Local edits on a `FugueText` produce operations that can be shipped to
+//! other replicas and integrated one at a time with `FugueText::apply_op`,
+//! instead of exchanging the whole document on every keystroke.
+
+use super::block::FugueBlock;
+use super::node::NodeId;
+use super::text::TextError;
+use serde::{Deserialize, Serialize};
+
+/// A run of consecutive characters inserted by one client
+///
+/// With per-character clocks, the characters of a block are identified by
+/// `client_id` plus a clock value, so a range of clocks `start..=end`
+/// names a contiguous run of characters without listing each of them.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::{NodeId, NodeIdRange};
+///
+/// let range = NodeIdRange::new("client1".to_string(), 3, 5);
+///
+/// assert_eq!(range.len(), 3);
+/// assert!(range.contains(&NodeId::new("client1".to_string(), 4, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NodeIdRange {
+    /// Client that inserted the characters
+    pub client_id: String,
+
+    /// Clock of the first character (inclusive)
+    pub start: u64,
+
+    /// Clock of the last character (inclusive)
+    pub end: u64,
+}
+
+impl NodeIdRange {
+    /// Create a range covering clocks `start..=end` of `client_id`
+    pub fn new(client_id: String, start: u64, end: u64) -> Self {
+        Self {
+            client_id,
+            start,
+            end,
+        }
+    }
+
+    /// Number of characters in the range
+    pub fn len(&self) -> u64 {
+        (self.end + 1).saturating_sub(self.start)
+    }
+
+    /// Check whether the range covers no characters
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Check whether the range covers the given character
+    pub fn contains(&self, id: &NodeId) -> bool {
+        id.client_id == self.client_id && (self.start..=self.end).contains(&id.clock)
+    }
+
+    /// NodeId of the first character in the range
+    pub fn first(&self) -> NodeId {
+        NodeId::new(self.client_id.clone(), self.start, 0)
+    }
+}
+
+/// An operation produced by a local edit on `FugueText`
+///
+/// - `Insert` carries the new block with its origins, which is everything a
+///   remote replica needs to place it.
+/// - `Delete` carries the ranges of characters that were tombstoned, plus
+///   an ID of its own so replicas can tell which deletions a peer has seen.
+///
+/// Operations are idempotent and commute once their dependencies are met,
+/// so replicas can apply them in any causal order and more than once.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::FugueText;
+///
+/// let mut alice = FugueText::new("alice".to_string());
+/// let mut bob = FugueText::new("bob".to_string());
+///
+/// let op = alice.insert(0, "Hello").unwrap();
+/// bob.apply_op(&op).unwrap();
+///
+/// assert_eq!(bob.to_string(), "Hello");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FugueOp {
+    /// A block of newly inserted text
+    Insert(FugueBlock),
+
+    /// Characters that were deleted
+    Delete {
+        /// Deleting client and the clock value it allocated for the delete
+        id: NodeId,
+
+        /// Deleted characters
+        ranges: Vec<NodeIdRange>,
+    },
+}
+
+impl FugueOp {
+    /// Check that the operation is well-formed
+    ///
+    /// An insert must not allocate clocks below 1 and a delete range must
+    /// not end before it starts. This says nothing about whether the
+    /// operation's dependencies have been seen.
+    pub fn validate(&self) -> Result<(), TextError> {
+        match self {
+            FugueOp::Insert(block) => {
+                if block.len() as u64 > block.id.clock || block.id.offset != 0 {
+                    return Err(TextError::InvalidOperation(format!(
+                        "block {} cannot hold {} characters",
+                        block.id,
+                        block.len()
+                    )));
+                }
+            }
+            FugueOp::Delete { ranges, .. } => {
+                if let Some(range) = ranges.iter().find(|r| r.start == 0 || r.end < r.start) {
+                    return Err(TextError::InvalidOperation(format!(
+                        "invalid delete range {}@{}..={}",
+                        range.client_id, range.start, range.end
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether applying the operation would change nothing
+    pub fn is_empty(&self) -> bool {
+        match self {
+            FugueOp::Insert(block) => block.is_empty(),
+            FugueOp::Delete { ranges, .. } => ranges.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_contains() {
+        let range = NodeIdRange::new("client1".to_string(), 3, 5);
+
+        assert_eq!(range.len(), 3);
+        assert_eq!(range.first(), NodeId::new("client1".to_string(), 3, 0));
+        assert!(range.contains(&NodeId::new("client1".to_string(), 3, 0)));
+        assert!(range.contains(&NodeId::new("client1".to_string(), 5, 0)));
+        assert!(!range.contains(&NodeId::new("client1".to_string(), 6, 0)));
+        assert!(!range.contains(&NodeId::new("client2".to_string(), 4, 0)));
+    }
+
+    #[test]
+    fn test_validate() {
+        let id = NodeId::new("client1".to_string(), 2, 0);
+        let block = FugueBlock::new(id, "abc".to_string(), None, None);
+        assert!(FugueOp::Insert(block).validate().is_err());
+
+        let id = NodeId::new("client1".to_string(), 3, 0);
+        let block = FugueBlock::new(id, "abc".to_string(), None, None);
+        assert!(FugueOp::Insert(block).validate().is_ok());
+
+        let op = FugueOp::Delete {
+            id: NodeId::new("client2".to_string(), 7, 0),
+            ranges: vec![NodeIdRange::new("client1".to_string(), 5, 4)],
+        };
+        assert!(op.validate().is_err());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let op = FugueOp::Delete {
+            id: NodeId::new("client2".to_string(), 7, 0),
+            ranges: vec![NodeIdRange::new("client1".to_string(), 1, 4)],
+        };
+
+        let json = serde_json::to_string(&op).unwrap();
+        let deserialized: FugueOp = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(op, deserialized);
+    }
+}
@@ -0,0 +1,365 @@
+//! BlockOrder: Document order of Fugue blocks
+//!
+//! Blocks are kept in an order-statistic tree (a treap keyed by position)
+//! whose nodes also sum the visible graphemes and rope chars below them.
+//! That turns every position lookup `FugueText` needs into a walk down or
+//! up the tree, so integrating a block costs O(log n) instead of a scan over
+//! the whole document.
+
+use super::node::NodeId;
+use std::collections::HashMap;
+
+/// Marker for a missing child or parent
+const NIL: usize = usize::MAX;
+
+/// A block in the tree
+#[derive(Debug, Clone)]
+struct Node {
+    id: NodeId,
+    priority: u64,
+    left: usize,
+    right: usize,
+    parent: usize,
+
+    /// Visible graphemes of this block (0 once deleted)
+    graphemes: usize,
+
+    /// Visible rope chars of this block (0 once deleted)
+    chars: usize,
+
+    /// Number of blocks in the subtree
+    subtree_count: usize,
+
+    /// Visible graphemes in the subtree
+    subtree_graphemes: usize,
+
+    /// Visible rope chars in the subtree
+    subtree_chars: usize,
+}
+
+/// Block IDs in document order, tombstones included
+///
+/// Besides indexing by position, the tree maps a block ID back to its index
+/// and to the visible text before it, which is what the rope needs.
+///
+/// Priorities come from a fixed-seed generator, so two replicas holding the
+/// same blocks may still have differently shaped trees; only the in-order
+/// sequence is meaningful.
+#[derive(Debug, Clone)]
+pub(super) struct BlockOrder {
+    nodes: Vec<Node>,
+    slots: HashMap<NodeId, usize>,
+    root: usize,
+    seed: u64,
+}
+
+impl BlockOrder {
+    /// Create an empty order
+    pub(super) fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            slots: HashMap::new(),
+            root: NIL,
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Number of blocks, tombstones included
+    pub(super) fn len(&self) -> usize {
+        self.count(self.root)
+    }
+
+    /// Insert a block so that it ends up at `index`
+    ///
+    /// `graphemes` and `chars` are the block's visible length (0 for a
+    /// tombstone).
+    pub(super) fn insert(&mut self, index: usize, id: NodeId, graphemes: usize, chars: usize) {
+        debug_assert!(index <= self.len(), "index {} out of bounds", index);
+        debug_assert!(!self.slots.contains_key(&id), "block {} already placed", id);
+
+        let slot = self.nodes.len();
+        let priority = self.next_priority();
+        self.nodes.push(Node {
+            id: id.clone(),
+            priority,
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            graphemes,
+            chars,
+            subtree_count: 1,
+            subtree_graphemes: graphemes,
+            subtree_chars: chars,
+        });
+        self.slots.insert(id, slot);
+
+        let (before, after) = self.split(self.root, index);
+        let left = self.join(before, slot);
+        self.root = self.join(left, after);
+        self.nodes[self.root].parent = NIL;
+    }
+
+    /// Block ID at `index`
+    pub(super) fn get(&self, index: usize) -> Option<&NodeId> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut node = self.root;
+        let mut index = index;
+        loop {
+            let left = self.nodes[node].left;
+            let left_count = self.count(left);
+            match index.cmp(&left_count) {
+                std::cmp::Ordering::Less => node = left,
+                std::cmp::Ordering::Equal => return Some(&self.nodes[node].id),
+                std::cmp::Ordering::Greater => {
+                    index -= left_count + 1;
+                    node = self.nodes[node].right;
+                }
+            }
+        }
+    }
+
+    /// Index of a block
+    pub(super) fn index_of(&self, id: &NodeId) -> Option<usize> {
+        let slot = *self.slots.get(id)?;
+        Some(self.prefix(slot, |node| node.subtree_count, |_| 1))
+    }
+
+    /// Visible graphemes and rope chars before a block
+    pub(super) fn offset_of(&self, id: &NodeId) -> Option<(usize, usize)> {
+        let slot = *self.slots.get(id)?;
+        Some((
+            self.prefix(slot, |node| node.subtree_graphemes, |node| node.graphemes),
+            self.prefix(slot, |node| node.subtree_chars, |node| node.chars),
+        ))
+    }
+
+    /// Find the visible grapheme at `position`
+    ///
+    /// Returns the block's index and the grapheme offset within the block.
+    pub(super) fn locate(&self, position: usize) -> Option<(usize, usize)> {
+        if position >= self.graphemes(self.root) {
+            return None;
+        }
+
+        let mut node = self.root;
+        let mut position = position;
+        let mut index = 0;
+        loop {
+            let current = &self.nodes[node];
+            let left_graphemes = self.graphemes(current.left);
+
+            if position < left_graphemes {
+                node = current.left;
+            } else if position < left_graphemes + current.graphemes {
+                return Some((index + self.count(current.left), position - left_graphemes));
+            } else {
+                position -= left_graphemes + current.graphemes;
+                index += self.count(current.left) + 1;
+                node = current.right;
+            }
+        }
+    }
+
+    /// Change the visible length of a block
+    pub(super) fn set_visible(&mut self, id: &NodeId, graphemes: usize, chars: usize) {
+        let slot = self.slots[id];
+        self.nodes[slot].graphemes = graphemes;
+        self.nodes[slot].chars = chars;
+
+        let mut node = slot;
+        while node != NIL {
+            self.update(node);
+            node = self.nodes[node].parent;
+        }
+    }
+
+    /// Sum `own` over every block before `slot`, using `subtree` for whole
+    /// subtrees to the left of the path to the root
+    fn prefix(&self, slot: usize, subtree: fn(&Node) -> usize, own: fn(&Node) -> usize) -> usize {
+        let sum_of = |node: usize| {
+            if node == NIL {
+                0
+            } else {
+                subtree(&self.nodes[node])
+            }
+        };
+
+        let mut total = sum_of(self.nodes[slot].left);
+        let mut node = slot;
+        let mut parent = self.nodes[node].parent;
+        while parent != NIL {
+            if self.nodes[parent].right == node {
+                total += sum_of(self.nodes[parent].left) + own(&self.nodes[parent]);
+            }
+            node = parent;
+            parent = self.nodes[node].parent;
+        }
+        total
+    }
+
+    /// Split a subtree into its first `count` blocks and the rest
+    fn split(&mut self, node: usize, count: usize) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+
+        let left = self.nodes[node].left;
+        let left_count = self.count(left);
+        if count <= left_count {
+            let (before, after) = self.split(left, count);
+            self.set_left(node, after);
+            self.update(node);
+            self.detach(before);
+            self.detach(node);
+            (before, node)
+        } else {
+            let right = self.nodes[node].right;
+            let (before, after) = self.split(right, count - left_count - 1);
+            self.set_right(node, before);
+            self.update(node);
+            self.detach(node);
+            self.detach(after);
+            (node, after)
+        }
+    }
+
+    /// Concatenate two subtrees, `left` first
+    fn join(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let child = self.nodes[left].right;
+            let joined = self.join(child, right);
+            self.set_right(left, joined);
+            self.update(left);
+            left
+        } else {
+            let child = self.nodes[right].left;
+            let joined = self.join(left, child);
+            self.set_left(right, joined);
+            self.update(right);
+            right
+        }
+    }
+
+    fn set_left(&mut self, node: usize, child: usize) {
+        self.nodes[node].left = child;
+        if child != NIL {
+            self.nodes[child].parent = node;
+        }
+    }
+
+    fn set_right(&mut self, node: usize, child: usize) {
+        self.nodes[node].right = child;
+        if child != NIL {
+            self.nodes[child].parent = node;
+        }
+    }
+
+    fn detach(&mut self, node: usize) {
+        if node != NIL {
+            self.nodes[node].parent = NIL;
+        }
+    }
+
+    /// Recompute a node's subtree sums from its children
+    fn update(&mut self, node: usize) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        let count = 1 + self.count(left) + self.count(right);
+        let graphemes = self.nodes[node].graphemes + self.graphemes(left) + self.graphemes(right);
+        let chars = self.nodes[node].chars + self.chars(left) + self.chars(right);
+
+        let current = &mut self.nodes[node];
+        current.subtree_count = count;
+        current.subtree_graphemes = graphemes;
+        current.subtree_chars = chars;
+    }
+
+    fn count(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].subtree_count
+        }
+    }
+
+    fn graphemes(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].subtree_graphemes
+        }
+    }
+
+    fn chars(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].subtree_chars
+        }
+    }
+
+    /// Next priority from a xorshift generator
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(clock: u64) -> NodeId {
+        NodeId::new("client1".to_string(), clock, 0)
+    }
+
+    #[test]
+    fn test_insert_and_index() {
+        let mut order = BlockOrder::new();
+        let mut expected: Vec<NodeId> = Vec::new();
+
+        // Insert at scattered positions to exercise splits and joins
+        for clock in 1..=200u64 {
+            let index = (clock as usize * 7) % (expected.len() + 1);
+            order.insert(index, id(clock), 1, 1);
+            expected.insert(index, id(clock));
+        }
+
+        assert_eq!(order.len(), expected.len());
+        for (index, block) in expected.iter().enumerate() {
+            assert_eq!(order.get(index), Some(block));
+            assert_eq!(order.index_of(block), Some(index));
+        }
+        assert_eq!(order.get(expected.len()), None);
+    }
+
+    #[test]
+    fn test_locate_skips_tombstones() {
+        let mut order = BlockOrder::new();
+        order.insert(0, id(3), 3, 3); // "abc"
+        order.insert(1, id(5), 2, 2); // "de"
+        order.insert(2, id(6), 1, 2); // "👋🏽" as one grapheme, two chars
+
+        order.set_visible(&id(5), 0, 0);
+
+        assert_eq!(order.locate(0), Some((0, 0)));
+        assert_eq!(order.locate(2), Some((0, 2)));
+        assert_eq!(order.locate(3), Some((2, 0)));
+        assert_eq!(order.locate(4), None);
+
+        assert_eq!(order.offset_of(&id(3)), Some((0, 0)));
+        assert_eq!(order.offset_of(&id(5)), Some((3, 3)));
+        assert_eq!(order.offset_of(&id(6)), Some((3, 3)));
+    }
+}
@@ -0,0 +1,624 @@
+//! RichText: Peritext formatting on top of FugueText
+//!
+//! Formatting lives beside the text as marks instead of inside it. Each mark
+//! is anchored to the gaps next to the characters at its edges, so it keeps
+//! covering the same characters while replicas insert and delete around it.
+//! Whether text typed at the edge of a mark joins it follows from which gap
+//! the anchor uses (see [`Expand`]).
+//!
+//! Concurrent marks never conflict structurally: every mark is kept, and
+//! for each character and attribute the mark with the highest ID wins. A
+//! mark whose value is `null` removes the attribute.
+//!
+//! # References
+//!
+//! - **Paper**: "Peritext: A CRDT for Collaborative Rich Text Editing"
+//!   (Litt et al., CSCW 2022)
+
+use super::node::NodeId;
+use super::op::FugueOp;
+use super::text::{FugueText, LamportClock, TextError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Where a mark starts or ends
+///
+/// Anchoring to a gap instead of a position keeps marks stable under
+/// concurrent edits. Anchors to deleted characters stay valid and resolve
+/// to the spot the character was deleted from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Anchor {
+    /// Start of the document
+    Start,
+
+    /// The gap just before a character
+    Before(NodeId),
+
+    /// The gap just after a character
+    After(NodeId),
+
+    /// End of the document
+    End,
+}
+
+/// Whether text typed at the edges of a mark joins it
+///
+/// Peritext's expansion rules: bold and italic usually grow when the user
+/// keeps typing after them, links and comments never do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Expand {
+    /// Text typed at either edge stays outside the mark
+    None,
+
+    /// Text typed right before the mark joins it
+    Before,
+
+    /// Text typed right after the mark joins it
+    #[default]
+    After,
+
+    /// Text typed at either edge joins the mark
+    Both,
+}
+
+impl Expand {
+    fn before(self) -> bool {
+        matches!(self, Expand::Before | Expand::Both)
+    }
+
+    fn after(self) -> bool {
+        matches!(self, Expand::After | Expand::Both)
+    }
+}
+
+/// A formatting mark over a range of characters
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::{Expand, RichText};
+/// use serde_json::json;
+///
+/// let mut text = RichText::new("client1".to_string());
+/// text.insert(0, "See docs").unwrap();
+///
+/// // A link carries its attributes as the mark's value
+/// text.format(4, 8, "link", json!({ "href": "https://synckit.dev" }), Expand::None)
+///     .unwrap();
+///
+/// let mark = text.marks().next().unwrap();
+/// assert_eq!(mark.key, "link");
+/// assert_eq!(mark.value["href"], "https://synckit.dev");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatMark {
+    /// Creating client and a Lamport clock value; orders concurrent marks
+    pub id: NodeId,
+
+    /// Gap where the mark starts
+    pub start: Anchor,
+
+    /// Gap where the mark ends
+    pub end: Anchor,
+
+    /// Attribute name, such as `bold` or `link`
+    pub key: String,
+
+    /// Attribute value; `null` removes the attribute from the range
+    pub value: Value,
+}
+
+/// An operation produced by a local edit on `RichText`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RichTextOp {
+    /// Plain text edit
+    Text(FugueOp),
+
+    /// Formatting change
+    Format(FormatMark),
+}
+
+/// A run of text with the same attributes, for rendering
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSpan {
+    /// The text of the run
+    pub text: String,
+
+    /// Attributes in effect over the whole run
+    pub attributes: BTreeMap<String, Value>,
+}
+
+/// Rich text CRDT: a FugueText plus Peritext formatting marks
+///
+/// Local edits return a [`RichTextOp`] for other replicas to apply with
+/// [`RichText::apply_op`]; [`RichText::merge`] exchanges whole states.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::crdt::text_fugue::{Expand, RichText};
+/// use serde_json::json;
+///
+/// let mut alice = RichText::new("alice".to_string());
+/// alice.insert(0, "Hello world").unwrap();
+///
+/// let mut bob = RichText::new("bob".to_string());
+/// bob.merge(&alice).unwrap();
+///
+/// // Concurrent, overlapping formatting
+/// alice.format(0, 7, "bold", json!(true), Expand::After).unwrap();
+/// bob.format(6, 11, "italic", json!(true), Expand::After).unwrap();
+///
+/// alice.merge(&bob).unwrap();
+/// bob.merge(&alice).unwrap();
+///
+/// let spans = alice.spans();
+/// assert_eq!(spans, bob.spans());
+/// assert_eq!(spans[0].text, "Hello ");
+/// assert_eq!(spans[1].text, "w");
+/// assert_eq!(spans[1].attributes.len(), 2);
+/// assert_eq!(spans[2].text, "orld");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RichText {
+    /// The plain text
+    text: FugueText,
+
+    /// Every mark ever applied, keyed by mark ID
+    marks: BTreeMap<NodeId, FormatMark>,
+
+    /// Lamport clock for mark IDs, kept ahead of the text's clock
+    clock: LamportClock,
+}
+
+impl RichText {
+    /// Create an empty document
+    pub fn new(client_id: String) -> Self {
+        Self {
+            text: FugueText::new(client_id),
+            marks: BTreeMap::new(),
+            clock: LamportClock::new(),
+        }
+    }
+
+    /// The underlying plain text
+    pub fn text(&self) -> &FugueText {
+        &self.text
+    }
+
+    /// Number of visible graphemes
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Check if the document is empty
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The visible text, without formatting
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        self.text.to_string()
+    }
+
+    /// Every mark, in ID order
+    pub fn marks(&self) -> impl Iterator<Item = &FormatMark> {
+        self.marks.values()
+    }
+
+    /// Insert text at the given grapheme position
+    ///
+    /// Whether the new text picks up formatting from its neighbours depends
+    /// on their marks' [`Expand`] rules.
+    pub fn insert(&mut self, position: usize, text: &str) -> Result<RichTextOp, TextError> {
+        self.text.insert(position, text).map(RichTextOp::Text)
+    }
+
+    /// Delete text at the given grapheme position
+    ///
+    /// Marks anchored to deleted characters keep covering what is left.
+    pub fn delete(&mut self, position: usize, length: usize) -> Result<RichTextOp, TextError> {
+        self.text.delete(position, length).map(RichTextOp::Text)
+    }
+
+    /// Set an attribute on the graphemes `start..end`
+    ///
+    /// # Arguments
+    ///
+    /// * `start`, `end` - Grapheme range to format (end exclusive)
+    /// * `key` - Attribute name
+    /// * `value` - Attribute value; `Value::Null` removes the attribute
+    /// * `expand` - Whether text later typed at the edges joins the range
+    ///
+    /// # Errors
+    ///
+    /// Returns `TextError::RangeOutOfBounds` if the range exceeds the
+    /// document and `TextError::InvalidOperation` if it is empty.
+    pub fn format(
+        &mut self,
+        start: usize,
+        end: usize,
+        key: &str,
+        value: Value,
+        expand: Expand,
+    ) -> Result<RichTextOp, TextError> {
+        let length = self.text.len();
+        if start > end || end > length {
+            return Err(TextError::RangeOutOfBounds { start, end, length });
+        }
+        if start == end {
+            return Err(TextError::InvalidOperation(
+                "cannot format an empty range".to_string(),
+            ));
+        }
+
+        let char_at = |position: usize| {
+            self.text
+                .node_id_at(position)
+                .expect("position is in bounds")
+        };
+
+        let start_anchor = match start.checked_sub(1) {
+            Some(before) if expand.before() => Anchor::After(char_at(before)),
+            None if expand.before() => Anchor::Start,
+            _ => Anchor::Before(char_at(start)),
+        };
+        let end_anchor = if !expand.after() {
+            Anchor::After(char_at(end - 1))
+        } else if end == length {
+            Anchor::End
+        } else {
+            Anchor::Before(char_at(end))
+        };
+
+        self.clock.update(self.text.clock());
+        let id = NodeId::new(self.text.client_id().to_string(), self.clock.tick(), 0);
+
+        let mark = FormatMark {
+            id: id.clone(),
+            start: start_anchor,
+            end: end_anchor,
+            key: key.to_string(),
+            value,
+        };
+        self.marks.insert(id, mark.clone());
+
+        Ok(RichTextOp::Format(mark))
+    }
+
+    /// Apply an operation produced by another replica
+    ///
+    /// Text operations follow `FugueText::apply_op`. A mark is stored right
+    /// away and takes effect once the characters it is anchored to arrive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TextError::InvalidOperation` if the operation is malformed
+    pub fn apply_op(&mut self, op: &RichTextOp) -> Result<(), TextError> {
+        match op {
+            RichTextOp::Text(op) => self.text.apply_op(op),
+            RichTextOp::Format(mark) => {
+                if mark.key.is_empty() {
+                    return Err(TextError::InvalidOperation(format!(
+                        "mark {} has no attribute name",
+                        mark.id
+                    )));
+                }
+
+                self.clock.update(mark.id.clock);
+                self.marks
+                    .entry(mark.id.clone())
+                    .or_insert_with(|| mark.clone());
+                Ok(())
+            }
+        }
+    }
+
+    /// Merge with another replica
+    pub fn merge(&mut self, remote: &RichText) -> Result<(), TextError> {
+        self.text.merge(&remote.text)?;
+        for (id, mark) in &remote.marks {
+            self.marks.entry(id.clone()).or_insert_with(|| mark.clone());
+        }
+        self.clock.update(remote.clock.value());
+
+        Ok(())
+    }
+
+    /// Attributes in effect at a grapheme position
+    pub fn attributes_at(&self, position: usize) -> BTreeMap<String, Value> {
+        let marks = self.resolved_marks();
+        Self::attributes_over(&marks, position, position + 1)
+    }
+
+    /// Split the text into runs of identical formatting
+    ///
+    /// Adjacent runs always differ in their attributes, so the result can be
+    /// rendered as is. Cost is O(n + m²) for n graphemes and m marks.
+    pub fn spans(&self) -> Vec<TextSpan> {
+        let text = self.text.to_string();
+        let graphemes: Vec<&str> = text.graphemes(true).collect();
+        let marks = self.resolved_marks();
+
+        let mut cuts: BTreeSet<usize> = BTreeSet::from([0, graphemes.len()]);
+        for (start, end, _) in &marks {
+            cuts.insert((*start).min(graphemes.len()));
+            cuts.insert((*end).min(graphemes.len()));
+        }
+        let cuts: Vec<usize> = cuts.into_iter().collect();
+
+        let mut spans: Vec<TextSpan> = Vec::new();
+        for pair in cuts.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let attributes = Self::attributes_over(&marks, from, to);
+            let run = graphemes[from..to].concat();
+
+            match spans.last_mut() {
+                Some(last) if last.attributes == attributes => last.text.push_str(&run),
+                _ => spans.push(TextSpan {
+                    text: run,
+                    attributes,
+                }),
+            }
+        }
+
+        spans
+    }
+
+    /// Every mark whose anchors resolve, as the grapheme range it covers
+    ///
+    /// Marks come out in ID order.
+    fn resolved_marks(&self) -> Vec<(usize, usize, &FormatMark)> {
+        self.marks
+            .values()
+            .filter_map(|mark| {
+                let start = self.resolve(&mark.start)?;
+                let end = self.resolve(&mark.end)?;
+                (start < end).then_some((start, end, mark))
+            })
+            .collect()
+    }
+
+    /// Attributes of marks covering all of `from..to`, later marks winning
+    fn attributes_over(
+        marks: &[(usize, usize, &FormatMark)],
+        from: usize,
+        to: usize,
+    ) -> BTreeMap<String, Value> {
+        let mut attributes = BTreeMap::new();
+        for (start, end, mark) in marks {
+            if *start <= from && to <= *end {
+                attributes.insert(mark.key.clone(), mark.value.clone());
+            }
+        }
+        attributes.retain(|_, value| !value.is_null());
+        attributes
+    }
+
+    /// Grapheme position of the gap an anchor points at
+    ///
+    /// Returns None if the anchor's character has not arrived yet.
+    fn resolve(&self, anchor: &Anchor) -> Option<usize> {
+        match anchor {
+            Anchor::Start => Some(0),
+            Anchor::End => Some(self.text.len()),
+            Anchor::Before(id) => self.text.offset_of_char(id).map(|(position, _)| position),
+            Anchor::After(id) => self
+                .text
+                .offset_of_char(id)
+                .map(|(position, visible)| position + visible as usize),
+        }
+    }
+}
+
+impl Serialize for RichText {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("RichText", 3)?;
+
+        state.serialize_field("text", &self.text)?;
+        let marks: Vec<&FormatMark> = self.marks.values().collect();
+        state.serialize_field("marks", &marks)?;
+        state.serialize_field("clock", &self.clock)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RichText {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RichTextHelper {
+            text: FugueText,
+            marks: Vec<FormatMark>,
+            clock: LamportClock,
+        }
+
+        let helper = RichTextHelper::deserialize(deserializer)?;
+
+        Ok(Self {
+            text: helper.text,
+            marks: helper
+                .marks
+                .into_iter()
+                .map(|mark| (mark.id.clone(), mark))
+                .collect(),
+            clock: helper.clock,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attrs(pairs: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_format_spans() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "Hello world").unwrap();
+        text.format(0, 5, "bold", json!(true), Expand::After)
+            .unwrap();
+
+        let spans = text.spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].text, "Hello");
+        assert_eq!(spans[0].attributes, attrs(&[("bold", json!(true))]));
+        assert_eq!(spans[1].text, " world");
+        assert!(spans[1].attributes.is_empty());
+    }
+
+    #[test]
+    fn test_expand_rules() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "ab cd ef").unwrap();
+        text.format(0, 2, "bold", json!(true), Expand::After)
+            .unwrap();
+        text.format(3, 5, "link", json!({ "href": "x" }), Expand::None)
+            .unwrap();
+        text.format(6, 8, "italic", json!(true), Expand::Before)
+            .unwrap();
+
+        text.insert(2, "B").unwrap(); // "abB cd ef": joins the bold
+        text.insert(3, "l").unwrap(); // "abBl cd ef": still inside the bold
+        text.insert(7, "L").unwrap(); // "abBl cdL ef": stays out of the link
+        text.insert(5, "<").unwrap(); // "abBl <cdL ef": stays out of the link
+        text.insert(10, "I").unwrap(); // "abBl <cdL Ief": joins the italic
+
+        assert_eq!(text.to_string(), "abBl <cdL Ief");
+        let has = |position: usize, key: &str| text.attributes_at(position).contains_key(key);
+        assert!(has(2, "bold") && has(3, "bold") && !has(4, "bold"));
+        assert!(!has(5, "link") && has(6, "link") && has(7, "link") && !has(8, "link"));
+        assert!(!has(9, "italic") && has(10, "italic") && has(12, "italic"));
+    }
+
+    #[test]
+    fn test_concurrent_values_resolve_deterministically() {
+        let mut alice = RichText::new("alice".to_string());
+        alice.insert(0, "colour").unwrap();
+        let mut bob = RichText::new("bob".to_string());
+        bob.merge(&alice).unwrap();
+
+        let red = alice
+            .format(0, 6, "color", json!("red"), Expand::After)
+            .unwrap();
+        let blue = bob
+            .format(2, 4, "color", json!("blue"), Expand::After)
+            .unwrap();
+
+        alice.apply_op(&blue).unwrap();
+        bob.apply_op(&red).unwrap();
+
+        // Same clock: the higher client ID wins the overlap
+        assert_eq!(alice.spans(), bob.spans());
+        assert_eq!(alice.attributes_at(3)["color"], json!("blue"));
+        assert_eq!(alice.attributes_at(0)["color"], json!("red"));
+    }
+
+    #[test]
+    fn test_null_removes_formatting() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "Hello").unwrap();
+        text.format(0, 5, "bold", json!(true), Expand::After)
+            .unwrap();
+        text.format(1, 3, "bold", Value::Null, Expand::After)
+            .unwrap();
+
+        let spans = text.spans();
+        let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(texts, vec!["H", "el", "lo"]);
+        assert!(spans[1].attributes.is_empty());
+    }
+
+    #[test]
+    fn test_marks_survive_deleting_their_edges() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "abcdef").unwrap();
+        text.format(1, 5, "bold", json!(true), Expand::None)
+            .unwrap();
+
+        text.delete(4, 2).unwrap(); // "abcd"
+        text.delete(0, 2).unwrap(); // "cd"
+
+        let spans = text.spans();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "cd");
+        assert!(spans[0].attributes.contains_key("bold"));
+    }
+
+    #[test]
+    fn test_ops_converge_in_any_order() {
+        let mut alice = RichText::new("alice".to_string());
+        let ops = vec![
+            alice.insert(0, "Hello world").unwrap(),
+            alice
+                .format(0, 5, "bold", json!(true), Expand::After)
+                .unwrap(),
+            alice.insert(5, "!").unwrap(),
+        ];
+
+        let mut in_order = RichText::new("bob".to_string());
+        for op in &ops {
+            in_order.apply_op(op).unwrap();
+        }
+
+        // The mark arrives before the text it is anchored to
+        let mut reversed = RichText::new("carol".to_string());
+        for op in ops.iter().rev() {
+            reversed.apply_op(op).unwrap();
+        }
+
+        assert_eq!(in_order.spans(), alice.spans());
+        assert_eq!(reversed.spans(), alice.spans());
+        assert_eq!(alice.spans()[0].text, "Hello!");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "See docs").unwrap();
+        text.format(
+            4,
+            8,
+            "link",
+            json!({ "href": "https://synckit.dev" }),
+            Expand::None,
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&text).unwrap();
+        let restored: RichText = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.spans(), text.spans());
+        assert_eq!(restored.marks().count(), 1);
+    }
+
+    #[test]
+    fn test_format_rejects_bad_ranges() {
+        let mut text = RichText::new("client1".to_string());
+        text.insert(0, "abc").unwrap();
+
+        assert!(text
+            .format(1, 1, "bold", json!(true), Expand::After)
+            .is_err());
+        assert!(text
+            .format(2, 4, "bold", json!(true), Expand::After)
+            .is_err());
+        assert!(text.format(0, 3, "bold", json!(true), Expand::Both).is_ok());
+    }
+}
@@ -2,15 +2,25 @@
 //!
 //! This module implements the complete Fugue Text CRDT with:
 //! - Rope-based text storage for efficient edits
-//! - BTreeMap for CRDT metadata maintaining Fugue ordering
+//! - BTreeMap for CRDT metadata, plus the blocks' document order
 //! - Run-Length Encoding for memory efficiency
 //! - Lamport clocks for causality tracking
-//! - O(log n) position lookup (Phase 1.5 - binary search with position cache)
+//! - Operation-based sync: local edits return `FugueOp`s that other
+//!   replicas integrate one at a time with `apply_op`
+//! - State vectors, so peers exchange only the operations the other lacks
 
 use super::block::FugueBlock;
+use super::cursor::{Assoc, Cursor};
 use super::node::NodeId;
+use super::op::{FugueOp, NodeIdRange};
+use super::order::BlockOrder;
+use crate::crdt::Crdt;
+use crate::error::SyncError;
+use crate::sync::VectorClock;
+use crate::ClientID;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[cfg(feature = "text-crdt")]
 use ropey::Rope;
@@ -18,26 +28,6 @@ use ropey::Rope;
 #[cfg(feature = "text-crdt")]
 use unicode_segmentation::UnicodeSegmentation;
 
-/// Side of a node in the Fugue tree (left or right child of parent)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Side {
-    Left,
-    Right,
-}
-
-/// Tree node for Fugue tree reconstruction.
-///
-/// The Fugue algorithm requires building an explicit tree structure from
-/// the implicit tree encoded in left_origin/right_origin metadata.
-/// This struct represents a node in that reconstructed tree.
-#[derive(Debug, Clone)]
-struct TreeNode {
-    id: NodeId,
-    parent: Option<NodeId>,
-    side: Side,
-    deleted: bool,
-}
-
 /// Lamport timestamp for causality tracking
 ///
 /// Lamport clocks provide a "happens-before" partial ordering of events
@@ -159,6 +149,15 @@ pub enum TextError {
 
     /// Rope operation failed
     RopeError(String),
+
+    /// String is not a `client@clock:offset` NodeId
+    InvalidNodeId(String),
+
+    /// Operation is malformed and can never be applied
+    InvalidOperation(String),
+
+    /// String is not a cursor in its compact form
+    InvalidCursor(String),
 }
 
 impl std::fmt::Display for TextError {
@@ -199,11 +198,47 @@ impl std::fmt::Display for TextError {
             TextError::RopeError(msg) => {
                 write!(f, "Rope error: {}", msg)
             }
+            TextError::InvalidNodeId(s) => {
+                write!(f, "Invalid NodeId: {}", s)
+            }
+            TextError::InvalidOperation(msg) => {
+                write!(f, "Invalid operation: {}", msg)
+            }
+            TextError::InvalidCursor(s) => {
+                write!(f, "Invalid cursor: {}", s)
+            }
         }
     }
 }
 
 impl std::error::Error for TextError {}
+/// Highest clock this replica has seen from each client
+///
+/// Built from block and delete IDs, so it summarizes a whole document in one
+/// entry per client. It has the same shape as the sync layer's
+/// `VectorClock`, which lets it travel in the existing protocol messages.
+///
+/// A state vector assumes a replica holds every operation of a client up to
+/// the clock it records. That holds when each client's operations are
+/// delivered in the order they were made, which is what `encode_diff` and
+/// the sync log provide.
+pub type StateVector = VectorClock;
+
+/// What a [`FugueText::gc`] pass reclaimed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcStats {
+    /// Tombstones whose text was dropped
+    pub blocks_collected: usize,
+
+    /// Collected tombstones folded into a neighbour
+    pub blocks_merged: usize,
+
+    /// Deletions dropped from the delete log
+    pub deletes_pruned: usize,
+
+    /// Estimated heap and inline bytes freed
+    pub bytes_reclaimed: usize,
+}
 
 /// Fugue Text CRDT
 ///
@@ -211,7 +246,9 @@ impl std::error::Error for TextError {}
 /// maximal non-interleaving properties. It uses a hybrid architecture:
 ///
 /// - **Rope**: Efficient text storage (ropey crate, O(log n) edits)
-/// - **BTreeMap**: CRDT metadata maintaining Fugue ordering
+/// - **BTreeMap**: CRDT metadata, keyed by each block's last character
+/// - **Document order**: Block IDs in Fugue order, tombstones included, in
+///   an order-statistic tree that also sums visible lengths
 /// - **RLE**: Run-Length Encoding (5-10x memory reduction)
 ///
 /// # Architecture
@@ -220,19 +257,33 @@ impl std::error::Error for TextError {}
 /// FugueText {
 ///     rope: "Hello World"           // Actual text (ropey::Rope)
 ///     blocks: {                      // CRDT metadata (BTreeMap)
-///         client1@1:0 => FugueBlock { text: "Hello ", ... }
-///         client2@2:0 => FugueBlock { text: "World", ... }
+///         client1@6:0 => FugueBlock { text: "Hello ", ... }
+///         client2@5:0 => FugueBlock { text: "World", ... }
 ///     }
+///     order: [client1@6:0, client2@5:0]
 /// }
 /// ```
 ///
-/// # Performance (Phase 1.5)
+/// # Operations
+///
+/// `insert` and `delete` return a [`FugueOp`] describing the edit. Sending
+/// that op to other replicas and calling [`FugueText::apply_op`] there is
+/// all it takes to sync a keystroke; ops whose origins have not arrived yet
+/// are buffered and applied once they can be. After a disconnect, a peer
+/// sends its [`FugueText::state_vector`] and gets back just the operations
+/// it is missing from [`FugueText::encode_diff`]. [`FugueText::merge`]
+/// remains available for exchanging whole states.
 ///
-/// - Insert: O(log n) - Rope O(log n) + position lookup O(log n)
-/// - Delete: O(n) - Still needs full scan for range deletion
-/// - Merge: O(m log n) - m remote blocks, n local blocks
+/// # Performance
+///
+/// - Insert/Delete: O(log n) to map positions and edit the rope
+/// - apply_op: O(log n) per block, plus a scan over concurrent siblings,
+///   independent of document length
+/// - Merge: every remote block is visited; known blocks are skipped in
+///   O(log n) and new ones cost the same as `apply_op`
+/// - encode_diff: per client, only the blocks and deletions the peer has
+///   not seen are visited
 /// - Memory: ~7 bytes/char with RLE (vs 61 bytes without!)
-/// - Position cache: O(n) rebuild, amortized O(1) per operation
 ///
 /// # Example
 ///
@@ -253,23 +304,45 @@ pub struct FugueText {
     /// Note: Rope is rebuilt from blocks during deserialization
     rope: Rope,
 
-    /// CRDT metadata: BTreeMap maintains Fugue ordering via NodeId Ord
+    /// CRDT metadata, keyed by the NodeId of each block's last character
     blocks: BTreeMap<NodeId, FugueBlock>,
 
+    /// Block IDs in document order, tombstones included
+    ///
+    /// Tombstones keep their place so that operations using a deleted
+    /// character as an origin still land in the right spot. The tree also
+    /// maps blocks to grapheme and rope positions in O(log n).
+    order: BlockOrder,
+
+    /// Last clock of every block, per client
+    ///
+    /// A client's blocks cover disjoint clock ranges, so the block holding a
+    /// character is the first one whose last clock is >= the character's.
+    ranges: HashMap<String, BTreeSet<u64>>,
+
+    /// Number of visible graphemes
+    len: usize,
+
+    /// Applied deletions per deleting client, keyed by the delete's clock
+    ///
+    /// Tombstones alone cannot say when they were deleted, so the log is what
+    /// lets `encode_diff` send only the deletions a peer has not seen.
+    deletes: BTreeMap<String, BTreeMap<u64, Vec<NodeIdRange>>>,
+
+    /// Highest block or delete clock seen per client
+    version: StateVector,
+
     /// Lamport clock for causality tracking
     clock: LamportClock,
 
     /// Client/replica identifier
     client_id: String,
 
-    /// Cache validity flag (avoids O(n) scan to check if rebuild needed)
-    /// Set to false on insert/delete (O(1)), checked before find_origins (O(1))
-    cache_valid: bool,
-
-    /// Cached vector of non-deleted blocks for O(log n) binary search
-    /// Rebuilt when cache_valid is false. Avoids O(n) allocation on every insert!
-    #[cfg(feature = "text-crdt")]
-    cached_blocks: Vec<NodeId>,
+    /// Remote operations waiting for the characters they reference
+    ///
+    /// Not serialized: a replica that reloads relies on the sender
+    /// redelivering anything it had not acknowledged.
+    pending: Vec<FugueOp>,
 }
 
 #[cfg(feature = "text-crdt")]
@@ -279,12 +352,15 @@ impl Serialize for FugueText {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("FugueText", 3)?;
+        let mut state = serializer.serialize_struct("FugueText", 4)?;
 
         // Convert BTreeMap to Vec for JSON compatibility (JSON requires string keys)
         let blocks_vec: Vec<(&NodeId, &FugueBlock)> = self.blocks.iter().collect();
         state.serialize_field("blocks", &blocks_vec)?;
 
+        let deletes_vec: Vec<(NodeId, &Vec<NodeIdRange>)> = self.logged_deletes().collect();
+        state.serialize_field("deletes", &deletes_vec)?;
+
         state.serialize_field("clock", &self.clock)?;
         state.serialize_field("client_id", &self.client_id)?;
         state.end()
@@ -300,31 +376,30 @@ impl<'de> Deserialize<'de> for FugueText {
         #[derive(Deserialize)]
         struct FugueTextHelper {
             blocks: Vec<(NodeId, FugueBlock)>,
+            #[serde(default)]
+            deletes: Vec<(NodeId, Vec<NodeIdRange>)>,
             clock: LamportClock,
             client_id: String,
         }
 
         let helper = FugueTextHelper::deserialize(deserializer)?;
 
-        // Convert Vec back to BTreeMap
+        // Convert Vec back to BTreeMap so blocks are replayed in (mostly)
+        // causal order
         let blocks: BTreeMap<NodeId, FugueBlock> = helper.blocks.into_iter().collect();
 
-        // Rebuild rope from blocks
-        let mut text = String::new();
-        for block in blocks.values() {
-            if !block.is_deleted() {
-                text.push_str(&block.text);
-            }
+        // Rebuild document order and rope by integrating every block
+        let mut text = Self::new(helper.client_id);
+        for block in blocks.into_values() {
+            text.merge_block(block);
         }
+        for (id, ranges) in helper.deletes {
+            text.merge_delete(id, ranges);
+        }
+        text.flush_pending();
+        text.clock.update(helper.clock.value());
 
-        Ok(Self {
-            rope: Rope::from_str(&text),
-            blocks,
-            clock: helper.clock,
-            client_id: helper.client_id,
-            cache_valid: false,        // Cache needs rebuild after deserialization
-            cached_blocks: Vec::new(), // Will be rebuilt on first find_origins
-        })
+        Ok(text)
     }
 }
 
@@ -349,10 +424,14 @@ impl FugueText {
         Self {
             rope: Rope::new(),
             blocks: BTreeMap::new(),
+            order: BlockOrder::new(),
+            ranges: HashMap::new(),
+            len: 0,
+            deletes: BTreeMap::new(),
+            version: StateVector::new(),
             clock: LamportClock::new(),
             client_id,
-            cache_valid: true,         // Empty document has valid (empty) cache
-            cached_blocks: Vec::new(), // Empty document has empty blocks vector
+            pending: Vec::new(),
         }
     }
 
@@ -371,12 +450,12 @@ impl FugueText {
     /// assert_eq!(text.len(), 7);  // Not 10 (byte length)
     /// ```
     pub fn len(&self) -> usize {
-        self.rope.len_chars()
+        self.len
     }
 
     /// Check if the text is empty
     pub fn is_empty(&self) -> bool {
-        self.rope.len_chars() == 0
+        self.len == 0
     }
 
     /// Convert to String
@@ -408,10 +487,17 @@ impl FugueText {
         self.clock.value()
     }
 
+    /// Number of remote operations buffered until their dependencies arrive
+    pub fn pending_ops(&self) -> usize {
+        self.pending.len()
+    }
+
     /// Insert text at the given grapheme position
     ///
-    /// This is the core Fugue operation. Complexity is O(log n) in Phase 1.5
-    /// due to binary search position lookup (O(log n) rope insert + O(log n) find_origins).
+    /// The new block's left origin is the visible character before
+    /// `position`, and its right origin is whatever directly follows that
+    /// character, tombstones included. Remote replicas use the two origins to
+    /// place the block in the same spot.
     ///
     /// # Arguments
     ///
@@ -420,7 +506,9 @@ impl FugueText {
     ///
     /// # Returns
     ///
-    /// NodeId of the created block
+    /// A `FugueOp::Insert` carrying the new block, to be sent to other
+    /// replicas. Inserting an empty string changes nothing and returns an
+    /// empty operation, which `apply_op` ignores.
     ///
     /// # Errors
     ///
@@ -429,59 +517,104 @@ impl FugueText {
     /// # Example
     ///
     /// ```rust
-    /// use synckit_core::crdt::text_fugue::FugueText;
+    /// use synckit_core::crdt::text_fugue::{FugueOp, FugueText};
     ///
     /// let mut text = FugueText::new("client1".to_string());
     /// text.insert(0, "Hello").unwrap();
-    /// text.insert(5, " World").unwrap();
+    /// let op = text.insert(5, " World").unwrap();
     ///
     /// assert_eq!(text.to_string(), "Hello World");
+    /// assert!(matches!(op, FugueOp::Insert(block) if block.text == " World"));
     /// ```
-    pub fn insert(&mut self, position: usize, text: &str) -> Result<NodeId, TextError> {
+    pub fn insert(&mut self, position: usize, text: &str) -> Result<FugueOp, TextError> {
         // 1. Validate position
-        let len = self.len();
-        if position > len {
+        if position > self.len {
             return Err(TextError::PositionOutOfBounds {
                 position,
-                length: len,
+                length: self.len,
             });
         }
 
-        // 2. Find CRDT origins (Phase 1.5: O(log n) with cache!)
-        let (left_origin, right_origin) = self.find_origins(position)?;
-
-        // 3. Calculate grapheme length for per-character clock allocation
-        #[cfg(feature = "text-crdt")]
+        // 2. One clock value per grapheme, so count them first
         let char_count = text.graphemes(true).count();
-        #[cfg(not(feature = "text-crdt"))]
-        let char_count = text.chars().count();
+        if char_count == 0 {
+            let id = NodeId::new(self.client_id.clone(), self.clock.value(), 0);
+            return Ok(FugueOp::Insert(FugueBlock::new(
+                id,
+                String::new(),
+                None,
+                None,
+            )));
+        }
+
+        // 3. Left origin: the visible character before the insertion point.
+        // The new text goes directly after it, ahead of any tombstones.
+        let (left_origin, index) = match position.checked_sub(1) {
+            None => (None, 0),
+            Some(before) => {
+                let (index, offset) = self.locate(before).expect("position is in bounds");
+                let origin = self.char_id(&self.block_at(index), offset);
+                self.split_after(&origin);
+                (Some(origin), index + 1)
+            }
+        };
+
+        // 4. Right origin: whatever follows in document order
+        let right_origin = self.order.get(index).map(|id| self.char_id(id, 0));
 
-        // 4. Generate timestamp range and NodeId (one clock value per character!)
-        // This allocates clock values [timestamp - char_count + 1, timestamp]
-        // Example: "Hello" with 5 chars allocates clocks [1, 2, 3, 4, 5]
+        // 5. Allocate clocks [timestamp - char_count + 1, timestamp]; the
+        // block ID is the last one
         let timestamp = self.clock.tick_by(char_count);
         let id = NodeId::new(self.client_id.clone(), timestamp, 0);
+        let block = FugueBlock::new(id, text.to_string(), left_origin, right_origin);
 
-        // 5. Cache the insert length for later use
-        #[cfg(feature = "text-crdt")]
-        let insert_len = char_count;
+        // 6. Integrate like a remote block so both paths agree on placement
+        self.integrate(block.clone());
 
-        // 6. Create FugueBlock with RLE (entire text as one block!)
-        let block = FugueBlock::new(id.clone(), text.to_string(), left_origin, right_origin);
+        Ok(FugueOp::Insert(block))
+    }
+
+    /// Insert text directly before a character, even a deleted one
+    ///
+    /// `insert` places text after the visible character before the
+    /// position, ahead of any tombstones there. This instead places it
+    /// between `next` and whatever precedes it in document order, which is
+    /// how deleted text is put back where it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TextError::BlockNotFound` if this replica has not seen `next`
+    pub fn insert_before_char(&mut self, next: &NodeId, text: &str) -> Result<FugueOp, TextError> {
+        let Some(block_id) = self.block_containing(next) else {
+            return Err(TextError::BlockNotFound(next.clone()));
+        };
 
-        // 7. Insert into BTreeMap (maintains Fugue ordering)
-        self.blocks.insert(id.clone(), block);
+        let char_count = text.graphemes(true).count();
+        if char_count == 0 {
+            let id = NodeId::new(self.client_id.clone(), self.clock.value(), 0);
+            return Ok(FugueOp::Insert(FugueBlock::new(
+                id,
+                String::new(),
+                None,
+                None,
+            )));
+        }
 
-        // 8. Insert into rope (O(log n))
-        let byte_pos = self.char_to_byte(position)?;
-        self.rope.insert(byte_pos, text);
+        // Left origin: the character right before `next`, deleted or not
+        let left_origin = if next.clock > self.start_clock(&block_id) {
+            Some(NodeId::new(next.client_id.clone(), next.clock - 1, 0))
+        } else {
+            self.index_of_block(&block_id)
+                .checked_sub(1)
+                .map(|index| self.block_at(index))
+        };
 
-        // 9. Update position cache incrementally (O(k) instead of O(n) rebuild!)
-        self.invalidate_position_cache(byte_pos); // Rope cache separate
-        #[cfg(feature = "text-crdt")]
-        self.update_cache_after_insert(position, insert_len, &id);
+        let timestamp = self.clock.tick_by(char_count);
+        let id = NodeId::new(self.client_id.clone(), timestamp, 0);
+        let block = FugueBlock::new(id, text.to_string(), left_origin, Some(next.clone()));
+        self.integrate(block.clone());
 
-        Ok(id)
+        Ok(FugueOp::Insert(block))
     }
 
     /// Delete text at the given position
@@ -496,7 +629,9 @@ impl FugueText {
     ///
     /// # Returns
     ///
-    /// Vec of NodeIds that were marked deleted
+    /// A `FugueOp::Delete` listing the deleted character ranges, to be sent
+    /// to other replicas. A non-empty delete allocates one clock value for
+    /// its own ID.
     ///
     /// # Errors
     ///
@@ -513,219 +648,312 @@ impl FugueText {
     ///
     /// assert_eq!(text.to_string(), "Hello");
     /// ```
-    pub fn delete(&mut self, position: usize, length: usize) -> Result<Vec<NodeId>, TextError> {
+    pub fn delete(&mut self, position: usize, length: usize) -> Result<FugueOp, TextError> {
         // 1. Validate range
-        let doc_len = self.len();
-        if position + length > doc_len {
+        if position + length > self.len {
             return Err(TextError::RangeOutOfBounds {
                 start: position,
                 end: position + length,
-                length: doc_len,
+                length: self.len,
             });
         }
 
-        // 2. Find blocks that overlap deletion range and split if needed
-        let mut blocks_to_split = Vec::new();
-        let mut deleted_ids = Vec::new();
-        let mut current_pos = 0;
+        // 2. Collect the visible characters in the range as clock ranges
+        let mut ranges: Vec<NodeIdRange> = Vec::new();
+        if length > 0 {
+            let (mut index, mut offset) = self.locate(position).expect("range is in bounds");
+            let mut remaining = length;
 
-        // First pass: identify blocks that need splitting
-        for (id, block) in &self.blocks {
-            if block.is_deleted() {
-                continue;
-            }
+            while remaining > 0 {
+                let id = self.block_at(index);
+                let block = &self.blocks[&id];
+
+                if !block.is_deleted() {
+                    let take = (block.len() - offset).min(remaining);
+                    let start = self.start_clock(&id) + offset as u64;
+                    let end = start + take as u64 - 1;
+
+                    match ranges.last_mut() {
+                        Some(last) if last.client_id == id.client_id && last.end + 1 == start => {
+                            last.end = end;
+                        }
+                        _ => ranges.push(NodeIdRange::new(id.client_id.clone(), start, end)),
+                    }
+                    remaining -= take;
+                }
 
-            let block_len = block.len();
-            let block_start = current_pos;
-            let block_end = current_pos + block_len;
-
-            // Check if block overlaps deletion range
-            if block_start < position + length && block_end > position {
-                // Calculate overlap boundaries
-                let delete_start = position.max(block_start);
-                let delete_end = (position + length).min(block_end);
-                let offset_in_block_start = delete_start - block_start;
-                let offset_in_block_end = delete_end - block_start;
-
-                blocks_to_split.push((
-                    id.clone(),
-                    block.clone(),
-                    block_start,
-                    offset_in_block_start,
-                    offset_in_block_end,
-                ));
+                offset = 0;
+                index += 1;
             }
+        }
 
-            current_pos += block_len;
+        if ranges.is_empty() {
+            let id = NodeId::new(self.client_id.clone(), self.clock.value(), 0);
+            return Ok(FugueOp::Delete { id, ranges });
         }
 
-        // Second pass: split blocks and create new ones
-        for (orig_id, orig_block, _block_start, offset_start, offset_end) in blocks_to_split {
-            let block_len = orig_block.len();
-
-            // Check if we need to split (partial deletion)
-            let needs_left_split = offset_start > 0;
-            let needs_right_split = offset_end < block_len;
-
-            if needs_left_split || needs_right_split {
-                // Block splitting: create up to 3 blocks
-                self.split_block_for_deletion(
-                    &orig_id,
-                    &orig_block,
-                    offset_start,
-                    offset_end,
-                    &mut deleted_ids,
-                )?;
-            } else {
-                // Entire block is deleted - just mark it
-                if let Some(block) = self.blocks.get_mut(&orig_id) {
-                    block.mark_deleted();
-                    deleted_ids.push(orig_id);
+        // 3. Tombstone them (splitting blocks at the range boundaries)
+        let id = NodeId::new(self.client_id.clone(), self.clock.tick(), 0);
+        self.log_delete(&id, &ranges);
+        let op = FugueOp::Delete { id, ranges };
+        self.try_apply(op.clone());
+
+        Ok(op)
+    }
+
+    /// Delete characters by ID
+    ///
+    /// Deletes whichever of the listed characters are still visible, so
+    /// text inserted between them by other replicas stays. This is how an
+    /// insert is undone.
+    ///
+    /// # Returns
+    ///
+    /// A `FugueOp::Delete` for the characters actually deleted, which is
+    /// empty if all of them were already gone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::{FugueOp, FugueText, NodeIdRange};
+    ///
+    /// let mut text = FugueText::new("client1".to_string());
+    /// text.insert(0, "Hello").unwrap(); // clocks 1-5
+    /// text.insert(5, " World").unwrap(); // clocks 6-11
+    ///
+    /// let range = NodeIdRange::new("client1".to_string(), 6, 11);
+    /// text.delete_ranges(&[range]);
+    ///
+    /// assert_eq!(text.to_string(), "Hello");
+    /// ```
+    pub fn delete_ranges(&mut self, ranges: &[NodeIdRange]) -> FugueOp {
+        let mut visible: Vec<NodeIdRange> = Vec::new();
+        for range in ranges {
+            let mut clock = range.start;
+            while clock <= range.end {
+                let id = NodeId::new(range.client_id.clone(), clock, 0);
+                let Some(block_id) = self.block_containing(&id) else {
+                    // Never seen here; skip to the next block we know of
+                    match self.next_block_start(&range.client_id, clock) {
+                        Some(next) => {
+                            clock = next;
+                            continue;
+                        }
+                        None => break,
+                    }
+                };
+
+                // One range per block: neighbouring clocks need not be
+                // neighbours in the document
+                let run_end = block_id.clock.min(range.end);
+                if !self.blocks[&block_id].is_deleted() {
+                    visible.push(NodeIdRange::new(range.client_id.clone(), clock, run_end));
                 }
+                clock = run_end + 1;
             }
         }
 
-        // 3. Delete from rope (O(log n))
-        if !deleted_ids.is_empty() {
-            let byte_start = self.char_to_byte(position)?;
-            let byte_end = self.char_to_byte(position + length)?;
-            self.rope.remove(byte_start..byte_end);
-
-            // 4. Invalidate position cache (block splitting creates new blocks)
-            self.invalidate_position_cache(byte_start); // Rope cache separate
-            #[cfg(feature = "text-crdt")]
-            {
-                // Invalidate cache - block splitting changes the block structure
-                self.cache_valid = false;
-            }
+        if visible.is_empty() {
+            let id = NodeId::new(self.client_id.clone(), self.clock.value(), 0);
+            return FugueOp::Delete {
+                id,
+                ranges: visible,
+            };
         }
 
-        Ok(deleted_ids)
+        let id = NodeId::new(self.client_id.clone(), self.clock.tick(), 0);
+        self.log_delete(&id, &visible);
+        let op = FugueOp::Delete {
+            id,
+            ranges: visible,
+        };
+        self.try_apply(op.clone());
+
+        op
     }
 
-    /// Split a block when deleting a portion of it (Clock-based IDs)
+    /// Text of a character range, deleted characters included
     ///
-    /// Creates up to 3 blocks with clock-based IDs (all with offset=0):
-    /// 1. Left block: characters before deletion (if any)
-    /// 2. Middle block: deleted characters (marked as deleted)
-    /// 3. Right block: characters after deletion (if any)
+    /// Characters this replica has not seen, or whose text was dropped by
+    /// `gc`, are left out.
+    pub(crate) fn text_of(&self, range: &NodeIdRange) -> String {
+        let mut text = String::new();
+        let mut clock = range.start;
+
+        while clock <= range.end {
+            let id = NodeId::new(range.client_id.clone(), clock, 0);
+            let Some(block_id) = self.block_containing(&id) else {
+                match self.next_block_start(&range.client_id, clock) {
+                    Some(next) => {
+                        clock = next;
+                        continue;
+                    }
+                    None => break,
+                }
+            };
+
+            let start = (clock - self.start_clock(&block_id)) as usize;
+            let run_end = block_id.clock.min(range.end);
+            let count = (run_end - clock + 1) as usize;
+            text.extend(
+                self.blocks[&block_id]
+                    .text
+                    .graphemes(true)
+                    .skip(start)
+                    .take(count),
+            );
+            clock = run_end + 1;
+        }
+
+        text
+    }
+
+    /// Apply an operation produced by another replica
     ///
-    /// # Clock-based ID Generation
+    /// Inserts are placed using their origins and deletes tombstone the
+    /// listed characters. An operation that references characters this
+    /// replica has not seen yet is buffered, and retried whenever another
+    /// operation is applied. Applying an operation more than once is a no-op.
     ///
-    /// With per-character clocks, block `test@15:0` with length=15 contains
-    /// characters at clocks [1, 2, 3, ..., 15]. When deleting chars 6-10:
-    /// - Left block: `test@5:0` (clocks 1-5)
-    /// - Middle block: `test@10:0` (clocks 6-10, deleted)
-    /// - Right block: `test@15:0` (clocks 11-15)
+    /// # Errors
     ///
-    /// All new block IDs have offset=0!
+    /// Returns `TextError::InvalidOperation` if the operation is malformed
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `orig_id` - Original block ID
-    /// * `orig_block` - Original block to split
-    /// * `offset_start` - Start offset within block (in graphemes)
-    /// * `offset_end` - End offset within block (in graphemes)
-    /// * `deleted_ids` - Vector to collect IDs of deleted blocks
-    #[cfg(feature = "text-crdt")]
-    fn split_block_for_deletion(
-        &mut self,
-        orig_id: &NodeId,
-        orig_block: &FugueBlock,
-        offset_start: usize,
-        offset_end: usize,
-        deleted_ids: &mut Vec<NodeId>,
-    ) -> Result<(), TextError> {
-        use unicode_segmentation::UnicodeSegmentation;
-
-        // Split text into grapheme clusters
-        let graphemes: Vec<&str> = orig_block.text.graphemes(true).collect();
-        let block_len = graphemes.len();
-
-        // Validate offsets
-        if offset_start >= block_len || offset_end > block_len || offset_start >= offset_end {
-            return Err(TextError::InvalidBlockSplit {
-                block_id: orig_id.clone(),
-                offset_start,
-                offset_end,
-                block_len,
-            });
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::FugueText;
+    ///
+    /// let mut alice = FugueText::new("alice".to_string());
+    /// let hello = alice.insert(0, "Hello").unwrap();
+    /// let world = alice.insert(5, " World").unwrap();
+    ///
+    /// // Ops may arrive out of order
+    /// let mut bob = FugueText::new("bob".to_string());
+    /// bob.apply_op(&world).unwrap();
+    /// assert_eq!(bob.pending_ops(), 1);
+    ///
+    /// bob.apply_op(&hello).unwrap();
+    /// assert_eq!(bob.to_string(), "Hello World");
+    /// assert_eq!(bob.pending_ops(), 0);
+    /// ```
+    pub fn apply_op(&mut self, op: &FugueOp) -> Result<(), TextError> {
+        op.validate()?;
+        if let FugueOp::Delete { id, ranges } = op {
+            self.log_delete(id, ranges);
         }
 
-        // Extract text segments
-        let left_text: String = graphemes[..offset_start].join("");
-        let middle_text: String = graphemes[offset_start..offset_end].join("");
-        let right_text: String = graphemes[offset_end..].join("");
+        if let Some(rest) = self.try_apply(op.clone()) {
+            self.pending.push(rest);
+        }
+        self.flush_pending();
 
-        // Calculate the block's starting clock value
-        // Block ID stores the LAST clock value, so start = end - len + 1
-        // Example: block@15:0 with len=15 → start_clock = 15 - 15 + 1 = 1
-        let block_start_clock = orig_id.clock - (block_len as u64) + 1;
+        Ok(())
+    }
 
-        // Remove original block (if it still exists)
-        if !self.blocks.contains_key(orig_id) {
-            // Block was already removed or doesn't exist
-            return Ok(());
-        }
-        self.blocks.remove(orig_id);
-
-        // IMPORTANT: All split blocks maintain the SAME origins as the original block!
-        // They represent parts of the same insert operation, so they have the same
-        // position in the Fugue tree. The clock ranges differentiate them.
-
-        // Create left block (if needed)
-        if !left_text.is_empty() {
-            let left_len = offset_start as u64;
-            let left_end_clock = block_start_clock + left_len - 1;
-            let left_id = NodeId::new(orig_id.client_id.clone(), left_end_clock, 0);
-
-            let left_block = FugueBlock::new(
-                left_id.clone(),
-                left_text,
-                orig_block.left_origin.clone(),  // Same as original!
-                orig_block.right_origin.clone(), // Same as original!
+    /// Summarize which operations this replica has seen
+    ///
+    /// Send this to a peer and apply the ops it returns from `encode_diff`
+    /// to catch up without downloading the whole document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::FugueText;
+    ///
+    /// let mut text = FugueText::new("client1".to_string());
+    /// text.insert(0, "Hello").unwrap();
+    ///
+    /// assert_eq!(text.state_vector().get(&"client1".to_string()), 5);
+    /// ```
+    pub fn state_vector(&self) -> StateVector {
+        self.version.clone()
+    }
+
+    /// Encode the operations a peer at `since` is missing
+    ///
+    /// Returns the blocks (or the tail of a block) with clocks above the
+    /// peer's entry for their client, followed by the deletions it has not
+    /// seen. Applying them with `apply_op` brings the peer up to date; ops
+    /// it already has are no-ops, so an outdated vector only costs bandwidth.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::FugueText;
+    ///
+    /// let mut server = FugueText::new("server".to_string());
+    /// server.insert(0, "Hello").unwrap();
+    ///
+    /// let mut client = FugueText::new("client".to_string());
+    /// client.merge(&server).unwrap();
+    ///
+    /// // Client goes offline while the server keeps editing
+    /// server.insert(5, " World").unwrap();
+    ///
+    /// let diff = server.encode_diff(&client.state_vector());
+    /// assert_eq!(diff.len(), 1);
+    ///
+    /// for op in &diff {
+    ///     client.apply_op(op).unwrap();
+    /// }
+    /// assert_eq!(client.to_string(), "Hello World");
+    /// ```
+    pub fn encode_diff(&self, since: &StateVector) -> Vec<FugueOp> {
+        let mut ops = Vec::new();
+
+        // Only look at each client's blocks past what the peer has seen
+        let mut new_blocks: Vec<NodeId> = Vec::new();
+        for (client_id, ends) in &self.ranges {
+            let seen = since.get(client_id);
+            new_blocks.extend(
+                ends.range(seen + 1..)
+                    .map(|&end| NodeId::new(client_id.clone(), end, 0)),
             );
-            self.blocks.insert(left_id.clone(), left_block);
         }
 
-        // Create middle block (deleted)
-        let _middle_len = (offset_end - offset_start) as u64;
-        let middle_end_clock = block_start_clock + offset_end as u64 - 1;
-        let middle_id = NodeId::new(orig_id.client_id.clone(), middle_end_clock, 0);
+        // Key order is causal, so the receiver rarely needs to buffer
+        new_blocks.sort();
+        for id in &new_blocks {
+            let block = &self.blocks[id];
+            let seen = since.get(&id.client_id);
 
-        let mut middle_block = FugueBlock::new(
-            middle_id.clone(),
-            middle_text,
-            orig_block.left_origin.clone(),  // Same as original!
-            orig_block.right_origin.clone(), // Same as original!
-        );
-        middle_block.mark_deleted();
-        self.blocks.insert(middle_id.clone(), middle_block);
-        deleted_ids.push(middle_id.clone());
-
-        // Create right block (if needed)
-        if !right_text.is_empty() {
-            let _right_len = (block_len - offset_end) as u64;
-            let right_end_clock = block_start_clock + block_len as u64 - 1;
-            let right_id = NodeId::new(orig_id.client_id.clone(), right_end_clock, 0);
-
-            let right_block = FugueBlock::new(
-                right_id.clone(),
-                right_text,
-                orig_block.left_origin.clone(),  // Same as original!
-                orig_block.right_origin.clone(), // Same as original!
+            let start = self.start_clock(id);
+            if start > seen {
+                ops.push(FugueOp::Insert(block.clone()));
+                continue;
+            }
+
+            // Only the tail is new: chain it to the last character seen
+            let tail = block.piece(
+                id.clone(),
+                (seen + 1 - start) as usize,
+                block.len(),
+                Some(NodeId::new(id.client_id.clone(), seen, 0)),
+                block.right_origin.clone(),
             );
-            self.blocks.insert(right_id, right_block);
+            ops.push(FugueOp::Insert(tail));
         }
 
-        Ok(())
+        for (client_id, log) in &self.deletes {
+            let seen = since.get(client_id);
+            for (&clock, ranges) in log.range(seen + 1..) {
+                ops.push(FugueOp::Delete {
+                    id: NodeId::new(client_id.clone(), clock, 0),
+                    ranges: ranges.clone(),
+                });
+            }
+        }
+
+        ops
     }
 
     /// Get the NodeId of the character at the given position
     ///
     /// Returns a stable NodeId that identifies the character at the specified
-    /// grapheme position. This NodeId includes the block's timestamp and the
-    /// offset within the block, making it stable across text edits.
+    /// grapheme position. With per-character clocks, the NodeId is the
+    /// inserting client plus the character's own clock value (offset 0), so
+    /// it stays valid across text edits.
     ///
     /// This is critical for Peritext format spans - format ranges must reference
     /// stable character identifiers, not position indices that shift on edits.
@@ -736,7 +964,7 @@ impl FugueText {
     ///
     /// # Returns
     ///
-    /// NodeId with correct offset for the character at this position
+    /// NodeId of the character at this position
     ///
     /// # Errors
     ///
@@ -744,7 +972,7 @@ impl FugueText {
     ///
     /// # Complexity
     ///
-    /// O(log n) with position cache, O(n) without cache
+    /// O(log n) in the number of blocks
     ///
     /// # Example
     ///
@@ -756,71 +984,15 @@ impl FugueText {
     ///
     /// let node_id = text.get_node_id_at_position(2).unwrap();
     /// // Returns NodeId for 'l' at position 2
-    /// // Format: client1@1:2 (client1, clock=1, offset=2)
+    /// // Format: client1@3:0 (client1, clock=3, offset=0)
+    /// assert_eq!(node_id.clock, 3);
     /// ```
-    pub fn get_node_id_at_position(&mut self, position: usize) -> Result<NodeId, TextError> {
-        // 1. Validate position
-        let len = self.len();
-        if position >= len {
-            return Err(TextError::PositionOutOfBounds {
-                position,
-                length: len,
-            });
-        }
-
-        // 2. Ensure cache is valid for O(log n) lookup
-        if !self.cache_valid {
-            self.rebuild_position_cache();
-            self.cache_valid = true;
-        }
-
-        // 3. Binary search to find block containing this position
-        if self.cached_blocks.is_empty() {
-            return Err(TextError::PositionOutOfBounds {
+    pub fn get_node_id_at_position(&self, position: usize) -> Result<NodeId, TextError> {
+        self.node_id_at(position)
+            .ok_or(TextError::PositionOutOfBounds {
                 position,
-                length: len,
-            });
-        }
-
-        let search_result = self.cached_blocks.binary_search_by(|id| {
-            let block = &self.blocks[id];
-            let block_start = block.cached_position().unwrap_or(0);
-            let block_end = block_start + block.len();
-
-            if position < block_start {
-                std::cmp::Ordering::Greater
-            } else if position >= block_end {
-                std::cmp::Ordering::Less
-            } else {
-                std::cmp::Ordering::Equal
-            }
-        });
-
-        // 4. Calculate clock value for character and create NodeId
-        match search_result {
-            Ok(idx) => {
-                let block_id = &self.cached_blocks[idx];
-                let block = &self.blocks[block_id];
-                let block_start = block.cached_position().unwrap_or(0);
-                let offset_in_block = position - block_start;
-
-                // Calculate the actual clock value for this character
-                // Block ID stores the LAST clock, so start = end - len + 1
-                let block_len = block.len() as u64;
-                let block_start_clock = block_id.clock.saturating_sub(block_len - 1);
-                let char_clock = block_start_clock + offset_in_block as u64;
-
-                // Create NodeId with clock value (offset=0!)
-                Ok(NodeId::new(block_id.client_id.clone(), char_clock, 0))
-            }
-            Err(_) => {
-                // Should never happen if cache is valid and position is in bounds
-                Err(TextError::PositionOutOfBounds {
-                    position,
-                    length: len,
-                })
-            }
-        }
+                length: self.len,
+            })
     }
 
     /// Get the current position of a character identified by NodeId
@@ -828,11 +1000,8 @@ impl FugueText {
     /// This is the reverse of `get_node_id_at_position`. Given a clock-based NodeId
     /// (client_id, clock, 0), returns the character's current position in the text.
     ///
-    /// With per-character clocks, the clock value directly identifies a unique character.
-    /// We find the block whose clock range contains this clock value.
-    ///
     /// Returns None if the NodeId doesn't exist (e.g., character was deleted).
-    /// Complexity: O(n) for searching blocks (could be optimized with a block index).
+    /// Complexity: O(log n)
     ///
     /// # Arguments
     ///
@@ -853,48 +1022,107 @@ impl FugueText {
     ///     println!("Character was deleted");
     /// }
     /// ```
-    pub fn get_position_of_node_id(&mut self, node_id: &NodeId) -> Option<usize> {
-        // 1. Ensure cache is valid
-        if !self.cache_valid {
-            self.rebuild_position_cache();
-            self.cache_valid = true;
+    pub fn get_position_of_node_id(&self, node_id: &NodeId) -> Option<usize> {
+        self.offset_of_char(node_id)
+            .and_then(|(position, visible)| visible.then_some(position))
+    }
+
+    /// Create a cursor at the gap before grapheme `position`
+    ///
+    /// The cursor sticks to the character on the `assoc` side of the gap
+    /// and follows it through later edits; see [`Cursor`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `TextError::PositionOutOfBounds` if position > length
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::{Assoc, FugueText};
+    ///
+    /// let mut text = FugueText::new("client1".to_string());
+    /// text.insert(0, "ac").unwrap();
+    ///
+    /// let before = text.cursor_at(1, Assoc::Before).unwrap();
+    /// let after = text.cursor_at(1, Assoc::After).unwrap();
+    /// text.insert(1, "b").unwrap();
+    ///
+    /// assert_eq!(text.resolve_cursor(&before), Some(1));
+    /// assert_eq!(text.resolve_cursor(&after), Some(2));
+    /// ```
+    pub fn cursor_at(&self, position: usize, assoc: Assoc) -> Result<Cursor, TextError> {
+        if position > self.len {
+            return Err(TextError::PositionOutOfBounds {
+                position,
+                length: self.len,
+            });
         }
 
-        // 2. Find the block whose clock range contains node_id.clock
-        // Use find_block_for_nodeid which handles clock ranges
-        let block_id = self.find_block_for_nodeid(node_id)?;
+        let id = match assoc {
+            Assoc::Before => position
+                .checked_sub(1)
+                .and_then(|before| self.node_id_at(before)),
+            Assoc::After => self.node_id_at(position),
+        };
 
-        // 3. Check if block exists and is visible
-        if let Some(block) = self.blocks.get(&block_id) {
-            // Block is deleted, character doesn't exist
-            if block.deleted {
-                return None;
-            }
+        Ok(Cursor { id, assoc })
+    }
+
+    /// Current grapheme position of a cursor
+    ///
+    /// A cursor whose character was deleted resolves to the gap it was
+    /// deleted from. Returns None if the character has not reached this
+    /// replica yet.
+    ///
+    /// # Complexity
+    ///
+    /// O(log n) in the number of blocks
+    pub fn resolve_cursor(&self, cursor: &Cursor) -> Option<usize> {
+        let Some(id) = &cursor.id else {
+            return Some(match cursor.assoc {
+                Assoc::Before => 0,
+                Assoc::After => self.len,
+            });
+        };
 
-            // 4. Calculate offset within block based on clock values
-            let block_len = block.len() as u64;
-            let block_start_clock = block_id.clock.saturating_sub(block_len - 1);
-            let offset_in_block = (node_id.clock - block_start_clock) as usize;
+        let (position, visible) = self.offset_of_char(id)?;
+        Some(match cursor.assoc {
+            Assoc::Before if visible => position + 1,
+            _ => position,
+        })
+    }
 
-            // Validate offset is within block length
-            if offset_in_block >= block.len() {
-                return None;
-            }
+    /// NodeId of the visible character at `position`
+    pub(crate) fn node_id_at(&self, position: usize) -> Option<NodeId> {
+        let (index, offset) = self.locate(position)?;
+        Some(self.char_id(&self.block_at(index), offset))
+    }
 
-            // 5. Get block's starting position and add offset
-            if let Some(block_start) = block.cached_position() {
-                return Some(block_start + offset_in_block);
-            }
-        }
+    /// Number of visible graphemes before a character, and whether the
+    /// character itself is visible
+    ///
+    /// Deleted characters still resolve, to the spot they were deleted
+    /// from. Returns None for characters this replica has not seen.
+    pub(crate) fn offset_of_char(&self, node_id: &NodeId) -> Option<(usize, bool)> {
+        let block_id = self.block_containing(node_id)?;
+        let (before, _) = self.order.offset_of(&block_id)?;
 
-        // Block doesn't exist or character was deleted
-        None
+        if self.blocks[&block_id].is_deleted() {
+            Some((before, false))
+        } else {
+            Some((
+                before + (node_id.clock - self.start_clock(&block_id)) as usize,
+                true,
+            ))
+        }
     }
 
     /// Merge with another FugueText replica
     ///
-    /// Merges remote blocks into local state, ensuring convergence.
-    /// Complexity: O(m log n) where m = remote blocks, n = local blocks.
+    /// Integrates every remote block this replica is missing and applies
+    /// remote deletions, ensuring convergence. Prefer exchanging `FugueOp`s
+    /// with `apply_op` where possible; merge ships the whole state.
     ///
     /// # Arguments
     ///
@@ -918,626 +1146,703 @@ impl FugueText {
     /// assert_eq!(text1.to_string(), text2.to_string());
     /// ```
     pub fn merge(&mut self, remote: &FugueText) -> Result<(), TextError> {
-        // 1. Merge remote blocks into local BTreeMap
-        for (remote_id, remote_block) in &remote.blocks {
-            match self.blocks.get_mut(remote_id) {
-                Some(local_block) => {
-                    // Block exists locally - merge deletion status
-                    if remote_block.is_deleted() && !local_block.is_deleted() {
-                        local_block.mark_deleted();
-                    }
-                }
-                None => {
-                    // New block from remote - insert it
-                    self.blocks.insert(remote_id.clone(), remote_block.clone());
-                }
+        // Blocks are keyed by their last clock, and every origin has a lower
+        // clock than the blocks that reference it, so key order is causal.
+        // Only tombstones folded by `gc` can make a block wait for the end.
+        for block in remote.blocks.values() {
+            if !self.holds(block) {
+                self.merge_block(block.clone());
             }
         }
-
-        // 2. Rebuild rope from blocks (Phase 1: simple O(n) rebuild)
-        // Phase 2 optimization: incremental update
-        self.rebuild_rope();
-
-        // 3. Update Lamport clock
-        let remote_max_clock = remote
-            .blocks
-            .values()
-            .map(|b| b.id.clock)
-            .max()
-            .unwrap_or(0);
-        self.clock.update(remote_max_clock);
+        for (id, ranges) in remote.logged_deletes() {
+            if !self.is_logged(&id) {
+                self.merge_delete(id, ranges.clone());
+            }
+        }
+        self.flush_pending();
 
         Ok(())
     }
 
-    /// Find CRDT origins for insertion at given position (Phase 1.5 optimized)
+    /// Copy the text into a new replica, to edit apart and merge back later
+    ///
+    /// The copy writes under `client_id`, which must not be used by any
+    /// other replica: two replicas inserting under one ID would produce
+    /// clashing characters.
     ///
-    /// **Phase 1.5 Optimization: Binary Search O(log n)**
-    /// - Uses cached_start_pos for O(log n) binary search
-    /// - Uses cached_blocks vector (O(1) access, no O(n) allocation!)
-    /// - Lazy cache rebuild if invalid (O(1) check via cache_valid flag)
-    /// - Reduces complexity from O(n²) → O(n log n) for sequential ops
+    /// # Example
     ///
-    /// **Performance:**
-    /// - Phase 1: O(n) linear scan per insert → O(n²) total
-    /// - Phase 1.5: O(log n) binary search per insert → O(n log n) total
-    /// - Expected: 260K ops from ~40 min → <500ms (4,800x faster!)
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::FugueText;
     ///
-    /// Returns (left_origin, right_origin) for Fugue's two-phase resolution.
-    fn find_origins(
-        &mut self,
-        grapheme_pos: usize,
-    ) -> Result<(Option<NodeId>, Option<NodeId>), TextError> {
-        // Phase 1.5: O(1) check if cache needs rebuild (using flag, not scanning!)
-        if !self.cache_valid {
-            self.rebuild_position_cache();
-            self.cache_valid = true;
+    /// let mut main = FugueText::new("client1".to_string());
+    /// main.insert(0, "Hello").unwrap();
+    ///
+    /// let mut branch = main.fork("client1-draft".to_string());
+    /// branch.insert(5, " World").unwrap();
+    /// main.insert(0, ">").unwrap();
+    ///
+    /// main.merge(&branch).unwrap();
+    /// assert_eq!(main.to_string(), ">Hello World");
+    /// ```
+    pub fn fork(&self, client_id: String) -> FugueText {
+        FugueText {
+            client_id,
+            ..self.clone()
         }
+    }
 
-        // Phase 1.5: Use cached blocks vector (O(1) access, no allocation!)
-        if self.cached_blocks.is_empty() {
-            // Empty document - no origins
-            return Ok((None, None));
-        }
+    /// Garbage-collect tombstones that every replica has seen deleted
+    ///
+    /// `frontier` must be causally stable: every replica is known to have
+    /// applied all operations up to it. A tombstone is collected once both
+    /// its insert and its deletion are at or below the frontier: its text
+    /// is dropped (the clock range stays, so IDs inside it still resolve)
+    /// and the deletion leaves the delete log. Runs of collected tombstones
+    /// that were split from one block, and that no other block references,
+    /// are folded back together.
+    ///
+    /// Collected replicas keep merging and exchanging ops with the others;
+    /// they only lose the text of stable deletions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::crdt::text_fugue::{FugueText, StateVector};
+    ///
+    /// let mut alice = FugueText::new("alice".to_string());
+    /// let mut bob = FugueText::new("bob".to_string());
+    ///
+    /// alice.insert(0, "Hello cruel world").unwrap();
+    /// alice.delete(5, 6).unwrap();
+    /// bob.merge(&alice).unwrap();
+    ///
+    /// // Both replicas have seen everything up to here
+    /// let mut frontier: StateVector = alice.state_vector();
+    /// frontier.merge(&bob.state_vector());
+    ///
+    /// let stats = alice.gc(&frontier);
+    /// assert_eq!(stats.blocks_collected, 1);
+    /// assert!(stats.bytes_reclaimed > 0);
+    ///
+    /// bob.insert(5, ",").unwrap();
+    /// alice.merge(&bob).unwrap();
+    /// assert_eq!(alice.to_string(), "Hello, world");
+    /// ```
+    pub fn gc(&mut self, frontier: &VectorClock) -> GcStats {
+        let mut stats = GcStats::default();
 
-        // Binary search using cached positions (O(log n))
-        let search_result = self.cached_blocks.binary_search_by(|id| {
-            let block = &self.blocks[id];
-            let block_start = block.cached_position().unwrap();
-            let block_end = block_start + block.len();
+        let stable: Vec<(NodeId, Vec<NodeIdRange>)> = self
+            .logged_deletes()
+            .filter(|(id, _)| id.clock <= frontier.get(&id.client_id))
+            .map(|(id, ranges)| (id, ranges.clone()))
+            .collect();
 
-            if grapheme_pos < block_start {
-                std::cmp::Ordering::Greater // Search in left half
-            } else if grapheme_pos >= block_end {
-                std::cmp::Ordering::Less // Search in right half
-            } else {
-                std::cmp::Ordering::Equal // Found the block!
+        for (id, ranges) in stable {
+            let mut complete = true;
+            for range in &ranges {
+                complete &= self.collect_range(range, frontier, &mut stats);
             }
-        });
 
-        let mut left_origin = None;
-        let mut right_origin = None;
-
-        match search_result {
-            Ok(idx) => {
-                // Found exact block containing position
-                let id = &self.cached_blocks[idx];
-                let block = &self.blocks[id];
-                let block_start = block.cached_position().unwrap();
-                let block_end = block_start + block.len();
-
-                if grapheme_pos == block_start {
-                    // Insert right before this block
-                    // Right origin: first character of this block
-                    let block_len = block.len() as u64;
-                    let block_start_clock = id.clock.saturating_sub(block_len - 1);
-                    right_origin = Some(NodeId::new(id.client_id.clone(), block_start_clock, 0));
-
-                    // Find left_origin (last character of previous block)
-                    if idx > 0 {
-                        let prev_id = &self.cached_blocks[idx - 1];
-                        // Last character has the block's clock value (blocks store LAST clock)
-                        left_origin =
-                            Some(NodeId::new(prev_id.client_id.clone(), prev_id.clock, 0));
+            // Every replica has applied it, and a new one gets the
+            // tombstones themselves
+            if complete {
+                if let Some(log) = self.deletes.get_mut(&id.client_id) {
+                    log.remove(&id.clock);
+                    if log.is_empty() {
+                        self.deletes.remove(&id.client_id);
                     }
-                } else if grapheme_pos == block_end {
-                    // Insert right after this block
-                    // Left origin: last character of this block (block's clock value)
-                    left_origin = Some(NodeId::new(id.client_id.clone(), id.clock, 0));
-
-                    // Find right_origin (next block - first character)
-                    if idx + 1 < self.cached_blocks.len() {
-                        let next_id = &self.cached_blocks[idx + 1];
-                        let next_block = &self.blocks[next_id];
-                        let next_block_len = next_block.len() as u64;
-                        let next_start_clock = next_id.clock.saturating_sub(next_block_len - 1);
-                        right_origin =
-                            Some(NodeId::new(next_id.client_id.clone(), next_start_clock, 0));
-                    }
-                } else {
-                    // Insert INSIDE this block
-                    // Calculate character-level clock values for proper Fugue tree structure
-                    let offset_in_block = grapheme_pos - block_start;
-                    let block_len = block.len() as u64;
-                    let block_start_clock = id.clock.saturating_sub(block_len - 1);
-
-                    // Left origin: character immediately before insertion point
-                    let left_char_clock = block_start_clock + offset_in_block as u64 - 1;
-                    left_origin = Some(NodeId::new(id.client_id.clone(), left_char_clock, 0));
-
-                    // Right origin: character at insertion point
-                    let right_char_clock = block_start_clock + offset_in_block as u64;
-                    right_origin = Some(NodeId::new(id.client_id.clone(), right_char_clock, 0));
-                }
-            }
-            Err(idx) => {
-                // Position falls between blocks or at boundaries
-                if idx == 0 {
-                    // Insert at very beginning - right origin is first char of first block
-                    let first_id = &self.cached_blocks[0];
-                    let first_block = &self.blocks[first_id];
-                    let first_block_len = first_block.len() as u64;
-                    let first_start_clock = first_id.clock.saturating_sub(first_block_len - 1);
-                    right_origin = Some(NodeId::new(
-                        first_id.client_id.clone(),
-                        first_start_clock,
-                        0,
-                    ));
-                } else if idx >= self.cached_blocks.len() {
-                    // Insert at very end - point to last character of last block
-                    let last_id = &self.cached_blocks[self.cached_blocks.len() - 1];
-                    // Last character has the block's clock value
-                    left_origin = Some(NodeId::new(last_id.client_id.clone(), last_id.clock, 0));
-                } else {
-                    // Insert between blocks
-                    // Left: last character of previous block (its clock value)
-                    let left_id = &self.cached_blocks[idx - 1];
-                    left_origin = Some(NodeId::new(left_id.client_id.clone(), left_id.clock, 0));
-
-                    // Right: first character of next block
-                    let right_id = &self.cached_blocks[idx];
-                    let right_block = &self.blocks[right_id];
-                    let right_block_len = right_block.len() as u64;
-                    let right_start_clock = right_id.clock.saturating_sub(right_block_len - 1);
-                    right_origin = Some(NodeId::new(
-                        right_id.client_id.clone(),
-                        right_start_clock,
-                        0,
-                    ));
                 }
+                stats.deletes_pruned += 1;
+                stats.bytes_reclaimed += std::mem::size_of::<NodeId>()
+                    + ranges.len() * std::mem::size_of::<NodeIdRange>();
             }
         }
 
-        Ok((left_origin, right_origin))
+        self.fold_collected(&mut stats);
+
+        stats
     }
 
-    /// Convert grapheme position to byte position (for rope operations)
-    fn char_to_byte(&self, char_pos: usize) -> Result<usize, TextError> {
-        if char_pos > self.rope.len_chars() {
-            return Err(TextError::PositionOutOfBounds {
-                position: char_pos,
-                length: self.rope.len_chars(),
-            });
+    /// Drop the text of the tombstones in a stable deletion range
+    ///
+    /// Returns false if part of the range is unknown, still visible or was
+    /// inserted after the frontier, in which case the deletion stays logged.
+    fn collect_range(
+        &mut self,
+        range: &NodeIdRange,
+        frontier: &VectorClock,
+        stats: &mut GcStats,
+    ) -> bool {
+        if range.end > frontier.get(&range.client_id) {
+            return false;
         }
-        Ok(self.rope.char_to_byte(char_pos))
-    }
 
-    /// Invalidate position cache for blocks after given byte position
-    fn invalidate_position_cache(&mut self, from_byte_pos: usize) {
-        for block in self.blocks.values_mut() {
-            if let Some(rope_pos) = block.rope_position() {
-                if rope_pos >= from_byte_pos {
-                    block.invalidate_rope_position();
+        let mut complete = true;
+        let mut clock = range.start;
+        while clock <= range.end {
+            let id = NodeId::new(range.client_id.clone(), clock, 0);
+
+            let Some(block_id) = self.block_containing(&id) else {
+                complete = false;
+                clock = self
+                    .next_block_start(&range.client_id, clock)
+                    .unwrap_or(range.end + 1);
+                continue;
+            };
+
+            let run_end = block_id.clock.min(range.end);
+            if self.blocks[&block_id].is_deleted() {
+                let end = NodeId::new(range.client_id.clone(), run_end, 0);
+                self.split_before(&id);
+                self.split_after(&end);
+
+                let block = self.blocks.get_mut(&end).expect("run was split out");
+                if !block.is_collected() {
+                    stats.bytes_reclaimed += block.collect();
+                    stats.blocks_collected += 1;
                 }
+            } else {
+                complete = false;
             }
+
+            clock = run_end + 1;
         }
+
+        complete
     }
 
-    /// Rebuild rope from scratch (Phase 1: simple O(n) implementation)
+    /// Fold collected tombstones back into the block they were split from
     ///
-    /// This is used after merge to ensure rope matches CRDT state.
-    /// Phase 2 optimization: incremental updates instead of full rebuild.
-    fn rebuild_rope(&mut self) {
-        // CRITICAL: Build text in DOCUMENT ORDER (Fugue tree), NOT BTreeMap order!
-        // BTreeMap order is causal/timestamp order, which differs from document
-        // order in concurrent scenarios.
-        let document_order = self.get_document_order();
-        let mut text = String::new();
-
-        for id in document_order {
-            if let Some(block) = self.blocks.get(&id) {
-                if !block.is_deleted() {
-                    text.push_str(&block.text);
-                }
+    /// A run `a`, `b` is folded when `b` directly follows `a` in document
+    /// order and is chained to it exactly as `split_before` leaves it, and
+    /// no other block or buffered op uses the boundary between them as an
+    /// origin. The folded block is the one the split would have produced it
+    /// from, so integration is unaffected.
+    fn fold_collected(&mut self, stats: &mut GcStats) {
+        let mut left_refs: HashMap<NodeId, usize> = HashMap::new();
+        let mut right_refs: BTreeSet<NodeId> = BTreeSet::new();
+        let pending = self.pending.iter().filter_map(|op| match op {
+            FugueOp::Insert(block) => Some(block),
+            FugueOp::Delete { .. } => None,
+        });
+        for block in self.blocks.values().chain(pending) {
+            if let Some(origin) = &block.left_origin {
+                *left_refs.entry(origin.clone()).or_default() += 1;
+            }
+            if let Some(origin) = &block.right_origin {
+                right_refs.insert(origin.clone());
             }
         }
 
-        // Replace rope
-        self.rope = Rope::from_str(&text);
+        let mut order: Vec<NodeId> = Vec::with_capacity(self.order.len());
+        let mut folded = 0;
+        for index in 0..self.order.len() {
+            let id = self.block_at(index);
+
+            if let Some(prev) = order.last() {
+                let block = &self.blocks[&id];
+                let before = &self.blocks[prev];
+                let foldable = before.is_collected()
+                    && block.is_collected()
+                    && prev.client_id == id.client_id
+                    && self.start_clock(&id) == prev.clock + 1
+                    && block.left_origin.as_ref() == Some(prev)
+                    && block.right_origin == before.right_origin
+                    && left_refs.get(prev) == Some(&1)
+                    && !right_refs.contains(&self.char_id(&id, 0));
+
+                if foldable {
+                    let prev = order.pop().expect("checked above");
+                    let before = self.blocks.remove(&prev).expect("block exists");
+                    let block = self.blocks.get_mut(&id).expect("block exists");
+                    block.absorb_collected(&before);
+                    block.left_origin = before.left_origin;
+                    if let Some(ends) = self.ranges.get_mut(&prev.client_id) {
+                        ends.remove(&prev.clock);
+                    }
 
-        // Invalidate all position caches (Phase 1.5: O(1) flag + O(n) rope invalidation)
-        for block in self.blocks.values_mut() {
-            block.invalidate_rope_position();
+                    folded += 1;
+                    stats.bytes_reclaimed +=
+                        std::mem::size_of::<FugueBlock>() + std::mem::size_of::<NodeId>();
+                }
+            }
+
+            order.push(id);
         }
-        self.cache_valid = false; // Mark cache as stale
+
+        if folded > 0 {
+            self.order = BlockOrder::new();
+            for (index, id) in order.into_iter().enumerate() {
+                let (graphemes, chars) = visible_len(&self.blocks[&id]);
+                self.order.insert(index, id, graphemes, chars);
+            }
+        }
+        stats.blocks_merged = folded;
     }
 
-    /// Get blocks in document order using Fugue tree traversal.
-    ///
-    /// CRITICAL: This is the ONLY correct way to determine character positions.
-    /// BTreeMap iteration gives causal/timestamp order, NOT document order.
+    /// Check whether one local block already covers a remote block
     ///
-    /// # Algorithm
-    /// 1. Reconstruct Fugue tree from left_origin/right_origin metadata
-    /// 2. Perform in-order traversal of the tree
-    /// 3. Return NodeIds in document order
-    ///
-    /// # Complexity
-    /// - Time: O(n²) for tree reconstruction, O(n) for traversal
-    /// - Space: O(n) for tree storage
-    ///
-    /// # Returns
-    /// Vector of NodeIds in document order (how characters appear in text)
-    fn get_document_order(&self) -> Vec<NodeId> {
-        // Step 1: Reconstruct the Fugue tree
-        let tree = self.reconstruct_fugue_tree();
+    /// Lets `merge` skip known blocks without cloning them. A remote block
+    /// that was split differently here just takes the slower path.
+    fn holds(&self, block: &FugueBlock) -> bool {
+        let first = NodeId::new(
+            block.id.client_id.clone(),
+            block.id.clock + 1 - block.len() as u64,
+            0,
+        );
 
-        // Step 2: In-order traversal to get document order
-        self.in_order_traversal(&tree)
+        self.block_containing(&first).is_some_and(|local| {
+            local.clock >= block.id.clock
+                && (!block.is_deleted() || self.blocks[&local].is_deleted())
+        })
     }
 
-    /// Find the block that contains a given character-level NodeId.
-    ///
-    /// With per-character clock allocation, each block represents a RANGE of clock values,
-    /// not just a single clock. Block IDs always have offset=0.
-    ///
-    /// # Clock Range Calculation
-    ///
-    /// A block with ID `client@5:0` and length=3 contains characters at clocks [3, 4, 5]:
-    /// - block_start_clock = 5 - 3 + 1 = 3
-    /// - block_end_clock = 5
-    ///
-    /// # Arguments
-    /// * `node_id` - Character-level NodeId pointing to a specific clock value
-    ///
-    /// # Returns
-    /// Block ID that contains this clock value, None if not found
-    fn find_block_for_nodeid(&self, node_id: &NodeId) -> Option<NodeId> {
-        // Find block with matching client_id whose clock range contains node_id.clock
-        for (block_id, block) in &self.blocks {
-            if block_id.client_id == node_id.client_id {
-                // Block represents clock range [start_clock, end_clock]
-                // Example: block@5:0 with len=3 → clocks [3, 4, 5]
-                let block_len = block.len() as u64;
-                if block_len == 0 {
-                    continue; // Empty blocks don't contain any characters
-                }
+    /// Integrate a block from a remote state, buffering it if it is early
+    fn merge_block(&mut self, block: FugueBlock) {
+        if let Some(block) = self.integrate_remote(block) {
+            self.pending.push(FugueOp::Insert(block));
+        }
+    }
 
-                let block_start_clock = block_id.clock.saturating_sub(block_len - 1);
-                let block_end_clock = block_id.clock;
+    /// Apply a logged deletion from a remote state, buffering what is early
+    fn merge_delete(&mut self, id: NodeId, ranges: Vec<NodeIdRange>) {
+        self.log_delete(&id, &ranges);
+        if let Some(rest) = self.try_apply(FugueOp::Delete { id, ranges }) {
+            self.pending.push(rest);
+        }
+    }
 
-                if node_id.clock >= block_start_clock && node_id.clock <= block_end_clock {
-                    return Some(block_id.clone());
-                }
+    /// Apply as much of `op` as possible, returning what still has to wait
+    fn try_apply(&mut self, op: FugueOp) -> Option<FugueOp> {
+        match op {
+            FugueOp::Insert(block) => self.integrate_remote(block).map(FugueOp::Insert),
+            FugueOp::Delete { id, ranges } => {
+                let missing: Vec<NodeIdRange> = ranges
+                    .iter()
+                    .flat_map(|range| self.delete_range(range))
+                    .collect();
+                (!missing.is_empty()).then_some(FugueOp::Delete {
+                    id,
+                    ranges: missing,
+                })
             }
         }
-        None
     }
 
-    /// Reconstruct the Fugue tree from left_origin and right_origin metadata.
+    /// Add a deletion to the log
     ///
-    /// This builds an explicit tree structure with parent-child relationships
-    /// from the implicit tree encoded in left_origin/right_origin fields.
-    ///
-    /// **NOTE**: This works at BLOCK level. Blocks are the atomic units in the tree.
-    /// Character-level NodeIds in origins are mapped to their containing blocks.
-    ///
-    /// # Algorithm
-    /// Process blocks in timestamp order (NodeId order). For each block:
-    /// 1. Map character-level origins to their containing blocks
-    /// 2. Check if left_origin block is an ancestor of right_origin block
-    /// 3. If YES: block becomes left child of right_origin block
-    /// 4. If NO: block becomes right child of left_origin block
-    ///
-    /// # Returns
-    /// HashMap mapping NodeId → TreeNode with parent/side information
-    fn reconstruct_fugue_tree(&self) -> HashMap<NodeId, TreeNode> {
-        let mut tree = HashMap::new();
-
-        // Process blocks in timestamp order (critical for ancestor checks)
-        let mut sorted_blocks: Vec<_> = self.blocks.iter().collect();
-        sorted_blocks.sort_by_key(|(id, _)| *id);
+    /// The whole deletion is logged, even the parts still waiting on their
+    /// inserts, so it is replayed after a reload and served to other peers.
+    fn log_delete(&mut self, id: &NodeId, ranges: &[NodeIdRange]) {
+        if ranges.is_empty() {
+            return;
+        }
 
-        for (id, block) in sorted_blocks {
-            // Map character-level NodeIds to their containing blocks
-            let left_block = block
-                .left_origin
-                .as_ref()
-                .and_then(|node_id| self.find_block_for_nodeid(node_id));
-            let right_block = block
-                .right_origin
-                .as_ref()
-                .and_then(|node_id| self.find_block_for_nodeid(node_id));
+        let logged = self
+            .deletes
+            .entry(id.client_id.clone())
+            .or_default()
+            .entry(id.clock)
+            .or_default();
+        for range in ranges {
+            if !logged.contains(range) {
+                logged.push(range.clone());
+            }
+        }
+        self.observe(id);
+    }
 
-            let (parent, side) = self.determine_parent_and_side(&left_block, &right_block, &tree);
+    /// Check whether a deletion is already in the log
+    fn is_logged(&self, id: &NodeId) -> bool {
+        self.deletes
+            .get(&id.client_id)
+            .is_some_and(|log| log.contains_key(&id.clock))
+    }
 
-            tree.insert(
-                id.clone(),
-                TreeNode {
-                    id: id.clone(),
-                    parent,
-                    side,
-                    deleted: block.is_deleted(),
-                },
-            );
-        }
+    /// Every logged deletion with its ID
+    fn logged_deletes(&self) -> impl Iterator<Item = (NodeId, &Vec<NodeIdRange>)> {
+        self.deletes.iter().flat_map(|(client_id, log)| {
+            log.iter()
+                .map(|(&clock, ranges)| (NodeId::new(client_id.clone(), clock, 0), ranges))
+        })
+    }
 
-        tree
+    /// Record that a block or delete with this ID has been seen
+    fn observe(&mut self, id: &NodeId) {
+        let seen = self.version.clocks.entry(id.client_id.clone()).or_insert(0);
+        *seen = (*seen).max(id.clock);
+        self.clock.update(id.clock);
     }
 
-    /// Determine parent and side for a new node based on Fugue algorithm.
-    ///
-    /// # Fugue Rule
-    /// When inserting between positions a and b:
-    /// - If a is NOT an ancestor of b: new node is right child of a
-    /// - If a IS an ancestor of b: new node is left child of b
-    ///
-    /// # Arguments
-    /// * `left_origin` - Block to the left (a)
-    /// * `right_origin` - Block to the right (b)
-    /// * `tree` - Partial tree built from earlier blocks
-    fn determine_parent_and_side(
-        &self,
-        left_origin: &Option<NodeId>,
-        right_origin: &Option<NodeId>,
-        tree: &HashMap<NodeId, TreeNode>,
-    ) -> (Option<NodeId>, Side) {
-        match (left_origin, right_origin) {
-            // No origins: root node (first block ever inserted)
-            (None, None) => (None, Side::Right),
-
-            // Only left origin: insert at end
-            (Some(a), None) => (Some(a.clone()), Side::Right),
-
-            // Only right origin: insert at start
-            (None, Some(b)) => (Some(b.clone()), Side::Left),
-
-            // Both origins: check ancestor relationship
-            (Some(a), Some(b)) => {
-                if self.is_ancestor_in_tree(a, b, tree) {
-                    // a is ancestor of b → new node is left child of b
-                    (Some(b.clone()), Side::Left)
-                } else {
-                    // a is NOT ancestor of b → new node is right child of a
-                    (Some(a.clone()), Side::Right)
-                }
+    /// Retry buffered operations until none of them makes progress
+    fn flush_pending(&mut self) {
+        loop {
+            let mut progressed = false;
+
+            for op in std::mem::take(&mut self.pending) {
+                let rest = self.try_apply(op.clone());
+                progressed |= rest.as_ref() != Some(&op);
+                self.pending.extend(rest);
+            }
+
+            if !progressed {
+                break;
             }
         }
     }
 
-    /// Check if node `a` is an ancestor of node `b` in the tree.
-    ///
-    /// Walks up from b to root, checking if we encounter a.
+    /// Integrate a remote block, skipping characters that are already known
     ///
-    /// # Arguments
-    /// * `a` - Potential ancestor
-    /// * `b` - Potential descendant
-    /// * `tree` - Tree structure to search
-    fn is_ancestor_in_tree(
-        &self,
-        a: &NodeId,
-        b: &NodeId,
-        tree: &HashMap<NodeId, TreeNode>,
-    ) -> bool {
-        let mut current = Some(b.clone());
+    /// Known characters only pick up the block's deletion; unknown runs are
+    /// integrated as new blocks, chained to the run before them. Returns the
+    /// block untouched if one of its origins has not arrived yet.
+    fn integrate_remote(&mut self, block: FugueBlock) -> Option<FugueBlock> {
+        if block.is_empty() {
+            return None;
+        }
 
-        while let Some(node_id) = current {
-            if &node_id == a {
-                return true;
-            }
+        let is_known = |origin: &Option<NodeId>| {
+            origin
+                .as_ref()
+                .is_none_or(|id| self.block_containing(id).is_some())
+        };
+        if !is_known(&block.left_origin) || !is_known(&block.right_origin) {
+            return Some(block);
+        }
 
-            // Walk to parent
-            current = tree.get(&node_id).and_then(|node| node.parent.clone());
+        let client = &block.id.client_id;
+        let start = block.id.clock + 1 - block.len() as u64;
+        let mut clock = start;
+
+        while clock <= block.id.clock {
+            let id = NodeId::new(client.clone(), clock, 0);
+
+            let run_end = match self.block_containing(&id) {
+                Some(local) => {
+                    let run_end = local.clock.min(block.id.clock);
+                    if block.is_deleted() {
+                        self.delete_range(&NodeIdRange::new(client.clone(), clock, run_end));
+                    }
+                    run_end
+                }
+                None => {
+                    let run_end = self
+                        .next_block_start(client, clock)
+                        .map_or(block.id.clock, |next| (next - 1).min(block.id.clock));
+
+                    let left_origin = if clock == start {
+                        block.left_origin.clone()
+                    } else {
+                        Some(NodeId::new(client.clone(), clock - 1, 0))
+                    };
+
+                    let piece = block.piece(
+                        NodeId::new(client.clone(), run_end, 0),
+                        (clock - start) as usize,
+                        (run_end + 1 - start) as usize,
+                        left_origin,
+                        block.right_origin.clone(),
+                    );
+                    self.integrate(piece);
+                    run_end
+                }
+            };
+
+            clock = run_end + 1;
         }
 
-        false
+        None
     }
 
-    /// Perform in-order traversal of the Fugue tree to get document order.
+    /// Place a block whose characters are new and whose origins are known
     ///
-    /// # In-Order Traversal Algorithm
-    /// 1. Traverse left children (sorted by NodeId for determinism)
-    /// 2. Visit the node
-    /// 3. Traverse right children (sorted by NodeId)
-    ///
-    /// This produces the correct document order for Fugue CRDT.
-    ///
-    /// # Arguments
-    /// * `tree` - Reconstructed Fugue tree
-    ///
-    /// # Returns
-    /// Vector of NodeIds in document order
-    fn in_order_traversal(&self, tree: &HashMap<NodeId, TreeNode>) -> Vec<NodeId> {
-        // Find root nodes (nodes with no parent)
-        let mut roots: Vec<NodeId> = tree
-            .values()
-            .filter(|node| node.parent.is_none())
-            .map(|node| node.id.clone())
-            .collect();
-
-        // Sort roots by NodeId for deterministic ordering
-        // This ensures concurrent inserts at position 0 converge
-        roots.sort();
+    /// This is the list integration from the Fugue paper's reference
+    /// implementation (YjsMod), which yields the FugueMax order. Starting
+    /// after the left origin, it scans towards the right origin and skips
+    /// concurrent siblings (and their subtrees) that sort before the new
+    /// block, so every replica ends up with the same order no matter in which
+    /// order blocks were integrated.
+    fn integrate(&mut self, block: FugueBlock) {
+        // Origins must sit on block boundaries to be compared by index
+        if let Some(origin) = &block.left_origin {
+            self.split_after(origin);
+        }
+        if let Some(origin) = &block.right_origin {
+            self.split_before(origin);
+        }
 
-        let mut result = Vec::new();
+        let left = block
+            .left_origin
+            .as_ref()
+            .and_then(|origin| self.index_of(origin));
+        let right = self.right_index(&block.right_origin);
 
-        // Traverse from each root (usually just one, but handle multiple)
-        for root_id in roots {
-            self.in_order_visit(&root_id, tree, &mut result);
-        }
+        let mut dest = left.map_or(0, |index| index + 1);
+        let mut scanning = false;
+        let mut i = dest;
 
-        result
-    }
-
-    /// Recursive in-order tree traversal helper.
-    fn in_order_visit(
-        &self,
-        node_id: &NodeId,
-        tree: &HashMap<NodeId, TreeNode>,
-        result: &mut Vec<NodeId>,
-    ) {
-        let node = &tree[node_id];
-
-        // 1. Traverse left children (sorted by NodeId)
-        // IMPORTANT: Include deleted nodes in traversal (they may have non-deleted children)
-        let mut left_children: Vec<NodeId> = tree
-            .values()
-            .filter(|n| {
-                n.parent.as_ref() == Some(node_id) && n.side == Side::Left
-                // Don't filter by deleted here - deleted nodes can have children!
-            })
-            .map(|n| n.id.clone())
-            .collect();
+        loop {
+            if !scanning {
+                dest = i;
+            }
+            if i == self.order.len() || i == right {
+                break;
+            }
 
-        left_children.sort(); // Deterministic ordering by causal dot (NodeId)
+            let other = &self.blocks[&self.block_at(i)];
+            let other_left = other
+                .left_origin
+                .as_ref()
+                .and_then(|origin| self.index_of(origin));
+            let other_right = self.right_index(&other.right_origin);
+
+            match other_left.cmp(&left) {
+                // `other` hangs off something before our left origin: stop
+                Ordering::Less => break,
+                Ordering::Equal => match other_right.cmp(&right) {
+                    // Sibling whose subtree might still precede us
+                    Ordering::Less => scanning = true,
+                    // True conflict: siblings are ordered by client ID
+                    Ordering::Equal => {
+                        if block.id.client_id < other.id.client_id {
+                            break;
+                        }
+                        scanning = false;
+                    }
+                    Ordering::Greater => scanning = false,
+                },
+                // Inside a sibling's subtree
+                Ordering::Greater => {}
+            }
 
-        for child_id in left_children {
-            self.in_order_visit(&child_id, tree, result);
+            i += 1;
         }
 
-        // 2. Visit this node (if not deleted)
-        if !node.deleted {
-            result.push(node_id.clone());
+        let (graphemes, chars) = visible_len(&block);
+        self.order.insert(dest, block.id.clone(), graphemes, chars);
+        if graphemes > 0 {
+            let (_, char_pos) = self.order.offset_of(&block.id).expect("block was placed");
+            self.rope.insert(char_pos, &block.text);
+            self.len += graphemes;
         }
 
-        // 3. Traverse right children (sorted by NodeId)
-        // IMPORTANT: Include deleted nodes in traversal (they may have non-deleted children)
-        let mut right_children: Vec<NodeId> = tree
-            .values()
-            .filter(|n| {
-                n.parent.as_ref() == Some(node_id) && n.side == Side::Right
-                // Don't filter by deleted here - deleted nodes can have children!
-            })
-            .map(|n| n.id.clone())
-            .collect();
+        self.ranges
+            .entry(block.id.client_id.clone())
+            .or_default()
+            .insert(block.id.clock);
+        self.observe(&block.id);
+        self.blocks.insert(block.id.clone(), block);
+    }
+
+    /// Tombstone the characters in `range`, returning the parts not seen yet
+    fn delete_range(&mut self, range: &NodeIdRange) -> Vec<NodeIdRange> {
+        let mut missing = Vec::new();
+        let mut clock = range.start;
+
+        while clock <= range.end {
+            let id = NodeId::new(range.client_id.clone(), clock, 0);
+
+            let run_end = match self.block_containing(&id) {
+                Some(block_id) => {
+                    let run_end = block_id.clock.min(range.end);
+                    if !self.blocks[&block_id].is_deleted() {
+                        // Cut out exactly clock..=run_end, which ends up
+                        // keyed by its last character
+                        self.split_before(&id);
+                        self.split_after(&NodeId::new(range.client_id.clone(), run_end, 0));
+                        self.mark_deleted(&NodeId::new(range.client_id.clone(), run_end, 0));
+                    }
+                    run_end
+                }
+                None => {
+                    let run_end = self
+                        .next_block_start(&range.client_id, clock)
+                        .map_or(range.end, |next| (next - 1).min(range.end));
+                    missing.push(NodeIdRange::new(range.client_id.clone(), clock, run_end));
+                    run_end
+                }
+            };
+
+            clock = run_end + 1;
+        }
 
-        right_children.sort(); // Deterministic ordering by causal dot (NodeId)
+        missing
+    }
 
-        for child_id in right_children {
-            self.in_order_visit(&child_id, tree, result);
+    /// Tombstone a whole block and drop its text from the rope
+    fn mark_deleted(&mut self, block_id: &NodeId) {
+        let block = self.blocks.get_mut(block_id).expect("block exists");
+        if block.is_deleted() {
+            return;
         }
+
+        let (graphemes, chars) = visible_len(block);
+        block.mark_deleted();
+
+        let (_, char_pos) = self.order.offset_of(block_id).expect("block is placed");
+        self.order.set_visible(block_id, 0, 0);
+        self.len -= graphemes;
+        self.rope.remove(char_pos..char_pos + chars);
     }
 
-    /// Rebuild position cache for all blocks (Phase 1.5 optimization)
+    /// Split the block holding `id` so that `id` starts a block
     ///
-    /// This enables O(log n) binary search in find_origins() instead of O(n)
-    /// linear scan. For each block, we compute its cumulative grapheme start
-    /// position in the document. Also rebuilds the cached_blocks vector.
-    ///
-    /// **Performance Impact:**
-    /// - Without cache: O(n) position lookup → O(n²) for n sequential ops
-    /// - With cache: O(log n) binary search → O(n log n) for n sequential ops
-    /// - Expected: 260K ops from ~40 min → <500ms (4,800x faster!)
-    ///
-    /// **Complexity:** O(n) - Single pass through all blocks
-    ///
-    /// **When Called:**
-    /// - Lazily on first find_origins() call after cache invalidation
-    /// - Triggered by cache_valid flag (O(1) check)
-    ///
-    /// # Example
-    ///
-    /// ```text
-    /// Before:
-    ///   Block A: text="Hello", cached_start_pos=MAX (invalid)
-    ///   Block B: text=" World", cached_start_pos=MAX (invalid)
-    ///
-    /// After rebuild_position_cache():
-    ///   Block A: text="Hello", cached_start_pos=0   (starts at pos 0)
-    ///   Block B: text=" World", cached_start_pos=5  (starts at pos 5)
-    /// ```
-    fn rebuild_position_cache(&mut self) {
-        let mut current_pos = 0;
-        self.cached_blocks.clear();
+    /// The left piece takes the original origins and is keyed by its own
+    /// last character. The right piece keeps the block ID and is chained to
+    /// the left piece, exactly as if its characters had been integrated one
+    /// at a time.
+    fn split_before(&mut self, id: &NodeId) {
+        let Some(block_id) = self.block_containing(id) else {
+            return;
+        };
+        let start = self.start_clock(&block_id);
+        if id.clock == start {
+            return;
+        }
 
-        // CRITICAL: Must use document order (Fugue tree), NOT BTreeMap order!
-        // BTreeMap order is causal/timestamp order, which differs from document
-        // order in concurrent scenarios.
-        let document_order = self.get_document_order();
+        let block = self.blocks.remove(&block_id).expect("block exists");
+        let split = (id.clock - start) as usize;
+        let left_id = NodeId::new(id.client_id.clone(), id.clock - 1, 0);
 
-        for id in document_order {
-            if let Some(block) = self.blocks.get_mut(&id) {
-                if !block.is_deleted() {
-                    block.set_cached_position(current_pos);
-                    current_pos += block.len();
-                    self.cached_blocks.push(id.clone()); // Cache non-deleted block IDs
-                }
-            }
+        let left = block.piece(
+            left_id.clone(),
+            0,
+            split,
+            block.left_origin.clone(),
+            block.right_origin.clone(),
+        );
+        let right = block.piece(
+            block_id.clone(),
+            split,
+            block.len(),
+            Some(left_id.clone()),
+            block.right_origin.clone(),
+        );
+
+        let index = self.index_of_block(&block_id);
+        let (graphemes, chars) = visible_len(&right);
+        self.order.set_visible(&block_id, graphemes, chars);
+        let (graphemes, chars) = visible_len(&left);
+        self.order.insert(index, left_id.clone(), graphemes, chars);
+        if let Some(ends) = self.ranges.get_mut(&id.client_id) {
+            ends.insert(left_id.clock);
         }
+        self.blocks.insert(left_id, left);
+        self.blocks.insert(block_id, right);
     }
 
-    /// Update cache after insert
-    ///
-    /// **CRITICAL**: The incremental cache update optimization is incompatible with
-    /// Fugue tree traversal! The cache must be rebuilt using get_document_order() to
-    /// maintain correct character positions.
-    ///
-    /// The cache will be rebuilt on the next find_origins() call.
-    ///
-    /// # Arguments
-    /// * `insert_pos` - Grapheme position where text was inserted (unused)
-    /// * `insert_len` - Number of graphemes inserted (unused)
-    /// * `new_block_id` - NodeId of the newly created block (unused)
-    fn update_cache_after_insert(
-        &mut self,
-        _insert_pos: usize,
-        _insert_len: usize,
-        _new_block_id: &NodeId,
-    ) {
-        // Invalidate cache - will rebuild using Fugue tree traversal on next access
-        self.cache_valid = false;
+    /// Split the block holding `id` so that `id` ends a block
+    fn split_after(&mut self, id: &NodeId) {
+        self.split_before(&NodeId::new(id.client_id.clone(), id.clock + 1, 0));
     }
 
-    /// Update cache incrementally after delete (Phase 1.5 optimization)
-    ///
-    /// Similar to insert, but shifts positions backward and may remove blocks.
+    /// Find the block holding the character `id`
+    fn block_containing(&self, id: &NodeId) -> Option<NodeId> {
+        let end = *self.ranges.get(&id.client_id)?.range(id.clock..).next()?;
+        let block_id = NodeId::new(id.client_id.clone(), end, 0);
+
+        (self.start_clock(&block_id) <= id.clock).then_some(block_id)
+    }
+
+    /// First clock of the client's next block at or after `clock`
+    fn next_block_start(&self, client_id: &str, clock: u64) -> Option<u64> {
+        let end = *self.ranges.get(client_id)?.range(clock..).next()?;
+        Some(self.start_clock(&NodeId::new(client_id.to_string(), end, 0)))
+    }
+
+    /// Clock of a block's first character
     ///
-    /// **Performance:** O(log n) + O(k) where k = blocks after delete
+    /// Block IDs store the LAST clock, so start = end - len + 1
+    fn start_clock(&self, block_id: &NodeId) -> u64 {
+        block_id.clock + 1 - self.blocks[block_id].len() as u64
+    }
+
+    /// NodeId of the character `offset` graphemes into a block
+    fn char_id(&self, block_id: &NodeId, offset: usize) -> NodeId {
+        NodeId::new(
+            block_id.client_id.clone(),
+            self.start_clock(block_id) + offset as u64,
+            0,
+        )
+    }
+
+    /// Index in document order of the block holding the character `id`
+    fn index_of(&self, id: &NodeId) -> Option<usize> {
+        self.block_containing(id)
+            .map(|block_id| self.index_of_block(&block_id))
+    }
+
+    /// Index in document order of a right origin (the end if there is none)
+    fn right_index(&self, origin: &Option<NodeId>) -> usize {
+        origin
+            .as_ref()
+            .and_then(|origin| self.index_of(origin))
+            .unwrap_or(self.order.len())
+    }
+
+    /// Index in document order of a block
+    fn index_of_block(&self, block_id: &NodeId) -> usize {
+        self.order
+            .index_of(block_id)
+            .expect("every block is in the document order")
+    }
+
+    /// ID of the block at `index` in document order
+    fn block_at(&self, index: usize) -> NodeId {
+        self.order
+            .get(index)
+            .expect("index is in the document order")
+            .clone()
+    }
+
+    /// Find the visible grapheme at `position`
     ///
-    /// # Arguments
-    /// * `delete_pos` - Grapheme position where text was deleted
-    /// * `delete_len` - Number of graphemes deleted
-    #[allow(dead_code)]
-    fn update_cache_after_delete(&mut self, delete_pos: usize, _delete_len: usize) {
-        if !self.cache_valid {
-            // Cache is already invalid, will rebuild on next find_origins
-            return;
-        }
+    /// Returns the block's index in document order and the grapheme offset
+    /// within the block.
+    fn locate(&self, position: usize) -> Option<(usize, usize)> {
+        self.order.locate(position)
+    }
+}
 
-        // 1. Find deletion point using binary search - O(log n)
-        let _delete_idx = self
-            .cached_blocks
-            .binary_search_by(|id| {
-                let block = &self.blocks[id];
-                let block_start = block.cached_position().unwrap_or(0);
-                let block_end = block_start + block.len();
-
-                if delete_pos < block_start {
-                    std::cmp::Ordering::Greater
-                } else if delete_pos >= block_end {
-                    std::cmp::Ordering::Less
-                } else {
-                    std::cmp::Ordering::Equal
-                }
-            })
-            .unwrap_or_else(|idx| idx);
+/// The version is the state vector; the delta is the ops of `encode_diff`.
+#[cfg(feature = "text-crdt")]
+impl Crdt for FugueText {
+    type Version = StateVector;
+    type Delta = Vec<FugueOp>;
 
-        // 2. Remove deleted blocks from cached_blocks - O(k)
-        // Note: We need to check which blocks were deleted and remove them
-        self.cached_blocks.retain(|id| {
-            let block = &self.blocks[id];
-            !block.is_deleted()
-        });
+    fn new_replica(replica_id: ClientID) -> Self {
+        FugueText::new(replica_id)
+    }
+
+    fn merge(&mut self, other: &Self) {
+        FugueText::merge(self, other).expect("merging Fugue texts never fails");
+    }
 
-        // 3. Rebuild cached_blocks to ensure correct ordering after deletion
-        // This is necessary because deletion might affect multiple blocks
-        // CRITICAL: Must use document order (Fugue tree), NOT BTreeMap order!
-        let mut current_pos = 0;
-        self.cached_blocks.clear();
+    fn version(&self) -> StateVector {
+        self.state_vector()
+    }
 
-        let document_order = self.get_document_order();
+    fn delta_since(&self, since: &StateVector) -> Vec<FugueOp> {
+        self.encode_diff(since)
+    }
 
-        for id in document_order {
-            if let Some(block) = self.blocks.get_mut(&id) {
-                if !block.is_deleted() {
-                    block.set_cached_position(current_pos);
-                    current_pos += block.len();
-                    self.cached_blocks.push(id.clone());
-                }
-            }
+    fn apply_delta(&mut self, delta: &Vec<FugueOp>) -> crate::error::Result<()> {
+        for op in delta {
+            self.apply_op(op)
+                .map_err(|e| SyncError::InvalidOperation(e.to_string()))?;
         }
+        Ok(())
+    }
+}
 
-        // Cache remains valid after update
+/// Visible graphemes and rope chars of a block (0 for a tombstone)
+#[cfg(feature = "text-crdt")]
+fn visible_len(block: &FugueBlock) -> (usize, usize) {
+    if block.is_deleted() {
+        (0, 0)
+    } else {
+        (block.len(), block.text.chars().count())
     }
 }
 
@@ -2058,6 +2363,447 @@ mod tests {
         let result = text.get_node_id_at_position(5);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_cursor_follows_concurrent_edits() {
+        let mut alice = FugueText::new("alice".to_string());
+        alice.insert(0, "Hello world").unwrap();
+        let mut bob = FugueText::new("bob".to_string());
+        bob.merge(&alice).unwrap();
+
+        // Alice's selection covers "world"
+        let start = alice.cursor_at(6, Assoc::After).unwrap();
+        let end = alice.cursor_at(11, Assoc::Before).unwrap();
+
+        bob.insert(0, "Oh, ").unwrap();
+        bob.insert(15, "!").unwrap();
+        alice.merge(&bob).unwrap();
+
+        assert_eq!(alice.to_string(), "Oh, Hello world!");
+        assert_eq!(alice.resolve_cursor(&start), Some(10));
+        assert_eq!(alice.resolve_cursor(&end), Some(15));
+
+        // Deleting the selected text collapses the selection in place
+        bob.delete(10, 5).unwrap();
+        alice.merge(&bob).unwrap();
+        assert_eq!(alice.resolve_cursor(&start), Some(10));
+        assert_eq!(alice.resolve_cursor(&end), Some(10));
+    }
+
+    #[test]
+    fn test_cursor_at_document_edges() {
+        let mut text = FugueText::new("client1".to_string());
+        let start = text.cursor_at(0, Assoc::Before).unwrap();
+        let end = text.cursor_at(0, Assoc::After).unwrap();
+        assert_eq!(start, Cursor::start());
+        assert_eq!(end, Cursor::end());
+
+        text.insert(0, "abc").unwrap();
+        assert_eq!(text.resolve_cursor(&start), Some(0));
+        assert_eq!(text.resolve_cursor(&end), Some(3));
+        assert!(text.cursor_at(4, Assoc::After).is_err());
+
+        // A character this replica has not seen does not resolve
+        let unseen = Cursor {
+            id: Some(NodeId::new("client2".to_string(), 1, 0)),
+            assoc: Assoc::After,
+        };
+        assert_eq!(text.resolve_cursor(&unseen), None);
+    }
+
+    // ============================================================
+    // Operation-based sync
+    // ============================================================
+
+    #[test]
+    fn test_insert_op_roundtrip() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let mut text2 = FugueText::new("client2".to_string());
+
+        let op = text1.insert(0, "Hello").unwrap();
+        match &op {
+            FugueOp::Insert(block) => {
+                assert_eq!(block.id, NodeId::new("client1".to_string(), 5, 0));
+                assert_eq!(block.text, "Hello");
+            }
+            other => panic!("Expected insert, got {:?}", other),
+        }
+
+        text2.apply_op(&op).unwrap();
+        assert_eq!(text2.to_string(), "Hello");
+        assert_eq!(text2.clock(), 5);
+    }
+
+    #[test]
+    fn test_delete_op_lists_ranges() {
+        let mut text1 = FugueText::new("client1".to_string());
+        text1.insert(0, "Hello").unwrap(); // Clocks 1-5
+        text1.insert(5, " World").unwrap(); // Clocks 6-11
+
+        let mut text2 = text1.clone();
+
+        // "lo Wo" spans both blocks but only one run of clocks
+        let op = text1.delete(3, 5).unwrap();
+        assert_eq!(
+            op,
+            FugueOp::Delete {
+                id: NodeId::new("client1".to_string(), 12, 0),
+                ranges: vec![NodeIdRange::new("client1".to_string(), 4, 8)],
+            }
+        );
+
+        text2.apply_op(&op).unwrap();
+        assert_eq!(text1.to_string(), "Helrld");
+        assert_eq!(text2.to_string(), "Helrld");
+    }
+
+    #[test]
+    fn test_mid_block_insert_converges() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let mut text2 = FugueText::new("client2".to_string());
+
+        let base = text1.insert(0, "The quick brown fox").unwrap();
+        text2.apply_op(&base).unwrap();
+
+        let op = text1.insert(4, "very ").unwrap();
+        assert_eq!(text1.to_string(), "The very quick brown fox");
+
+        // Via operation...
+        text2.apply_op(&op).unwrap();
+        assert_eq!(text2.to_string(), "The very quick brown fox");
+
+        // ...and via whole-state merge
+        let mut text3 = FugueText::new("client3".to_string());
+        text3.merge(&text1).unwrap();
+        assert_eq!(text3.to_string(), "The very quick brown fox");
+    }
+
+    #[test]
+    fn test_apply_op_buffers_missing_origins() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let hello = text1.insert(0, "Hello").unwrap();
+        let world = text1.insert(5, " World").unwrap();
+        let delete = text1.delete(0, 1).unwrap();
+
+        let mut text2 = FugueText::new("client2".to_string());
+
+        // Delete and " World" both depend on "Hello"
+        text2.apply_op(&delete).unwrap();
+        text2.apply_op(&world).unwrap();
+        assert_eq!(text2.to_string(), "");
+        assert_eq!(text2.pending_ops(), 2);
+
+        text2.apply_op(&hello).unwrap();
+        assert_eq!(text2.to_string(), "ello World");
+        assert_eq!(text2.pending_ops(), 0);
+    }
+
+    #[test]
+    fn test_apply_op_is_idempotent() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let insert = text1.insert(0, "Hello").unwrap();
+        let delete = text1.delete(1, 2).unwrap();
+
+        let mut text2 = FugueText::new("client2".to_string());
+        for _ in 0..2 {
+            text2.apply_op(&insert).unwrap();
+            text2.apply_op(&delete).unwrap();
+        }
+
+        assert_eq!(text2.to_string(), "Hlo");
+        assert_eq!(text2.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_op_rejects_malformed() {
+        let mut text = FugueText::new("client1".to_string());
+        let block = FugueBlock::new(
+            NodeId::new("client2".to_string(), 1, 0),
+            "abc".to_string(),
+            None,
+            None,
+        );
+
+        let result = text.apply_op(&FugueOp::Insert(block));
+        assert!(matches!(result, Err(TextError::InvalidOperation(_))));
+        assert_eq!(text.pending_ops(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_ops_converge_in_any_order() {
+        let mut base = FugueText::new("base".to_string());
+        let init = base.insert(0, "abcdef").unwrap();
+
+        let mut text1 = FugueText::new("client1".to_string());
+        let mut text2 = FugueText::new("client2".to_string());
+        let mut text3 = FugueText::new("client3".to_string());
+        for text in [&mut text1, &mut text2, &mut text3] {
+            text.apply_op(&init).unwrap();
+        }
+
+        let ops = vec![
+            text1.insert(3, "XY").unwrap(),
+            text2.insert(3, "12").unwrap(),
+            text3.delete(2, 2).unwrap(),
+            text3.insert(2, "_").unwrap(),
+        ];
+
+        let mut forward = FugueText::new("r1".to_string());
+        let mut backward = FugueText::new("r2".to_string());
+        forward.apply_op(&init).unwrap();
+        backward.apply_op(&init).unwrap();
+        for op in &ops {
+            forward.apply_op(op).unwrap();
+        }
+        for op in ops.iter().rev() {
+            backward.apply_op(op).unwrap();
+        }
+
+        text1.merge(&text2).unwrap();
+        text1.merge(&text3).unwrap();
+
+        assert_eq!(forward.to_string(), backward.to_string());
+        assert_eq!(forward.to_string(), text1.to_string());
+        assert_eq!(forward.to_string(), "ab_XY12ef");
+    }
+
+    #[test]
+    fn test_concurrent_typing_does_not_interleave() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let mut text2 = FugueText::new("client2".to_string());
+
+        // Both type a word one keystroke at a time at the same spot
+        let mut ops = Vec::new();
+        for (i, c) in ["f", "o", "o"].iter().enumerate() {
+            ops.push(text1.insert(i, c).unwrap());
+        }
+        for (i, c) in ["b", "a", "r"].iter().enumerate() {
+            ops.push(text2.insert(i, c).unwrap());
+        }
+
+        let mut merged = FugueText::new("client3".to_string());
+        for op in &ops {
+            merged.apply_op(op).unwrap();
+        }
+
+        let result = merged.to_string();
+        assert!(result == "foobar" || result == "barfoo", "got {}", result);
+    }
+
+    #[test]
+    fn test_state_vector_tracks_inserts_and_deletes() {
+        let mut text1 = FugueText::new("client1".to_string());
+        let mut text2 = FugueText::new("client2".to_string());
+
+        text1.insert(0, "Hello").unwrap(); // Clocks 1-5
+        text2.merge(&text1).unwrap();
+        text2.delete(0, 1).unwrap(); // Delete ID client2@6
+
+        let vector = text2.state_vector();
+        assert_eq!(vector.get(&"client1".to_string()), 5);
+        assert_eq!(vector.get(&"client2".to_string()), 6);
+        assert_eq!(vector.get(&"client3".to_string()), 0);
+    }
+
+    #[test]
+    fn test_encode_diff_sends_only_missing_ops() {
+        let mut server = FugueText::new("server".to_string());
+        server.insert(0, "Hello World").unwrap();
+        server.delete(5, 1).unwrap();
+
+        let mut client = FugueText::new("client".to_string());
+        client.merge(&server).unwrap();
+        let since = client.state_vector();
+
+        // Nothing to send to an up-to-date peer
+        assert!(server.encode_diff(&since).is_empty());
+
+        // Offline edits on both sides
+        server.insert(0, ">> ").unwrap();
+        server.delete(8, 5).unwrap();
+        client.insert(10, "!").unwrap();
+
+        let diff = server.encode_diff(&since);
+        assert_eq!(diff.len(), 2);
+        assert!(matches!(&diff[0], FugueOp::Insert(block) if block.text == ">> "));
+        assert!(matches!(&diff[1], FugueOp::Delete { .. }));
+
+        for op in &diff {
+            client.apply_op(op).unwrap();
+        }
+        for op in client.encode_diff(&server.state_vector()) {
+            server.apply_op(&op).unwrap();
+        }
+
+        assert_eq!(client.to_string(), ">> Hello!");
+        assert_eq!(server.to_string(), client.to_string());
+    }
+
+    #[test]
+    fn test_encode_diff_sends_block_tail() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "Hello").unwrap();
+
+        let mut since = StateVector::new();
+        since.update(&"client1".to_string(), 3);
+
+        let diff = text.encode_diff(&since);
+        match diff.as_slice() {
+            [FugueOp::Insert(block)] => {
+                assert_eq!(block.text, "lo");
+                assert_eq!(
+                    block.left_origin,
+                    Some(NodeId::new("client1".to_string(), 3, 0))
+                );
+            }
+            other => panic!("Expected one insert, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_delete_log_survives_serialization() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "Hello").unwrap();
+        text.delete(0, 2).unwrap();
+
+        let json = serde_json::to_string(&text).unwrap();
+        let restored: FugueText = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.state_vector(), text.state_vector());
+        assert_eq!(
+            restored.encode_diff(&StateVector::new()),
+            text.encode_diff(&StateVector::new())
+        );
+    }
+
+    #[test]
+    fn test_gc_collects_stable_tombstones() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "Hello cruel world").unwrap();
+        text.delete(5, 6).unwrap();
+
+        let stats = text.gc(&text.state_vector());
+
+        assert_eq!(stats.blocks_collected, 1);
+        assert_eq!(stats.deletes_pruned, 1);
+        assert!(stats.bytes_reclaimed >= " cruel".len());
+        assert!(text.deletes.is_empty());
+        assert_eq!(text.to_string(), "Hello world");
+        assert!(text.blocks.values().any(|block| block.is_collected()));
+
+        // Nothing left to collect
+        assert_eq!(text.gc(&text.state_vector()), GcStats::default());
+    }
+
+    #[test]
+    fn test_gc_respects_frontier() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "Hello").unwrap(); // Clocks 1-5
+        let frontier = text.state_vector();
+        text.delete(0, 1).unwrap(); // Delete ID client1@6
+
+        // The deletion is not stable yet
+        assert_eq!(text.gc(&frontier), GcStats::default());
+        assert!(text.blocks.values().all(|block| !block.is_collected()));
+    }
+
+    #[test]
+    fn test_gc_folds_split_tombstones() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "abcdef").unwrap();
+        text.delete(1, 1).unwrap(); // "b"
+        text.delete(1, 1).unwrap(); // "c"
+        assert_eq!(text.blocks.len(), 4);
+
+        let stats = text.gc(&text.state_vector());
+
+        assert_eq!(stats.blocks_collected, 2);
+        assert_eq!(stats.blocks_merged, 1);
+        assert_eq!(text.blocks.len(), 3);
+        assert_eq!(text.to_string(), "adef");
+
+        // Characters inside the folded tombstone still resolve
+        let c = NodeId::new("client1".to_string(), 3, 0);
+        assert_eq!(text.block_containing(&c), Some(c.clone()));
+        assert_eq!(text.get_position_of_node_id(&c), None);
+    }
+
+    #[test]
+    fn test_gc_keeps_merge_correct() {
+        let mut alice = FugueText::new("alice".to_string());
+        let mut bob = FugueText::new("bob".to_string());
+
+        alice.insert(0, "Hello cruel world").unwrap();
+        alice.delete(5, 6).unwrap();
+        alice.delete(0, 1).unwrap();
+        bob.merge(&alice).unwrap();
+
+        let mut frontier = alice.state_vector();
+        frontier.merge(&bob.state_vector());
+        alice.gc(&frontier);
+
+        // Bob never collected and edits right next to the tombstones
+        let comma = bob.insert(4, ",").unwrap();
+        let bang = alice.insert(10, "!").unwrap();
+        alice.apply_op(&comma).unwrap();
+        bob.apply_op(&bang).unwrap();
+        assert_eq!(alice.to_string(), "ello, world!");
+        assert_eq!(bob.to_string(), alice.to_string());
+
+        // A replica bootstrapped from the collected state syncs with both
+        let json = serde_json::to_string(&alice).unwrap();
+        let mut carol: FugueText = serde_json::from_str(&json).unwrap();
+        carol.merge(&bob).unwrap();
+        bob.merge(&carol).unwrap();
+        assert_eq!(carol.to_string(), "ello, world!");
+        assert_eq!(bob.to_string(), carol.to_string());
+    }
+
+    #[test]
+    fn test_gc_folded_state_roundtrips() {
+        let mut alice = FugueText::new("alice".to_string());
+        let mut bob = FugueText::new("bob".to_string());
+
+        alice.insert(0, "ab").unwrap(); // alice@1-2
+        bob.merge(&alice).unwrap();
+        alice.insert(2, "cd").unwrap(); // alice@3-4, chained to "ab"
+        bob.insert(0, "X").unwrap(); // bob@3, right origin alice@1
+
+        alice.merge(&bob).unwrap();
+        alice.delete(1, 4).unwrap();
+        bob.merge(&alice).unwrap();
+
+        let stats = alice.gc(&alice.state_vector());
+        assert_eq!(stats.blocks_merged, 1);
+
+        // The folded tombstone is keyed alice@4, after bob@3 which
+        // references its first character
+        let json = serde_json::to_string(&alice).unwrap();
+        let restored: FugueText = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_string(), "X");
+        assert_eq!(restored.pending_ops(), 0);
+
+        bob.merge(&restored).unwrap();
+        assert_eq!(bob.to_string(), "X");
+    }
+
+    #[test]
+    fn test_deserialize_rebuilds_document_order() {
+        let mut text = FugueText::new("client1".to_string());
+        text.insert(0, "The quick brown fox").unwrap();
+        text.insert(4, "very ").unwrap();
+        text.delete(0, 4).unwrap();
+
+        let json = serde_json::to_string(&text).unwrap();
+        let restored: FugueText = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.to_string(), "very quick brown fox");
+        assert_eq!(restored.len(), text.len());
+        assert_eq!(restored.clock(), text.clock());
+
+        let node_id = text.get_node_id_at_position(3).unwrap();
+        assert_eq!(restored.get_position_of_node_id(&node_id), Some(3));
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,114 @@
+//! Common interface of the CRDTs
+//!
+//! Every CRDT here converges the same way: replicas exchange their state,
+//! or just the part a peer is missing, and merge it. [`Crdt`] captures that
+//! so containers such as `ORMap` can hold any of them as values and merge
+//! them recursively.
+//!
+//! # Deltas
+//!
+//! Shipping whole states gets expensive as they grow. A replica can
+//! instead send its peer's [`Crdt::version`] to the other side, which
+//! answers with [`Crdt::delta_since`]: only what the peer has not seen.
+//! Applying the delta leaves the peer as if it had merged the full state.
+
+use crate::error::{Result, SyncError};
+use crate::sync::{LWWField, Timestamp, VectorClock};
+use crate::ClientID;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+/// A state-based CRDT that can also exchange deltas
+pub trait Crdt: Clone + Serialize + DeserializeOwned {
+    /// What a replica has seen, to ask for a delta with
+    type Version: Clone + Default + PartialEq + Serialize + DeserializeOwned;
+
+    /// Changes a replica at some version is missing
+    type Delta: Clone + Serialize + DeserializeOwned;
+
+    /// Create an empty value written to as `replica_id`
+    fn new_replica(replica_id: ClientID) -> Self;
+
+    /// Merge another replica's state into this one
+    fn merge(&mut self, other: &Self);
+
+    /// What this replica has seen
+    fn version(&self) -> Self::Version;
+
+    /// Changes a replica at `since` is missing
+    ///
+    /// Extra changes are harmless: applying a delta is idempotent.
+    fn delta_since(&self, since: &Self::Version) -> Self::Delta;
+
+    /// Apply changes from [`Crdt::delta_since`]
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidOperation` if the delta is malformed.
+    fn apply_delta(&mut self, delta: &Self::Delta) -> Result<()>;
+
+    /// Serialize the state to JSON bytes
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| SyncError::SerializationError(e.to_string()))
+    }
+
+    /// Deserialize a state from [`Crdt::to_bytes`]
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).map_err(|e| SyncError::DeserializationError(e.to_string()))
+    }
+}
+
+/// LWW register: the newest write wins
+///
+/// Its version maps the writer to the write's clock, and its delta is the
+/// write itself, or nothing if the peer has it.
+impl Crdt for LWWField {
+    type Version = VectorClock;
+    type Delta = Option<LWWField>;
+
+    fn new_replica(_replica_id: ClientID) -> Self {
+        LWWField::new(JsonValue::Null, Timestamp::new(0, ClientID::new()))
+    }
+
+    fn merge(&mut self, other: &Self) {
+        *self = LWWField::merge(self, other);
+    }
+
+    fn version(&self) -> VectorClock {
+        let mut version = VectorClock::new();
+        version.update(&self.timestamp.client_id, self.timestamp.clock);
+        version
+    }
+
+    fn delta_since(&self, since: &VectorClock) -> Option<LWWField> {
+        (self.timestamp.clock > since.get(&self.timestamp.client_id)).then(|| self.clone())
+    }
+
+    fn apply_delta(&mut self, delta: &Option<LWWField>) -> Result<()> {
+        if let Some(field) = delta {
+            Crdt::merge(self, field);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lww_register_deltas() {
+        let mut local = LWWField::new_replica("a".to_string());
+        let remote = LWWField::new(json!("hi"), Timestamp::new(3, "b".to_string()));
+
+        let delta = remote.delta_since(&local.version());
+        local.apply_delta(&delta).unwrap();
+        assert_eq!(local, remote);
+        assert_eq!(remote.delta_since(&local.version()), None);
+
+        let bytes = local.to_bytes().unwrap();
+        assert_eq!(LWWField::from_bytes(&bytes).unwrap(), local);
+    }
+}
@@ -8,14 +8,39 @@
 //! - Determinism: Same inputs always produce same output
 //! - Idempotence: Applying operation twice has no effect
 //! - Commutativity: Order of merges doesn't matter
+//!
+//! # Nested paths
+//!
+//! Field paths address nested JSON (`user.address.city`, `items[0]`, see
+//! [`crate::path`]). Each written path is its own LWW entry, so concurrent
+//! edits to sibling leaves of one object both survive. A write to a parent
+//! covers its whole subtree: children written before it are dropped, while
+//! children written after it are layered on top when building the JSON view.
+//! When children disagree on their parent's shape (`a[0]` and `a.b`), the
+//! newest one decides whether it is an array or an object.
+//!
+//! # Multi-value fields
+//!
+//! LWW keeps one of two concurrent writes and drops the other. Fields where
+//! users need to see such conflicts (an assignee, a status) can be switched
+//! to `FieldMode::MultiValue`: they are stored as an [`MVRegister`] that
+//! keeps all concurrent values until a later write replaces them. Such
+//! fields are leaves: they are layered over the LWW fields in the JSON view
+//! and are not covered by writes to their parents.
 
-use crate::sync::{Timestamp, VectorClock};
+use crate::error::{Result, SyncError};
+use crate::path::{self, PathSegment};
+use crate::sync::policy::redact_value;
+use crate::sync::{
+    ConflictResolver, FieldMerge, FieldPolicy, HybridLogicalClock, MVEntry, MVRegister,
+    MergeDecision, MergeReport, TimeSource, Timestamp, VectorClock, Winner,
+};
 use crate::{ClientID, DocumentID, FieldPath};
-// TODO: Will be used when implementing full error handling
-// use crate::error::{Result, SyncError};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
 
 /// A document with field-level LWW conflict resolution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,8 +48,16 @@ pub struct Document {
     /// Unique document identifier
     pub id: DocumentID,
 
-    /// Document fields with LWW metadata
-    pub fields: HashMap<FieldPath, Field>,
+    /// Document fields with LWW metadata, keyed by canonical path
+    ///
+    /// Ordered so that a path's descendants form contiguous ranges.
+    pub fields: BTreeMap<FieldPath, Field>,
+
+    /// Multi-value fields, keyed by canonical path
+    ///
+    /// A path is in `FieldMode::MultiValue` once it has a register here.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registers: BTreeMap<FieldPath, MVRegister>,
 
     /// Vector clock for causality tracking
     pub version: VectorClock,
@@ -38,6 +71,65 @@ pub struct Field {
 
     /// Timestamp for LWW conflict resolution
     pub timestamp: Timestamp,
+
+    /// Tombstone flag - deleted fields are marked, not removed
+    ///
+    /// A deleted field keeps its timestamp so the delete takes part in LWW
+    /// merging like any other write. Removing the entry instead would let an
+    /// older replica resurrect the field on the next merge.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+
+    /// Parts of the value were cut out by `Delta::redact`
+    ///
+    /// The timestamp is the original write's, so merging the value back
+    /// would tie with the full one. A redacted value loses such ties and
+    /// never replaces the hidden parts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+}
+
+impl Field {
+    /// Create a live field with a value and timestamp
+    pub fn new(value: JsonValue, timestamp: Timestamp) -> Self {
+        Self {
+            value,
+            timestamp,
+            deleted: false,
+            redacted: false,
+        }
+    }
+
+    /// Create a tombstone recording a delete at the given timestamp
+    pub fn tombstone(timestamp: Timestamp) -> Self {
+        Self {
+            value: JsonValue::Null,
+            timestamp,
+            deleted: true,
+            redacted: false,
+        }
+    }
+
+    /// Check if this field is a tombstone
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// Check if parts of the value were redacted
+    pub fn is_redacted(&self) -> bool {
+        self.redacted
+    }
+}
+
+/// How concurrent writes to a field are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FieldMode {
+    /// The newest write wins (by timestamp, then client ID)
+    #[default]
+    Lww,
+
+    /// Concurrent writes are all kept until a later write replaces them
+    MultiValue,
 }
 
 impl Document {
@@ -45,7 +137,8 @@ impl Document {
     pub fn new(id: DocumentID) -> Self {
         Self {
             id,
-            fields: HashMap::new(),
+            fields: BTreeMap::new(),
+            registers: BTreeMap::new(),
             version: VectorClock::new(),
         }
     }
@@ -54,6 +147,9 @@ impl Document {
     ///
     /// This method uses LWW merge logic, so if there's already a value
     /// with a newer timestamp, it won't be overwritten.
+    ///
+    /// On a multi-value field the clock is ignored: the write replaces all
+    /// values this replica has seen.
     pub fn set_field(
         &mut self,
         field_path: FieldPath,
@@ -62,15 +158,129 @@ impl Document {
         client_id: ClientID,
     ) {
         let timestamp = Timestamp::new(clock, client_id);
-        let new_field = Field { value, timestamp };
+        let new_field = Field::new(value, timestamp);
 
-        // Use merge_field to respect LWW semantics
-        self.merge_field(field_path, new_field);
+        // Goes through merge_field to respect LWW semantics
+        self.write_field(field_path, new_field);
     }
 
     /// Get a field value
+    ///
+    /// Returns the value last written at exactly this path, or None for
+    /// paths that were never written or have been deleted. Use `get_value`
+    /// to read a path through the merged nested view.
+    ///
+    /// A multi-value field with concurrent values returns one of them (see
+    /// `MVRegister::value`); use `get_field_values` to get them all.
     pub fn get_field(&self, field_path: &FieldPath) -> Option<&JsonValue> {
-        self.fields.get(field_path).map(|f| &f.value)
+        if let Some(register) = self.register(field_path) {
+            return register.value();
+        }
+
+        self.fields
+            .get(field_path)
+            .or_else(|| self.fields.get(&path::normalize(field_path)))
+            .filter(|f| !f.is_deleted())
+            .map(|f| &f.value)
+    }
+
+    /// Get all current values of a field
+    ///
+    /// More than one value means concurrent writes to a multi-value field
+    /// that no later write has resolved yet. LWW fields have at most one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::document::FieldMode;
+    /// use synckit_core::Document;
+    /// use serde_json::json;
+    ///
+    /// let mut alice = Document::new("task-1".to_string());
+    /// alice.set_field_mode("assignee".to_string(), FieldMode::MultiValue).unwrap();
+    /// let mut bob = alice.clone();
+    ///
+    /// alice.set_field("assignee".to_string(), json!("alice"), 1, "alice".to_string());
+    /// bob.set_field("assignee".to_string(), json!("bob"), 1, "bob".to_string());
+    /// alice.merge(&bob);
+    ///
+    /// let assignee = "assignee".to_string();
+    /// assert_eq!(alice.get_field_values(&assignee), vec![&json!("alice"), &json!("bob")]);
+    ///
+    /// // Picking one resolves the conflict everywhere
+    /// alice.set_field(assignee.clone(), json!("bob"), 2, "alice".to_string());
+    /// bob.merge(&alice);
+    /// assert_eq!(bob.get_field_values(&assignee), vec![&json!("bob")]);
+    /// ```
+    pub fn get_field_values(&self, field_path: &FieldPath) -> Vec<&JsonValue> {
+        match self.register(field_path) {
+            Some(register) => register.values().collect(),
+            None => self.get_field(field_path).into_iter().collect(),
+        }
+    }
+
+    /// Get how concurrent writes to a field are resolved
+    pub fn field_mode(&self, field_path: &str) -> FieldMode {
+        match self.register(field_path) {
+            Some(_) => FieldMode::MultiValue,
+            None => FieldMode::Lww,
+        }
+    }
+
+    /// Choose how concurrent writes to a field are resolved
+    ///
+    /// Fields are LWW unless switched to `FieldMode::MultiValue`. The mode
+    /// travels with the document, so every replica that merges it treats
+    /// the field the same way. Switch before the field is first written:
+    /// an LWW value already at the path is hidden by the (empty) register.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidOperation` when switching a multi-value
+    /// field back to LWW, since replicas would disagree on its value.
+    pub fn set_field_mode(&mut self, field_path: FieldPath, mode: FieldMode) -> Result<()> {
+        let field_path = path::normalize(&field_path);
+
+        match mode {
+            FieldMode::MultiValue => {
+                self.registers.entry(field_path).or_default();
+                Ok(())
+            }
+            FieldMode::Lww if self.registers.contains_key(&field_path) => {
+                Err(SyncError::InvalidOperation(format!(
+                    "{} is a multi-value field and cannot be switched back to LWW",
+                    field_path
+                )))
+            }
+            FieldMode::Lww => Ok(()),
+        }
+    }
+
+    /// Get the value at a path in the nested JSON view
+    ///
+    /// Unlike `get_field`, this resolves paths inside values written at a
+    /// parent (`user.name` inside a `user` object) and includes children
+    /// written after the parent. Only the writes under the path's top-level
+    /// key are layered, which builds that key exactly as `to_json` does.
+    pub fn get_value(&self, field_path: &str) -> Option<JsonValue> {
+        let field_path = path::normalize(field_path);
+        let segments = path::parse(&field_path);
+        if segments.is_empty() {
+            return Some(self.to_json());
+        }
+
+        let top = path::format(&segments[..1]);
+        let json = self.build_json(related(&self.fields, &top), related(&self.registers, &top));
+        let mut node = &json;
+
+        for segment in segments {
+            node = match segment {
+                PathSegment::Key(key) => node.get(key)?,
+                PathSegment::Index(index) => node.get(index)?,
+            };
+        }
+
+        Some(node.clone())
     }
 
     /// Merge a remote field using LWW algorithm
@@ -81,45 +291,67 @@ impl Document {
     /// Comparison order:
     /// 1. Higher timestamp wins
     /// 2. If timestamps equal, higher client_id wins
-    /// 3. If both equal (duplicate), use value comparison for determinism
+    /// 3. If both equal (duplicate), a tombstone wins, then value comparison
+    ///    for determinism
+    ///
+    /// Nested paths: a write is ignored if an ancestor path holds a write at
+    /// least as new, and an accepted write drops descendants it covers.
     pub fn merge_field(&mut self, field_path: FieldPath, remote_field: Field) -> bool {
-        match self.fields.get(&field_path) {
-            Some(local_field) => {
-                // Compare timestamps for LWW
-                match remote_field.timestamp.compare_lww(&local_field.timestamp) {
-                    std::cmp::Ordering::Greater => {
-                        // Remote wins (newer timestamp or higher client_id)
-                        self.fields.insert(field_path, remote_field);
-                        true
-                    }
-                    std::cmp::Ordering::Less => {
-                        // Local wins (newer timestamp or higher client_id)
-                        false
-                    }
-                    std::cmp::Ordering::Equal => {
-                        // Exact same timestamp - use value comparison for determinism
-                        // This handles the edge case where same client writes same timestamp
-                        // with different values (which shouldn't happen in practice, but
-                        // we handle it for total ordering)
-                        let local_json = serde_json::to_string(&local_field.value).unwrap();
-                        let remote_json = serde_json::to_string(&remote_field.value).unwrap();
-
-                        if remote_json > local_json {
-                            self.fields.insert(field_path, remote_field);
-                            true
-                        } else {
-                            // Keep local (or keep existing if values are also equal)
-                            false
-                        }
-                    }
-                }
-            }
-            None => {
-                // No local value, remote wins
-                self.fields.insert(field_path, remote_field);
-                true
-            }
+        let field_path = if path::is_canonical(&field_path) {
+            field_path
+        } else {
+            path::normalize(&field_path)
+        };
+
+        let (_, remote_wins) = self.decide(&field_path, &remote_field, None);
+        if remote_wins {
+            self.store(field_path, remote_field);
         }
+
+        remote_wins
+    }
+
+    /// Merge a remote field and report the decision
+    ///
+    /// Same as `merge_field`, but a `resolver` may pick the winner instead
+    /// of LWW, and the result says which write was kept and why. Returns
+    /// None if the remote write is identical to the local one.
+    pub fn merge_field_with_report(
+        &mut self,
+        field_path: FieldPath,
+        remote_field: Field,
+        resolver: Option<&mut dyn ConflictResolver>,
+    ) -> Option<FieldMerge> {
+        let field_path = path::normalize(&field_path);
+        let local = self.fields.get(&field_path).cloned();
+        if local.as_ref() == Some(&remote_field) {
+            return None;
+        }
+
+        let (decision, took_remote) = self.decide(&field_path, &remote_field, resolver);
+        if took_remote {
+            self.store(field_path.clone(), remote_field.clone());
+        }
+
+        Some(FieldMerge {
+            path: field_path,
+            local,
+            remote: remote_field,
+            decision,
+            took_remote,
+        })
+    }
+
+    /// Merge a remote multi-value register
+    ///
+    /// Switches the field to `FieldMode::MultiValue` if it was not yet.
+    /// Returns true if the field's values changed.
+    pub fn merge_register(&mut self, field_path: FieldPath, remote: &MVRegister) -> bool {
+        let register = self
+            .registers
+            .entry(path::normalize(&field_path))
+            .or_default();
+        register.merge(remote)
     }
 
     /// Merge an entire remote document
@@ -136,6 +368,12 @@ impl Document {
             }
         }
 
+        for (field_path, remote_register) in &remote.registers {
+            if self.merge_register(field_path.clone(), remote_register) {
+                updated_count += 1;
+            }
+        }
+
         // Merge vector clocks
         self.version.merge(&remote.version);
 
@@ -143,29 +381,46 @@ impl Document {
     }
 
     /// Convert document to JSON for serialization
+    ///
+    /// Rebuilds the nested tree from the written paths. Parents sort before
+    /// their descendants, so newer child writes are layered over the value
+    /// written at the parent.
     pub fn to_json(&self) -> JsonValue {
-        let mut obj = serde_json::Map::new();
+        self.build_json(self.fields.iter(), self.registers.iter())
+    }
 
-        for (field_path, field) in &self.fields {
-            obj.insert(field_path.clone(), field.value.clone());
-        }
+    /// JSON view of the document as a client the policy restricts may read it
+    ///
+    /// Same as `to_json`, without the unreadable fields and the unreadable
+    /// parts of readable values (see [`crate::sync::policy`]).
+    pub fn to_json_redacted<P: FieldPolicy + ?Sized>(&self, policy: &P) -> JsonValue {
+        let JsonValue::Object(fields) = self.to_json() else {
+            unreachable!("to_json always returns an object");
+        };
 
-        JsonValue::Object(obj)
+        let readable = fields
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let field_path = path::format(&[PathSegment::Key(key.clone())]);
+                redact_value(policy, &self.id, &field_path, &value).map(|value| (key, value))
+            })
+            .collect();
+        JsonValue::Object(readable)
     }
 
-    /// Get all field paths
+    /// Get all field paths (deleted fields excluded)
     pub fn field_paths(&self) -> Vec<&FieldPath> {
-        self.fields.keys().collect()
+        self.visible_fields().collect()
     }
 
-    /// Check if document has any fields
+    /// Check if document has any fields (deleted fields excluded)
     pub fn is_empty(&self) -> bool {
-        self.fields.is_empty()
+        self.visible_fields().next().is_none()
     }
 
-    /// Get number of fields
+    /// Get number of fields (deleted fields excluded)
     pub fn field_count(&self) -> usize {
-        self.fields.len()
+        self.visible_fields().count()
     }
 
     /// Get document ID
@@ -179,13 +434,527 @@ impl Document {
     }
 
     /// Get all fields with metadata
-    pub fn fields(&self) -> &HashMap<FieldPath, Field> {
+    ///
+    /// Includes tombstones so callers can propagate deletes. Multi-value
+    /// fields are in `registers` instead.
+    pub fn fields(&self) -> &BTreeMap<FieldPath, Field> {
         &self.fields
     }
 
-    /// Delete a field
-    pub fn delete_field(&mut self, field_path: &FieldPath) {
-        self.fields.remove(field_path);
+    /// Get all multi-value fields
+    pub fn registers(&self) -> &BTreeMap<FieldPath, MVRegister> {
+        &self.registers
+    }
+
+    /// Delete a field (records a tombstone)
+    ///
+    /// The delete is an LWW write like `set_field`: it only takes effect if
+    /// its timestamp beats the current value, and a later concurrent set will
+    /// win over it after merging.
+    ///
+    /// On a multi-value field it removes the values this replica has seen;
+    /// values written concurrently survive it.
+    pub fn delete_field(&mut self, field_path: FieldPath, clock: u64, client_id: ClientID) {
+        let timestamp = Timestamp::new(clock, client_id);
+
+        // Goes through merge_field to respect LWW semantics
+        self.write_field(field_path, Field::tombstone(timestamp));
+    }
+
+    /// Set a field with a timestamp from a hybrid logical clock
+    ///
+    /// Unlike `set_field`, the caller does not pick a clock value: the
+    /// write is stamped with the current time, so it wins over every write
+    /// the clock has seen. Returns the timestamp used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::sync::HybridLogicalClock;
+    /// use synckit_core::Document;
+    /// use serde_json::json;
+    ///
+    /// let mut doc = Document::new("doc-1".to_string());
+    /// let mut clock = HybridLogicalClock::new("client1".to_string());
+    ///
+    /// doc.set_field_with_clock("title".to_string(), json!("Hello"), &mut clock);
+    /// assert_eq!(doc.get_field(&"title".to_string()), Some(&json!("Hello")));
+    /// ```
+    pub fn set_field_with_clock<S: TimeSource>(
+        &mut self,
+        field_path: FieldPath,
+        value: JsonValue,
+        clock: &mut HybridLogicalClock<S>,
+    ) -> Timestamp {
+        let timestamp = clock.now();
+        self.write_field(field_path, Field::new(value, timestamp.clone()));
+        timestamp
+    }
+
+    /// Delete a field with a timestamp from a hybrid logical clock
+    ///
+    /// Returns the timestamp used.
+    pub fn delete_field_with_clock<S: TimeSource>(
+        &mut self,
+        field_path: FieldPath,
+        clock: &mut HybridLogicalClock<S>,
+    ) -> Timestamp {
+        let timestamp = clock.now();
+        self.write_field(field_path, Field::tombstone(timestamp.clone()));
+        timestamp
+    }
+
+    /// Merge an entire remote document and report every field decision
+    ///
+    /// Same as `merge`, but returns what happened to each remote field that
+    /// differs from the local one, so overwritten local values can be
+    /// audited.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use synckit_core::sync::MergeDecision;
+    /// use synckit_core::Document;
+    /// use serde_json::json;
+    ///
+    /// let mut local = Document::new("doc-1".to_string());
+    /// local.set_field("title".to_string(), json!("Mine"), 1, "client1".to_string());
+    ///
+    /// let mut remote = Document::new("doc-1".to_string());
+    /// remote.set_field("title".to_string(), json!("Theirs"), 1, "client2".to_string());
+    ///
+    /// let report = local.merge_with_report(&remote);
+    /// let overwritten: Vec<_> = report.overwritten().collect();
+    /// assert_eq!(overwritten.len(), 1);
+    /// assert_eq!(overwritten[0].decision, MergeDecision::TieBrokenByClientId);
+    /// assert_eq!(overwritten[0].local.as_ref().unwrap().value, json!("Mine"));
+    /// ```
+    pub fn merge_with_report(&mut self, remote: &Document) -> MergeReport {
+        self.merge_reporting(remote, None)
+    }
+
+    /// Merge an entire remote document, letting `resolver` pick winners
+    ///
+    /// See [`ConflictResolver`] for the rules a resolver must follow for
+    /// replicas to converge.
+    pub fn merge_with_resolver(
+        &mut self,
+        remote: &Document,
+        resolver: &mut dyn ConflictResolver,
+    ) -> MergeReport {
+        self.merge_reporting(remote, Some(resolver))
+    }
+
+    fn merge_reporting(
+        &mut self,
+        remote: &Document,
+        mut resolver: Option<&mut dyn ConflictResolver>,
+    ) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        for (field_path, remote_field) in &remote.fields {
+            let resolver = resolver
+                .as_mut()
+                .map(|r| &mut **r as &mut dyn ConflictResolver);
+            report.fields.extend(self.merge_field_with_report(
+                field_path.clone(),
+                remote_field.clone(),
+                resolver,
+            ));
+        }
+
+        for (field_path, remote_register) in &remote.registers {
+            self.merge_register(field_path.clone(), remote_register);
+        }
+
+        self.version.merge(&remote.version);
+        report
+    }
+
+    /// Merge a remote document and observe its timestamps
+    ///
+    /// Same as `merge`, but afterwards the clock is ahead of every remote
+    /// write, so the next local write wins over them. Under
+    /// `DriftPolicy::Clamp`, remote writes beyond the drift limit are merged
+    /// with their timestamps moved back to it (see `HybridLogicalClock::clamp`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidTimestamp` if a remote write is stamped
+    /// too far in the future (see `HybridLogicalClock::check`). Nothing is
+    /// merged then. Values of multi-value fields are checked too, if they
+    /// were written with a timestamp.
+    pub fn merge_with_clock<S: TimeSource>(
+        &mut self,
+        remote: &Document,
+        clock: &mut HybridLogicalClock<S>,
+    ) -> Result<usize> {
+        let register_timestamps = remote
+            .registers
+            .values()
+            .flat_map(|register| register.entries())
+            .filter_map(|entry| entry.timestamp.as_ref());
+        let timestamps: Vec<&Timestamp> = remote
+            .fields
+            .values()
+            .map(|field| &field.timestamp)
+            .chain(register_timestamps)
+            .collect();
+
+        for timestamp in &timestamps {
+            clock.check(timestamp)?;
+        }
+        let clamped = timestamps.iter().any(|t| clock.clamp(t) != **t);
+        for timestamp in timestamps {
+            clock.observe(timestamp)?;
+        }
+
+        if !clamped {
+            return Ok(self.merge(remote));
+        }
+
+        let mut remote = remote.clone();
+        for field in remote.fields.values_mut() {
+            field.timestamp = clock.clamp(&field.timestamp);
+        }
+        for register in remote.registers.values_mut() {
+            let entries = register
+                .entries()
+                .iter()
+                .map(|entry| MVEntry {
+                    timestamp: entry.timestamp.as_ref().map(|t| clock.clamp(t)),
+                    ..entry.clone()
+                })
+                .collect();
+            *register = MVRegister::from_parts(entries, register.context().clone());
+        }
+        Ok(self.merge(&remote))
+    }
+
+    /// Decide whether a remote write wins over the local one at `field_path`
+    ///
+    /// This is the core LWW merge algorithm verified by TLA+; a resolver
+    /// can override it for fields that both replicas wrote. Returns the
+    /// reason and whether the remote write wins.
+    fn decide(
+        &self,
+        field_path: &str,
+        remote_field: &Field,
+        resolver: Option<&mut dyn ConflictResolver>,
+    ) -> (MergeDecision, bool) {
+        // A newer write to a parent already covers this path
+        if self.is_shadowed(field_path, &remote_field.timestamp) {
+            return (MergeDecision::KeptLocal, false);
+        }
+
+        let Some(local_field) = self.fields.get(field_path) else {
+            // No local value, remote wins
+            return (MergeDecision::TookRemote, true);
+        };
+
+        if let Some(winner) =
+            resolver.and_then(|r| r.resolve(field_path, local_field, remote_field))
+        {
+            return (MergeDecision::Resolved, winner == Winner::Remote);
+        }
+
+        let local = &local_field.timestamp;
+        let remote = &remote_field.timestamp;
+        match (
+            remote.clock.cmp(&local.clock),
+            remote.client_id.cmp(&local.client_id),
+        ) {
+            // Newer timestamp wins
+            (Ordering::Greater, _) => (MergeDecision::TookRemote, true),
+            (Ordering::Less, _) => (MergeDecision::KeptLocal, false),
+
+            // Same clock: higher client_id wins
+            (Ordering::Equal, Ordering::Greater) => (MergeDecision::TieBrokenByClientId, true),
+            (Ordering::Equal, Ordering::Less) => (MergeDecision::TieBrokenByClientId, false),
+
+            (Ordering::Equal, Ordering::Equal) => {
+                // Exact same timestamp - use value comparison for determinism
+                // This handles the edge case where same client writes same timestamp
+                // with different values (which shouldn't happen in practice, but
+                // we handle it for total ordering). A redacted copy of the write
+                // always loses to the full value.
+                let local_json = serde_json::to_string(&local_field.value).unwrap();
+                let remote_json = serde_json::to_string(&remote_field.value).unwrap();
+
+                // Keep local if not greater (or if values are also equal)
+                let remote_wins = (remote_field.deleted, !remote_field.redacted, remote_json)
+                    > (local_field.deleted, !local_field.redacted, local_json);
+                (MergeDecision::TieBrokenByValue, remote_wins)
+            }
+        }
+    }
+
+    /// Store a winning write, dropping the descendants it covers
+    fn store(&mut self, field_path: FieldPath, field: Field) {
+        self.prune_descendants(&field_path, &field.timestamp);
+        self.fields.insert(field_path, field);
+    }
+
+    /// Apply a local write, to the register if the field is multi-value
+    ///
+    /// Returns true if the document changed.
+    pub(crate) fn write_field(&mut self, field_path: FieldPath, field: Field) -> bool {
+        let Some(register) = self.registers.get_mut(&path::normalize(&field_path)) else {
+            return self.merge_field(field_path, field);
+        };
+
+        if field.is_deleted() {
+            let changed = !register.is_empty();
+            register.clear();
+            changed
+        } else {
+            register.set_at(field.value, field.timestamp);
+            true
+        }
+    }
+
+    /// Get the register of a multi-value field
+    fn register(&self, field_path: &str) -> Option<&MVRegister> {
+        self.registers
+            .get(field_path)
+            .or_else(|| self.registers.get(&path::normalize(field_path)))
+    }
+
+    /// Layer writes into a JSON tree, fields in path order, then registers
+    ///
+    /// Writes below one parent can disagree on its shape (`a[0]` wants an
+    /// array, `a.b` an object). The newest of them decides, and the older
+    /// writes that need the other shape are left out.
+    fn build_json<'a>(
+        &self,
+        fields: impl IntoIterator<Item = (&'a FieldPath, &'a Field)>,
+        registers: impl IntoIterator<Item = (&'a FieldPath, &'a MVRegister)>,
+    ) -> JsonValue {
+        let mut writes: Vec<(&FieldPath, &Field, Vec<PathSegment>)> = fields
+            .into_iter()
+            // Skip entries a newer parent write covers (only possible for
+            // documents assembled by hand, merge_field never stores them)
+            .filter(|(field_path, field)| !self.is_shadowed(field_path, &field.timestamp))
+            .map(|(field_path, field)| (field_path, field, path::parse(field_path)))
+            .collect();
+
+        // Newest first, each write claims the shape of every container on
+        // its path; a write that contradicts a claim is dropped
+        writes.sort_by(|a, b| b.1.timestamp.compare_lww(&a.1.timestamp));
+        let mut shapes: HashMap<&[PathSegment], Shape> = HashMap::new();
+        shapes.insert(&[], Shape::Object);
+        let mut kept: Vec<(&FieldPath, &Field, &[PathSegment])> = Vec::new();
+
+        for (field_path, field, segments) in &writes {
+            // Deletes claim nothing: one that misses the shape is a no-op
+            if field.is_deleted() {
+                kept.push((field_path, field, segments));
+                continue;
+            }
+
+            let mut claims: Vec<(&[PathSegment], Shape)> = (0..segments.len())
+                .map(|depth| (&segments[..depth], Shape::of_child(&segments[depth])))
+                .collect();
+            claims.push((&segments[..], Shape::of_value(&field.value)));
+
+            let fits = claims
+                .iter()
+                .all(|(prefix, shape)| shapes.get(prefix).is_none_or(|s| s == shape));
+            if fits {
+                shapes.extend(claims);
+                kept.push((field_path, field, segments));
+            }
+        }
+
+        // Parents before their children
+        kept.sort_by(|a, b| a.0.cmp(b.0));
+        let mut root = JsonValue::Object(serde_json::Map::new());
+        for (_, field, segments) in kept {
+            if field.is_deleted() {
+                remove_at(&mut root, segments);
+            } else {
+                write_at(&mut root, segments, field.value.clone());
+            }
+        }
+
+        for (field_path, register) in registers {
+            let segments = path::parse(field_path);
+            match register.value() {
+                Some(value) => write_at(&mut root, &segments, value.clone()),
+                None => remove_at(&mut root, &segments),
+            }
+        }
+
+        root
+    }
+
+    /// Check if an ancestor of `field_path` holds a write at least as new as `timestamp`
+    pub(crate) fn is_shadowed(&self, field_path: &str, timestamp: &Timestamp) -> bool {
+        path::ancestors(field_path).iter().any(|ancestor| {
+            self.fields
+                .get(ancestor)
+                .is_some_and(|f| f.timestamp.compare_lww(timestamp) != Ordering::Less)
+        })
+    }
+
+    /// Drop descendants of `field_path` that are not newer than `timestamp`
+    fn prune_descendants(&mut self, field_path: &str, timestamp: &Timestamp) {
+        let covered: Vec<FieldPath> = descendants(&self.fields, field_path)
+            .filter(|(_, f)| f.timestamp.compare_lww(timestamp) != Ordering::Greater)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in covered {
+            self.fields.remove(&path);
+        }
+    }
+
+    /// Iterate over paths of fields that hold a value, in path order
+    fn visible_fields(&self) -> impl Iterator<Item = &FieldPath> {
+        let lww = self
+            .fields
+            .iter()
+            .filter(|(path, field)| !field.is_deleted() && !self.registers.contains_key(*path))
+            .map(|(path, _)| path);
+        let multi_value = self
+            .registers
+            .iter()
+            .filter(|(_, register)| !register.is_empty())
+            .map(|(path, _)| path);
+
+        let mut paths: Vec<&FieldPath> = lww.chain(multi_value).collect();
+        paths.sort();
+        paths.into_iter()
+    }
+}
+
+/// Entries at a path, at its ancestors and at its descendants, in path order
+fn related<'a, T>(
+    map: &'a BTreeMap<FieldPath, T>,
+    field_path: &str,
+) -> impl Iterator<Item = (&'a FieldPath, &'a T)> {
+    let mut paths = path::ancestors(field_path);
+    paths.push(field_path.to_string());
+
+    paths
+        .into_iter()
+        .filter_map(|p| map.get_key_value(&p))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .chain(descendants(map, field_path))
+}
+
+/// Entries below a path, in path order
+pub(crate) fn descendants<'a, T>(
+    map: &'a BTreeMap<FieldPath, T>,
+    field_path: &str,
+) -> impl Iterator<Item = (&'a FieldPath, &'a T)> {
+    // Descendants start with "path." or "path[" - two contiguous key ranges
+    // ('/' and '\' are the characters right after '.' and '[')
+    let ranges = [
+        (format!("{}.", field_path), format!("{}/", field_path)),
+        (format!("{}[", field_path), format!("{}\\", field_path)),
+    ];
+
+    ranges.into_iter().flat_map(move |(start, end)| {
+        map.range::<str, _>((
+            Bound::Included(start.as_str()),
+            Bound::Excluded(end.as_str()),
+        ))
+    })
+}
+
+/// Shape of a node in the JSON view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Object,
+    Array,
+    Leaf,
+}
+
+impl Shape {
+    /// Shape of the container a path segment goes into
+    fn of_child(segment: &PathSegment) -> Self {
+        match segment {
+            PathSegment::Key(_) => Shape::Object,
+            PathSegment::Index(_) => Shape::Array,
+        }
+    }
+
+    /// Shape of a written value
+    fn of_value(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Object(_) => Shape::Object,
+            JsonValue::Array(_) => Shape::Array,
+            _ => Shape::Leaf,
+        }
+    }
+}
+
+/// Write a value at a nested path, creating (or replacing) containers on the way
+///
+/// Array slots past the end are padded with nulls.
+fn write_at(root: &mut JsonValue, segments: &[PathSegment], value: JsonValue) {
+    let mut node = root;
+
+    for segment in segments {
+        node = match segment {
+            PathSegment::Key(key) => {
+                if !node.is_object() {
+                    *node = JsonValue::Object(serde_json::Map::new());
+                }
+                node.as_object_mut()
+                    .unwrap()
+                    .entry(key.clone())
+                    .or_insert(JsonValue::Null)
+            }
+            PathSegment::Index(index) => {
+                if !node.is_array() {
+                    *node = JsonValue::Array(Vec::new());
+                }
+                let items = node.as_array_mut().unwrap();
+                if items.len() <= *index {
+                    items.resize(index + 1, JsonValue::Null);
+                }
+                &mut items[*index]
+            }
+        };
+    }
+
+    *node = value;
+}
+
+/// Remove the value at a nested path (no-op if the path does not exist)
+///
+/// Deleted array slots are set to null so sibling indices stay stable.
+fn remove_at(root: &mut JsonValue, segments: &[PathSegment]) {
+    let Some((last, parents)) = segments.split_last() else {
+        return;
+    };
+
+    let mut node = root;
+    for segment in parents {
+        let next = match segment {
+            PathSegment::Key(key) => node.get_mut(key),
+            PathSegment::Index(index) => node.get_mut(*index),
+        };
+        match next {
+            Some(next) => node = next,
+            None => return,
+        }
+    }
+
+    match last {
+        PathSegment::Key(key) => {
+            if let Some(obj) = node.as_object_mut() {
+                obj.remove(key);
+            }
+        }
+        PathSegment::Index(index) => {
+            if let Some(slot) = node.get_mut(*index) {
+                *slot = JsonValue::Null;
+            }
+        }
     }
 }
 
@@ -235,6 +1004,8 @@ mod tests {
         let remote_field = Field {
             value: json!("Remote Title"),
             timestamp: Timestamp::new(2, "client2".to_string()),
+            deleted: false,
+            redacted: false,
         };
 
         let updated = doc.merge_field("title".to_string(), remote_field);
@@ -262,6 +1033,8 @@ mod tests {
         let remote_field = Field {
             value: json!("Remote Title"),
             timestamp: Timestamp::new(1, "client2".to_string()),
+            deleted: false,
+            redacted: false,
         };
 
         let updated = doc.merge_field("title".to_string(), remote_field);
@@ -289,6 +1062,8 @@ mod tests {
         let remote_field = Field {
             value: json!("Remote Title"),
             timestamp: Timestamp::new(1, "client2".to_string()),
+            deleted: false,
+            redacted: false,
         };
 
         let updated = doc.merge_field("title".to_string(), remote_field);
@@ -368,6 +1143,344 @@ mod tests {
         assert_eq!(json["count"], json!(42));
     }
 
+    #[test]
+    fn test_delete_field_hides_value() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field("count".to_string(), json!(42), 1, "client1".to_string());
+
+        doc.delete_field("title".to_string(), 2, "client1".to_string());
+
+        assert_eq!(doc.get_field(&"title".to_string()), None);
+        assert_eq!(doc.field_count(), 1);
+        assert_eq!(doc.field_paths(), vec![&"count".to_string()]);
+        assert_eq!(doc.to_json(), json!({ "count": 42 }));
+
+        // Tombstone is kept with its timestamp
+        let tombstone = &doc.fields()["title"];
+        assert!(tombstone.is_deleted());
+        assert_eq!(
+            tombstone.timestamp,
+            Timestamp::new(2, "client1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_delete_survives_merge_with_older_replica() {
+        let mut doc1 = Document::new("doc-123".to_string());
+        doc1.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            1,
+            "client1".to_string(),
+        );
+        let stale = doc1.clone();
+
+        doc1.delete_field("title".to_string(), 2, "client1".to_string());
+
+        // Stale replica still has the old value - merging it must not resurrect it
+        let updated = doc1.merge(&stale);
+        assert_eq!(updated, 0);
+        assert_eq!(doc1.get_field(&"title".to_string()), None);
+
+        // And the stale replica learns about the delete
+        let mut stale = stale;
+        stale.merge(&doc1);
+        assert_eq!(stale.get_field(&"title".to_string()), None);
+        assert!(stale.is_empty());
+    }
+
+    #[test]
+    fn test_delete_older_than_value_is_ignored() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            5,
+            "client1".to_string(),
+        );
+
+        doc.delete_field("title".to_string(), 3, "client2".to_string());
+
+        assert_eq!(doc.get_field(&"title".to_string()), Some(&json!("Hello")));
+    }
+
+    #[test]
+    fn test_concurrent_set_and_delete_converge() {
+        let mut base = Document::new("doc-123".to_string());
+        base.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            1,
+            "client1".to_string(),
+        );
+
+        // client1 deletes, client2 concurrently overwrites with a later clock
+        let mut replica1 = base.clone();
+        replica1.delete_field("title".to_string(), 2, "client1".to_string());
+
+        let mut replica2 = base.clone();
+        replica2.set_field(
+            "title".to_string(),
+            json!("Revived"),
+            3,
+            "client2".to_string(),
+        );
+
+        let snapshot1 = replica1.clone();
+        replica1.merge(&replica2);
+        replica2.merge(&snapshot1);
+
+        assert_eq!(
+            replica1.get_field(&"title".to_string()),
+            Some(&json!("Revived"))
+        );
+        assert_eq!(replica1.to_json(), replica2.to_json());
+    }
+
+    #[test]
+    fn test_delete_wins_exact_timestamp_tie() {
+        let ts = Timestamp::new(1, "client1".to_string());
+
+        let mut doc1 = Document::new("doc-123".to_string());
+        doc1.merge_field("title".to_string(), Field::new(json!("Hello"), ts.clone()));
+        doc1.merge_field("title".to_string(), Field::tombstone(ts.clone()));
+
+        let mut doc2 = Document::new("doc-123".to_string());
+        doc2.merge_field("title".to_string(), Field::tombstone(ts.clone()));
+        doc2.merge_field("title".to_string(), Field::new(json!("Hello"), ts));
+
+        assert_eq!(doc1.get_field(&"title".to_string()), None);
+        assert_eq!(doc2.get_field(&"title".to_string()), None);
+    }
+
+    #[test]
+    fn test_nested_paths_to_json() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field(
+            "user.address.city".to_string(),
+            json!("Paris"),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field(
+            "user.name".to_string(),
+            json!("Alice"),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field("tags[1]".to_string(), json!("b"), 1, "client1".to_string());
+
+        assert_eq!(
+            doc.to_json(),
+            json!({
+                "user": { "name": "Alice", "address": { "city": "Paris" } },
+                "tags": [null, "b"]
+            })
+        );
+        assert_eq!(
+            doc.get_value("user.address"),
+            Some(json!({ "city": "Paris" }))
+        );
+        assert_eq!(doc.get_value("tags[1]"), Some(json!("b")));
+        assert_eq!(doc.get_value("user.missing"), None);
+    }
+
+    #[test]
+    fn test_get_value_matches_json_view() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field_mode("user.tags".to_string(), FieldMode::MultiValue)
+            .unwrap();
+        doc.set_field(
+            "user".to_string(),
+            json!({ "name": "Ann", "age": 30 }),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field("user.age".to_string(), json!(31), 2, "client1".to_string());
+        doc.delete_field("user.name".to_string(), 3, "client1".to_string());
+        doc.set_field(
+            "user.tags".to_string(),
+            json!(["a"]),
+            4,
+            "client1".to_string(),
+        );
+        doc.set_field(
+            "username".to_string(),
+            json!("ann"),
+            5,
+            "client1".to_string(),
+        );
+
+        let json = doc.to_json();
+        for path in ["user", "user.age", "user.name", "user.tags", "username"] {
+            let expected = path::parse(path)
+                .iter()
+                .try_fold(&json, |node, segment| match segment {
+                    PathSegment::Key(key) => node.get(key),
+                    PathSegment::Index(index) => node.get(index),
+                })
+                .cloned();
+            assert_eq!(doc.get_value(path), expected, "{}", path);
+        }
+        assert_eq!(
+            doc.get_value("user"),
+            Some(json!({ "age": 31, "tags": ["a"] }))
+        );
+    }
+
+    #[test]
+    fn test_newest_write_decides_container_shape() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field("a[0]".to_string(), json!(1), 1, "client1".to_string());
+        doc.set_field("a.c".to_string(), json!(3), 2, "client1".to_string());
+        doc.set_field("a[1]".to_string(), json!(4), 3, "client1".to_string());
+        doc.set_field("a.b".to_string(), json!(2), 5, "client1".to_string());
+
+        // The newer key writes make "a" an object, the index writes drop out
+        assert_eq!(doc.to_json(), json!({ "a": { "b": 2, "c": 3 } }));
+        assert_eq!(doc.get_value("a.b"), Some(json!(2)));
+        assert_eq!(doc.get_value("a[0]"), None);
+
+        // A newer index write turns it back into an array
+        doc.set_field("a[2]".to_string(), json!(5), 6, "client1".to_string());
+        assert_eq!(doc.to_json(), json!({ "a": [1, 4, 5] }));
+        assert_eq!(doc.get_value("a"), Some(json!([1, 4, 5])));
+        assert_eq!(doc.get_value("a.b"), None);
+    }
+
+    #[test]
+    fn test_concurrent_sibling_leaves_both_survive() {
+        let mut base = Document::new("doc-123".to_string());
+        base.set_field(
+            "user".to_string(),
+            json!({ "name": "Alice", "city": "Paris" }),
+            1,
+            "client1".to_string(),
+        );
+
+        let mut replica1 = base.clone();
+        replica1.set_field(
+            "user.name".to_string(),
+            json!("Bob"),
+            2,
+            "client1".to_string(),
+        );
+
+        let mut replica2 = base.clone();
+        replica2.set_field(
+            "user.city".to_string(),
+            json!("Berlin"),
+            2,
+            "client2".to_string(),
+        );
+
+        let snapshot1 = replica1.clone();
+        replica1.merge(&replica2);
+        replica2.merge(&snapshot1);
+
+        let expected = json!({ "user": { "name": "Bob", "city": "Berlin" } });
+        assert_eq!(replica1.to_json(), expected);
+        assert_eq!(replica2.to_json(), expected);
+        assert_eq!(replica1.fields, replica2.fields);
+    }
+
+    #[test]
+    fn test_parent_write_overrides_older_children() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field(
+            "user.name".to_string(),
+            json!("Alice"),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field("user.age".to_string(), json!(30), 3, "client1".to_string());
+
+        // Parent written at 2: replaces user.name (1) but user.age (3) is newer
+        doc.set_field(
+            "user".to_string(),
+            json!({ "name": "Bob" }),
+            2,
+            "client2".to_string(),
+        );
+
+        assert!(!doc.fields().contains_key("user.name"));
+        assert_eq!(
+            doc.to_json(),
+            json!({ "user": { "name": "Bob", "age": 30 } })
+        );
+
+        // A stale child write under the newer parent is ignored
+        doc.set_field(
+            "user.name".to_string(),
+            json!("Stale"),
+            1,
+            "client3".to_string(),
+        );
+        assert_eq!(doc.get_value("user.name"), Some(json!("Bob")));
+    }
+
+    #[test]
+    fn test_nested_merge_order_independent() {
+        let writes = [
+            ("user", json!({ "name": "A" }), 5),
+            ("user.name", json!("B"), 3),
+            ("user.email", json!("b@x"), 7),
+            ("user.name", json!("C"), 6),
+        ];
+
+        let mut forward = Document::new("doc-123".to_string());
+        for (path, value, clock) in writes.iter() {
+            forward.set_field(path.to_string(), value.clone(), *clock, "c1".to_string());
+        }
+
+        let mut backward = Document::new("doc-123".to_string());
+        for (path, value, clock) in writes.iter().rev() {
+            backward.set_field(path.to_string(), value.clone(), *clock, "c1".to_string());
+        }
+
+        assert_eq!(forward.fields, backward.fields);
+        assert_eq!(
+            forward.to_json(),
+            json!({ "user": { "name": "C", "email": "b@x" } })
+        );
+    }
+
+    #[test]
+    fn test_delete_nested_leaf() {
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field(
+            "user".to_string(),
+            json!({ "name": "Alice", "city": "Paris" }),
+            1,
+            "client1".to_string(),
+        );
+        doc.set_field(
+            "items".to_string(),
+            json!([1, 2, 3]),
+            1,
+            "client1".to_string(),
+        );
+
+        doc.delete_field("user.city".to_string(), 2, "client1".to_string());
+        doc.delete_field("items[1]".to_string(), 2, "client1".to_string());
+
+        assert_eq!(
+            doc.to_json(),
+            json!({ "user": { "name": "Alice" }, "items": [1, null, 3] })
+        );
+
+        // Deleting the parent drops the older tombstones under it
+        doc.delete_field("user".to_string(), 3, "client1".to_string());
+        assert!(!doc.fields().contains_key("user.city"));
+        assert_eq!(doc.to_json(), json!({ "items": [1, null, 3] }));
+    }
+
     #[test]
     fn test_convergence_property() {
         // Test convergence: two replicas merging in different orders reach same state
@@ -379,16 +1492,19 @@ mod tests {
         let client1_update = Document {
             id: "doc-123".to_string(),
             fields: {
-                let mut map = HashMap::new();
+                let mut map = BTreeMap::new();
                 map.insert(
                     "field1".to_string(),
                     Field {
                         value: json!("A"),
                         timestamp: Timestamp::new(1, "client1".to_string()),
+                        deleted: false,
+                        redacted: false,
                     },
                 );
                 map
             },
+            registers: BTreeMap::new(),
             version: VectorClock::new(),
         };
 
@@ -396,16 +1512,19 @@ mod tests {
         let client2_update = Document {
             id: "doc-123".to_string(),
             fields: {
-                let mut map = HashMap::new();
+                let mut map = BTreeMap::new();
                 map.insert(
                     "field1".to_string(),
                     Field {
                         value: json!("B"),
                         timestamp: Timestamp::new(2, "client2".to_string()),
+                        deleted: false,
+                        redacted: false,
                     },
                 );
                 map
             },
+            registers: BTreeMap::new(),
             version: VectorClock::new(),
         };
 
@@ -427,4 +1546,215 @@ mod tests {
         assert_eq!(replica1.get_field(&"field1".to_string()), Some(&json!("B")));
         assert_eq!(replica2.get_field(&"field1".to_string()), Some(&json!("B")));
     }
+
+    #[test]
+    fn test_hlc_writes_win_after_merge() {
+        use crate::sync::{HybridLogicalClock, ManualTimeSource};
+
+        // Client1's wall clock is an hour behind client2's
+        let time1 = ManualTimeSource::new(1_000_000);
+        let time2 = ManualTimeSource::new(1_000_000 + 3_600_000);
+        let mut clock1 = HybridLogicalClock::with_source("client1".to_string(), time1)
+            .with_max_drift(24 * 3_600_000);
+        let mut clock2 = HybridLogicalClock::with_source("client2".to_string(), time2);
+
+        let mut doc1 = Document::new("doc-123".to_string());
+        let mut doc2 = Document::new("doc-123".to_string());
+        doc2.set_field_with_clock("title".to_string(), json!("B"), &mut clock2);
+
+        // After seeing client2's write, client1's next write still wins
+        doc1.merge_with_clock(&doc2, &mut clock1).unwrap();
+        doc1.set_field_with_clock("title".to_string(), json!("A"), &mut clock1);
+        doc2.merge_with_clock(&doc1, &mut clock2).unwrap();
+
+        assert_eq!(doc2.get_field(&"title".to_string()), Some(&json!("A")));
+    }
+
+    #[test]
+    fn test_merge_with_clock_rejects_future_writes() {
+        use crate::sync::{HybridLogicalClock, ManualTimeSource};
+
+        let mut clock = HybridLogicalClock::with_source(
+            "client1".to_string(),
+            ManualTimeSource::new(1_000_000),
+        );
+
+        let mut remote = Document::new("doc-123".to_string());
+        remote.set_field(
+            "title".to_string(),
+            json!("From the future"),
+            Timestamp::from_hlc(9_000_000, 0, "client2".to_string()).clock,
+            "client2".to_string(),
+        );
+
+        let mut doc = Document::new("doc-123".to_string());
+        let result = doc.merge_with_clock(&remote, &mut clock);
+
+        assert!(matches!(result, Err(crate::SyncError::InvalidTimestamp(_))));
+        assert!(doc.is_empty());
+
+        // Values of multi-value fields are checked as well
+        let mut remote = Document::new("doc-123".to_string());
+        remote
+            .set_field_mode("owner".to_string(), FieldMode::MultiValue)
+            .unwrap();
+        remote.set_field(
+            "owner".to_string(),
+            json!("client2"),
+            Timestamp::from_hlc(9_000_000, 0, "client2".to_string()).clock,
+            "client2".to_string(),
+        );
+        let result = doc.merge_with_clock(&remote, &mut clock);
+        assert!(matches!(result, Err(crate::SyncError::InvalidTimestamp(_))));
+        assert!(doc.registers().is_empty());
+
+        // Within the limit, the clock moves past them
+        let near = Timestamp::from_hlc(1_000_500, 3, "client2".to_string());
+        remote.set_field(
+            "owner".to_string(),
+            json!("client2"),
+            near.clock,
+            "client2".to_string(),
+        );
+        doc.merge_with_clock(&remote, &mut clock).unwrap();
+        assert!(clock.now().is_newer_than(&near));
+    }
+
+    #[test]
+    fn test_local_write_wins_after_clamped_merge() {
+        use crate::sync::{DriftPolicy, HybridLogicalClock, ManualTimeSource};
+
+        let mut clock = HybridLogicalClock::with_source(
+            "client1".to_string(),
+            ManualTimeSource::new(1_000_000),
+        )
+        .with_drift_policy(DriftPolicy::Clamp);
+
+        let mut remote = Document::new("doc-123".to_string());
+        remote.set_field(
+            "x".to_string(),
+            json!("remote"),
+            Timestamp::from_hlc(9_000_000_000, 0, "client2".to_string()).clock,
+            "client2".to_string(),
+        );
+
+        let mut doc = Document::new("doc-123".to_string());
+        doc.merge_with_clock(&remote, &mut clock).unwrap();
+        assert_eq!(doc.to_json(), json!({ "x": "remote" }));
+        assert!(doc.fields()["x"].timestamp.physical_millis() < 9_000_000_000);
+
+        doc.set_field_with_clock("x".to_string(), json!("local"), &mut clock);
+        assert_eq!(doc.to_json(), json!({ "x": "local" }));
+    }
+
+    #[test]
+    fn test_multi_value_field_keeps_concurrent_writes() {
+        let assignee = "assignee".to_string();
+        let mut doc1 = Document::new("doc-123".to_string());
+        doc1.set_field_mode(assignee.clone(), FieldMode::MultiValue)
+            .unwrap();
+        let mut doc2 = doc1.clone();
+
+        // Same clock, different clients: LWW would silently pick client2
+        doc1.set_field(assignee.clone(), json!("alice"), 1, "client1".to_string());
+        doc2.set_field(assignee.clone(), json!("bob"), 1, "client2".to_string());
+        doc1.merge(&doc2);
+        doc2.merge(&doc1);
+
+        assert_eq!(
+            doc1.get_field_values(&assignee),
+            vec![&json!("alice"), &json!("bob")]
+        );
+        assert_eq!(doc1.to_json(), doc2.to_json());
+        assert_eq!(doc1.field_count(), 1);
+
+        // A write after seeing both replaces them
+        doc2.set_field(assignee.clone(), json!("carol"), 2, "client2".to_string());
+        doc1.merge(&doc2);
+        assert_eq!(doc1.get_field_values(&assignee), vec![&json!("carol")]);
+        assert_eq!(doc1.to_json(), json!({ "assignee": "carol" }));
+    }
+
+    #[test]
+    fn test_field_mode_travels_with_merge() {
+        let mut doc1 = Document::new("doc-123".to_string());
+        doc1.set_field_mode("tags[0]".to_string(), FieldMode::MultiValue)
+            .unwrap();
+
+        let mut doc2 = Document::new("doc-123".to_string());
+        doc2.merge(&doc1);
+        assert_eq!(doc2.field_mode("tags[0]"), FieldMode::MultiValue);
+        assert_eq!(doc2.field_mode("title"), FieldMode::Lww);
+
+        doc2.delete_field("tags[0]".to_string(), 1, "client2".to_string());
+        assert!(doc2.is_empty());
+
+        assert!(matches!(
+            doc2.set_field_mode("tags[0]".to_string(), FieldMode::Lww),
+            Err(SyncError::InvalidOperation(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_report_explains_decisions() {
+        use crate::sync::MergeDecision;
+
+        let mut doc = Document::new("doc-123".to_string());
+        doc.set_field("a".to_string(), json!("local"), 2, "client1".to_string());
+        doc.set_field("b".to_string(), json!("local"), 1, "client1".to_string());
+        doc.set_field("c".to_string(), json!("same"), 1, "client1".to_string());
+        doc.set_field("d".to_string(), json!("local"), 1, "client1".to_string());
+
+        let mut remote = Document::new("doc-123".to_string());
+        remote.set_field("a".to_string(), json!("remote"), 1, "client2".to_string());
+        remote.set_field("b".to_string(), json!("remote"), 1, "client0".to_string());
+        remote.set_field("c".to_string(), json!("same"), 1, "client1".to_string());
+        remote.delete_field("d".to_string(), 1, "client1".to_string());
+
+        let report = doc.merge_with_report(&remote);
+
+        // "c" is identical on both sides and not reported
+        let decisions: Vec<_> = report
+            .fields
+            .iter()
+            .map(|f| (f.path.as_str(), f.decision, f.took_remote))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("a", MergeDecision::KeptLocal, false),
+                ("b", MergeDecision::TieBrokenByClientId, false),
+                ("d", MergeDecision::TieBrokenByValue, true),
+            ]
+        );
+        assert_eq!(report.updated_count(), 1);
+
+        let overwritten = report.overwritten().next().unwrap();
+        assert_eq!(overwritten.local.as_ref().unwrap().value, json!("local"));
+        assert!(overwritten.remote.is_deleted());
+    }
+
+    #[test]
+    fn test_to_json_redacted() {
+        use crate::sync::policy::tests::PathPolicy;
+
+        let mut doc = Document::new("doc1".to_string());
+        doc.set_field(
+            "user".to_string(),
+            json!({ "name": "Ann", "salary": 100 }),
+            1,
+            "c1".to_string(),
+        );
+        doc.set_field("notes".to_string(), json!("n"), 1, "c1".to_string());
+        doc.set_field("title".to_string(), json!("T"), 1, "c1".to_string());
+
+        let policy = PathPolicy {
+            read_only: vec!["title"],
+            hidden: vec!["user.salary", "notes"],
+        };
+        assert_eq!(
+            doc.to_json_redacted(&policy),
+            json!({ "user": { "name": "Ann" }, "title": "T" })
+        );
+    }
 }
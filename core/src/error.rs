@@ -37,6 +37,12 @@ pub enum SyncError {
 
     #[error("Protocol error: {0}")]
     Protocol(String),
+
+    #[error("Buffer full: {0}")]
+    BufferFull(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
 }
 
 impl SyncError {
@@ -44,7 +50,10 @@ impl SyncError {
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
-            SyncError::NetworkError(_) | SyncError::StorageError(_) | SyncError::ConflictError(_)
+            SyncError::NetworkError(_)
+                | SyncError::StorageError(_)
+                | SyncError::ConflictError(_)
+                | SyncError::BufferFull(_)
         )
     }
 
@@ -61,6 +70,8 @@ impl SyncError {
             SyncError::ConflictError(_) => "CONFLICT_ERROR",
             SyncError::InvalidOperation(_) => "INVALID_OPERATION",
             SyncError::Protocol(_) => "PROTOCOL_ERROR",
+            SyncError::BufferFull(_) => "BUFFER_FULL",
+            SyncError::PermissionDenied(_) => "PERMISSION_DENIED",
         }
     }
 }
@@ -0,0 +1,295 @@
+//! Change history and time travel for documents
+//!
+//! A `Document` only holds the winning write of each field. [`History`]
+//! wraps a document and also keeps an append-only log of every write that
+//! changed it, local or merged in, each stamped with the document version
+//! after the write. That is enough to:
+//! - rebuild the document as it was at any past version
+//!   ([`History::at`]), by replaying the writes that version had seen;
+//! - list what changed between two versions ([`History::changes_between`]),
+//!   for audit trails.
+//!
+//! Replaying goes through the usual LWW merge, so the log can hold writes
+//! in any order, and a rebuilt document matches what a replica at that
+//! version would have had.
+//!
+//! Multi-value fields are written through but not logged: past versions
+//! are rebuilt without them.
+
+use crate::document::{Document, Field, FieldMode};
+use crate::error::Result;
+use crate::sync::{apply_delta_with_report, Delta, MergeReport, Timestamp, VectorClock};
+use crate::{ClientID, FieldPath};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// One logged write: a field value or a delete
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Canonical path of the field
+    pub path: FieldPath,
+
+    /// The write, with its LWW timestamp (a tombstone for deletes)
+    pub field: Field,
+
+    /// Document version right after the write
+    pub version: VectorClock,
+}
+
+impl HistoryEntry {
+    /// Check if this entry deleted the field
+    pub fn is_delete(&self) -> bool {
+        self.field.is_deleted()
+    }
+
+    /// Timestamp of the write
+    pub fn timestamp(&self) -> &Timestamp {
+        &self.field.timestamp
+    }
+}
+
+/// A document with a log of its changes
+///
+/// Local writes tick the writer's entry in the document version, so each
+/// one gets its own version to travel back to. Merged writes are logged
+/// with the version after the merge.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::history::History;
+/// use synckit_core::Document;
+/// use serde_json::json;
+///
+/// let mut history = History::new(Document::new("task-1".to_string()));
+/// history.set_field("status", json!("open"), 1, "alice");
+/// let opened = history.document().version().clone();
+///
+/// history.set_field("status", json!("done"), 2, "bob");
+///
+/// // The task as it was when opened
+/// let past = history.at(&opened);
+/// assert_eq!(past.get_field(&"status".to_string()), Some(&json!("open")));
+///
+/// // Who changed what since then
+/// let changes: Vec<_> = history
+///     .changes_between(&opened, history.document().version())
+///     .collect();
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes[0].timestamp().client_id, "bob");
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct History {
+    /// Current state
+    document: Document,
+
+    /// Writes that changed the document, in the order they were applied
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Start recording changes to a document
+    ///
+    /// Fields the document already has are logged as of its current
+    /// version: history before that is unknown.
+    pub fn new(document: Document) -> Self {
+        let entries = document
+            .fields()
+            .iter()
+            .map(|(path, field)| HistoryEntry {
+                path: path.clone(),
+                field: field.clone(),
+                version: document.version().clone(),
+            })
+            .collect();
+
+        Self { document, entries }
+    }
+
+    /// Current state of the document
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Stop recording and return the document
+    pub fn into_document(self) -> Document {
+        self.document
+    }
+
+    /// All logged writes, oldest first
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Set a field and log the write
+    ///
+    /// Takes the same arguments as `Document::set_field`. Returns false,
+    /// and logs nothing, if the write loses to a newer value.
+    pub fn set_field(
+        &mut self,
+        field_path: &str,
+        value: JsonValue,
+        clock: u64,
+        client_id: &str,
+    ) -> bool {
+        let timestamp = Timestamp::new(clock, client_id.to_string());
+        self.write(field_path, Field::new(value, timestamp))
+    }
+
+    /// Delete a field and log the delete
+    ///
+    /// Takes the same arguments as `Document::delete_field`.
+    pub fn delete_field(&mut self, field_path: &str, clock: u64, client_id: &str) -> bool {
+        let timestamp = Timestamp::new(clock, client_id.to_string());
+        self.write(field_path, Field::tombstone(timestamp))
+    }
+
+    /// Merge a remote document and log the writes that won
+    pub fn merge(&mut self, remote: &Document) -> MergeReport {
+        let report = self.document.merge_with_report(remote);
+        self.log_report(&report);
+        report
+    }
+
+    /// Apply a delta and log the writes that won
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidOperation` if the delta is for another
+    /// document.
+    pub fn apply_delta(&mut self, delta: &Delta) -> Result<MergeReport> {
+        delta.check_document(&self.document)?;
+        let report = apply_delta_with_report(&mut self.document, delta);
+        self.log_report(&report);
+        Ok(report)
+    }
+
+    /// Rebuild the document as it was at `version`
+    ///
+    /// Replays every logged write that `version` has seen. The result's
+    /// version is the latest logged version `version` covers.
+    pub fn at(&self, version: &VectorClock) -> Document {
+        let mut document = Document::new(self.document.id().clone());
+
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| version.dominates(&e.version))
+        {
+            document.merge_field(entry.path.clone(), entry.field.clone());
+            document.version.merge(&entry.version);
+        }
+
+        document
+    }
+
+    /// Iterate over writes seen at `to` but not yet at `from`, oldest first
+    pub fn changes_between<'a>(
+        &'a self,
+        from: &'a VectorClock,
+        to: &'a VectorClock,
+    ) -> impl Iterator<Item = &'a HistoryEntry> + 'a {
+        self.entries
+            .iter()
+            .filter(move |e| to.dominates(&e.version) && !from.dominates(&e.version))
+    }
+
+    /// Iterate over logged writes to one field, oldest first
+    pub fn field_history<'a>(
+        &'a self,
+        field_path: &str,
+    ) -> impl Iterator<Item = &'a HistoryEntry> + 'a {
+        let field_path = crate::path::normalize(field_path);
+        self.entries.iter().filter(move |e| e.path == field_path)
+    }
+
+    fn write(&mut self, field_path: &str, field: Field) -> bool {
+        let path = crate::path::normalize(field_path);
+        let client_id: ClientID = field.timestamp.client_id.clone();
+        let logged = self.document.field_mode(&path) == FieldMode::Lww;
+
+        if !self.document.write_field(path.clone(), field.clone()) {
+            return false;
+        }
+
+        self.document.version.tick(&client_id);
+        if logged {
+            self.entries.push(HistoryEntry {
+                path,
+                field,
+                version: self.document.version().clone(),
+            });
+        }
+        true
+    }
+
+    fn log_report(&mut self, report: &MergeReport) {
+        for merged in report.fields.iter().filter(|f| f.took_remote) {
+            self.entries.push(HistoryEntry {
+                path: merged.path.clone(),
+                field: merged.remote.clone(),
+                version: self.document.version().clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::compute_delta;
+    use serde_json::json;
+
+    #[test]
+    fn test_time_travel_replays_writes() {
+        let mut history = History::new(Document::new("doc-1".to_string()));
+
+        history.set_field("user", json!({ "name": "Ann", "age": 30 }), 1, "a");
+        let v1 = history.document().version().clone();
+        history.set_field("user.age", json!(31), 2, "a");
+        let v2 = history.document().version().clone();
+        history.delete_field("user", 3, "a");
+
+        assert_eq!(
+            history.at(&v1).to_json(),
+            json!({ "user": { "name": "Ann", "age": 30 } })
+        );
+        assert_eq!(
+            history.at(&v2).to_json(),
+            json!({ "user": { "name": "Ann", "age": 31 } })
+        );
+        assert_eq!(
+            history.at(history.document().version()).to_json(),
+            json!({})
+        );
+        assert_eq!(history.at(&VectorClock::new()).to_json(), json!({}));
+
+        assert_eq!(history.field_history("user").count(), 2);
+    }
+
+    #[test]
+    fn test_merged_writes_are_logged() {
+        let mut history = History::new(Document::new("doc-1".to_string()));
+        history.set_field("title", json!("Mine"), 5, "a");
+        let before = history.document().version().clone();
+
+        let mut remote = Document::new("doc-1".to_string());
+        remote.set_field("title".to_string(), json!("Old"), 1, "b".to_string());
+        remote.set_field("body".to_string(), json!("Text"), 2, "b".to_string());
+        remote.version.update(&"b".to_string(), 2);
+
+        // Only the write that won is logged
+        let delta = compute_delta(&Document::new("doc-1".to_string()), &remote);
+        history.apply_delta(&delta).unwrap();
+
+        let changes: Vec<_> = history
+            .changes_between(&before, history.document().version())
+            .map(|e| e.path.as_str())
+            .collect();
+        assert_eq!(changes, vec!["body"]);
+        assert_eq!(history.at(&before).field_count(), 1);
+
+        let other = Delta::empty("doc-2".to_string(), VectorClock::new());
+        assert!(history.apply_delta(&other).is_err());
+    }
+}
@@ -6,6 +6,9 @@
 //! - Vector clocks for causality tracking
 //! - CRDT data structures (OR-Set, PN-Counter, Text)
 //! - Binary protocol encoding/decoding (when prost feature enabled)
+//! - Undo/redo of local edits that preserves concurrent remote edits
+//! - Change history with replay of past versions
+//! - Branches that fork a document and merge back
 //!
 //! # Examples
 //!
@@ -27,10 +30,14 @@
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 pub mod awareness;
+pub mod branch;
 pub mod document;
 pub mod error;
+pub mod history;
+pub mod path;
 pub mod storage;
 pub mod sync;
+pub mod undo;
 
 // Protocol module only included if prost feature is enabled
 #[cfg(feature = "prost")]
@@ -50,9 +57,13 @@ pub mod wasm;
 
 // Re-exports for convenience
 pub use awareness::{Awareness, AwarenessState, AwarenessUpdate};
+pub use branch::Branch;
 pub use document::Document;
 pub use error::{Result, SyncError};
+pub use history::History;
+pub use path::PathSegment;
 pub use sync::{Timestamp, VectorClock};
+pub use undo::UndoManager;
 
 /// Client identifier type
 pub type ClientID = String;
@@ -61,6 +72,9 @@ pub type ClientID = String;
 pub type DocumentID = String;
 
 /// Field path within a document
+///
+/// Dot notation for object keys, brackets for array indices
+/// (e.g. `user.address.city`, `items[0].name`). See [`path`].
 pub type FieldPath = String;
 
 #[cfg(test)]
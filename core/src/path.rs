@@ -0,0 +1,225 @@
+//! Field path addressing for nested documents
+//!
+//! Field paths use dot notation for object keys and brackets for array
+//! indices, e.g. `user.address.city` or `items[0].name`. Documents store one
+//! LWW entry per written path; these helpers parse paths into segments and
+//! answer ancestor/descendant questions on the canonical string form.
+//!
+//! # Example
+//!
+//! ```rust
+//! use synckit_core::path::{self, PathSegment};
+//!
+//! let segments = path::parse("items[2].name");
+//! assert_eq!(
+//!     segments,
+//!     vec![
+//!         PathSegment::Key("items".to_string()),
+//!         PathSegment::Index(2),
+//!         PathSegment::Key("name".to_string()),
+//!     ]
+//! );
+//! assert_eq!(path::format(&segments), "items[2].name");
+//! assert!(path::is_ancestor("items", "items[2].name"));
+//! ```
+
+use crate::FieldPath;
+use std::fmt;
+
+/// A single step in a field path
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// Object key (`user` in `user.name`)
+    Key(String),
+
+    /// Array index (`0` in `items[0]`)
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{}", key),
+            PathSegment::Index(index) => write!(f, "[{}]", index),
+        }
+    }
+}
+
+/// Parse a path string into segments
+///
+/// Parsing is lenient: empty segments are skipped, and bracket groups that
+/// are not plain numbers (or that have no key in front of them) are kept as
+/// part of the key.
+pub fn parse(path: &str) -> Vec<PathSegment> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        if part.is_empty() {
+            continue;
+        }
+
+        // Split "items[0][1]" into key "items" and indices [0, 1]
+        let key_end = part.find('[').unwrap_or(part.len());
+        let (key, mut rest) = part.split_at(key_end);
+        let mut indices = Vec::new();
+
+        while let Some(stripped) = rest.strip_prefix('[') {
+            let Some(close) = stripped.find(']') else {
+                break;
+            };
+            let Ok(index) = stripped[..close].parse::<usize>() else {
+                break;
+            };
+            indices.push(index);
+            rest = &stripped[close + 1..];
+        }
+
+        if key.is_empty() || !rest.is_empty() {
+            // Not a well-formed "key[n]..." segment - treat it as a plain key
+            segments.push(PathSegment::Key(part.to_string()));
+            continue;
+        }
+
+        segments.push(PathSegment::Key(key.to_string()));
+        segments.extend(indices.into_iter().map(PathSegment::Index));
+    }
+
+    segments
+}
+
+/// Format segments back into a canonical path string
+pub fn format(segments: &[PathSegment]) -> FieldPath {
+    let mut path = String::new();
+
+    for segment in segments {
+        if let PathSegment::Key(_) = segment {
+            if !path.is_empty() {
+                path.push('.');
+            }
+        }
+        path.push_str(&segment.to_string());
+    }
+
+    path
+}
+
+/// Normalize a path string into its canonical form
+pub fn normalize(path: &str) -> FieldPath {
+    format(&parse(path))
+}
+
+/// Cheap check for paths that are already canonical
+///
+/// Only recognizes bracket-free paths without empty segments; anything else
+/// should go through `normalize`.
+pub fn is_canonical(path: &str) -> bool {
+    !path.contains('[') && !path.starts_with('.') && !path.ends_with('.') && !path.contains("..")
+}
+
+/// Build a canonical path from protocol segments
+///
+/// Each segment is parsed on its own, so `["items", "[0]"]`,
+/// `["items[0]"]` and the legacy single-segment `["user.name"]` all map to
+/// the expected path.
+pub fn from_segments<S: AsRef<str>>(segments: &[S]) -> FieldPath {
+    let parsed: Vec<PathSegment> = segments
+        .iter()
+        .flat_map(|segment| {
+            let segment = segment.as_ref();
+            match segment
+                .strip_prefix('[')
+                .and_then(|s| s.strip_suffix(']'))
+                .and_then(|s| s.parse::<usize>().ok())
+            {
+                Some(index) => vec![PathSegment::Index(index)],
+                None => parse(segment),
+            }
+        })
+        .collect();
+
+    format(&parsed)
+}
+
+/// Split a path into protocol segments (indices are encoded as `[n]`)
+pub fn to_segments(path: &str) -> Vec<String> {
+    parse(path)
+        .iter()
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+/// Check if `ancestor` is a strict ancestor of `path` (both canonical)
+pub fn is_ancestor(ancestor: &str, path: &str) -> bool {
+    path.len() > ancestor.len()
+        && path.starts_with(ancestor)
+        && matches!(path.as_bytes()[ancestor.len()], b'.' | b'[')
+}
+
+/// Canonical strict ancestors of a canonical path, nearest last
+///
+/// `a.b[0].c` yields `a`, `a.b`, `a.b[0]`.
+pub fn ancestors(path: &str) -> Vec<FieldPath> {
+    // Fast path: top-level keys have no ancestors
+    if !path.contains(['.', '[']) {
+        return Vec::new();
+    }
+
+    let segments = parse(path);
+    (1..segments.len())
+        .map(|len| format(&segments[..len]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys_and_indices() {
+        assert_eq!(
+            parse("user.address.city"),
+            vec![
+                PathSegment::Key("user".to_string()),
+                PathSegment::Key("address".to_string()),
+                PathSegment::Key("city".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse("matrix[1][2]"),
+            vec![
+                PathSegment::Key("matrix".to_string()),
+                PathSegment::Index(1),
+                PathSegment::Index(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_is_lenient() {
+        assert_eq!(normalize("a..b"), "a.b");
+        assert_eq!(parse("[0]"), vec![PathSegment::Key("[0]".to_string())]);
+        assert_eq!(parse("a[x]"), vec![PathSegment::Key("a[x]".to_string())]);
+    }
+
+    #[test]
+    fn test_protocol_segments_roundtrip() {
+        let path = "items[3].tags[0]";
+        let segments = to_segments(path);
+        assert_eq!(segments, vec!["items", "[3]", "tags", "[0]"]);
+        assert_eq!(from_segments(&segments), path);
+
+        // Legacy single-segment paths still resolve
+        assert_eq!(from_segments(&["user.name"]), "user.name");
+    }
+
+    #[test]
+    fn test_ancestry() {
+        assert!(is_ancestor("user", "user.name"));
+        assert!(is_ancestor("items", "items[0]"));
+        assert!(!is_ancestor("user", "username"));
+        assert!(!is_ancestor("user", "user"));
+
+        assert_eq!(ancestors("a.b[0].c"), vec!["a", "a.b", "a.b[0]"]);
+        assert!(ancestors("a").is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+// Access control - Answer permission checks from `auth.proto` messages
+//!
+//! The server grants each client a `Permissions` message in its
+//! `AuthResponse`. This module reads those grants to answer whether a
+//! client may read or write a field of a document, and to strip the
+//! fields it may not touch from a delta before it is sent or applied.
+//! Grants implement [`FieldPolicy`], so they plug into
+//! `Delta::apply_to_with_policy`, `Delta::redact`,
+//! `Document::to_json_redacted` and the [`SyncCoordinator`].
+//!
+//! [`SyncCoordinator`]: crate::protocol::sync::SyncCoordinator
+//!
+//! # Rules
+//!
+//! - Access to a field needs access to its document: a document without
+//!   an entry in `Permissions::documents` is neither readable nor writable.
+//! - `DocumentPermission::fields` narrows access further. The rule of the
+//!   field itself, or else of its nearest ancestor, applies: a rule on
+//!   `user` covers `user.email` unless `user.email` has its own rule.
+//!   Field rules cannot grant more than the document allows.
+//! - A write to a field replaces its whole subtree, and reading it returns
+//!   the whole subtree, so a field is only accessible if no rule on a
+//!   descendant denies the access.
+//!
+//! # Example
+//!
+//! ```rust
+//! use synckit_core::protocol::auth::{Access, AccessControl};
+//! use synckit_core::protocol::{DocumentPermission, FieldPermission, Permissions};
+//!
+//! let mut doc = DocumentPermission {
+//!     can_read: true,
+//!     can_write: true,
+//!     ..Default::default()
+//! };
+//! doc.fields.insert(
+//!     "owner".to_string(),
+//!     FieldPermission { can_read: true, can_write: false },
+//! );
+//!
+//! let mut permissions = Permissions::default();
+//! permissions.documents.insert("doc-1".to_string(), doc);
+//!
+//! let mut acl = AccessControl::new();
+//! acl.grant("alice".to_string(), permissions);
+//!
+//! assert!(acl.can_write("alice", "doc-1", "title"));
+//! assert!(acl.can_read("alice", "doc-1", "owner.name"));
+//! assert!(!acl.can_write("alice", "doc-1", "owner.name"));
+//! assert!(!acl.can_read("alice", "doc-2", "title"));
+//! assert!(!acl.allows("bob", "doc-1", "title", Access::Read));
+//! ```
+
+use crate::path;
+use crate::protocol::{DocumentPermission, FieldPermission, Permissions};
+use crate::sync::{Delta as SyncDelta, FieldPolicy};
+use crate::{ClientID, FieldPath};
+use std::collections::HashMap;
+
+pub use crate::sync::Access;
+
+impl FieldPermission {
+    /// Check if the rule allows `access`
+    pub fn allows(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.can_read,
+            Access::Write => self.can_write,
+        }
+    }
+}
+
+impl DocumentPermission {
+    /// Check if the grant allows `access` to a field and its subtree
+    pub fn allows_field(&self, field_path: &str, access: Access) -> bool {
+        let field_path = path::normalize(field_path);
+
+        // Part of the subtree may be off limits
+        self.rule_allows(&field_path, access)
+            && !self.fields.iter().any(|(rule_path, rule)| {
+                path::is_ancestor(&field_path, &path::normalize(rule_path)) && !rule.allows(access)
+            })
+    }
+
+    /// Check the document grant and the rule covering a canonical path
+    fn rule_allows(&self, field_path: &str, access: Access) -> bool {
+        let document_allows = match access {
+            Access::Read => self.can_read,
+            Access::Write => self.can_write,
+        };
+        if !document_allows {
+            return false;
+        }
+
+        let mut nearest: Option<(FieldPath, &FieldPermission)> = None;
+
+        for (rule_path, rule) in &self.fields {
+            let rule_path = path::normalize(rule_path);
+            let covers = rule_path == field_path || path::is_ancestor(&rule_path, field_path);
+            if covers
+                && nearest
+                    .as_ref()
+                    .is_none_or(|(p, _)| p.len() < rule_path.len())
+            {
+                nearest = Some((rule_path, rule));
+            }
+        }
+
+        nearest.is_none_or(|(_, rule)| rule.allows(access))
+    }
+}
+
+impl FieldPolicy for Permissions {
+    fn allows(&self, document_id: &str, field_path: &str, access: Access) -> bool {
+        self.documents
+            .get(document_id)
+            .is_some_and(|document| document.allows_field(field_path, access))
+    }
+
+    fn allows_node(&self, document_id: &str, field_path: &str, access: Access) -> bool {
+        self.documents
+            .get(document_id)
+            .is_some_and(|document| document.rule_allows(&path::normalize(field_path), access))
+    }
+}
+
+impl Permissions {
+    /// Keep only the changes of a delta the grant allows `access` to
+    ///
+    /// With `Access::Read`, before sending a delta to a client, the delta
+    /// is redacted (see `Delta::redact`). With `Access::Write`, before
+    /// applying one it sent, changes to fields it may not write are
+    /// dropped; use `Delta::check_writes` to reject the delta instead.
+    /// The versions are kept: what was dropped is simply never delivered.
+    pub fn filter_delta(&self, delta: &SyncDelta, access: Access) -> SyncDelta {
+        filter_delta(self, delta, access)
+    }
+}
+
+/// Permissions of every authenticated client
+///
+/// Clients without a grant have no access at all.
+#[derive(Debug, Clone, Default)]
+pub struct AccessControl {
+    clients: HashMap<ClientID, Permissions>,
+}
+
+impl AccessControl {
+    /// Create an empty access list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the permissions of a client, replacing any earlier grant
+    pub fn grant(&mut self, client_id: ClientID, permissions: Permissions) {
+        self.clients.insert(client_id, permissions);
+    }
+
+    /// Remove all permissions of a client
+    pub fn revoke(&mut self, client_id: &str) -> Option<Permissions> {
+        self.clients.remove(client_id)
+    }
+
+    /// Permissions granted to a client
+    pub fn permissions(&self, client_id: &str) -> Option<&Permissions> {
+        self.clients.get(client_id)
+    }
+
+    /// The policy of one client, for `Delta` and `Document` checks
+    pub fn for_client<'a>(&'a self, client_id: &'a str) -> ClientPolicy<'a> {
+        ClientPolicy {
+            permissions: self.permissions(client_id),
+        }
+    }
+
+    /// Check if a client may access a field of a document
+    pub fn allows(
+        &self,
+        client_id: &str,
+        document_id: &str,
+        field_path: &str,
+        access: Access,
+    ) -> bool {
+        self.for_client(client_id)
+            .allows(document_id, field_path, access)
+    }
+
+    /// Check if a client may read a field of a document
+    pub fn can_read(&self, client_id: &str, document_id: &str, field_path: &str) -> bool {
+        self.allows(client_id, document_id, field_path, Access::Read)
+    }
+
+    /// Check if a client may write a field of a document
+    pub fn can_write(&self, client_id: &str, document_id: &str, field_path: &str) -> bool {
+        self.allows(client_id, document_id, field_path, Access::Write)
+    }
+
+    /// Keep only the changes of a delta a client may access
+    ///
+    /// See [`Permissions::filter_delta`]. Nothing is kept for clients
+    /// without a grant.
+    pub fn filter_delta(&self, client_id: &str, delta: &SyncDelta, access: Access) -> SyncDelta {
+        filter_delta(&self.for_client(client_id), delta, access)
+    }
+}
+
+/// Permissions of one client; a client without a grant has no access
+#[derive(Debug, Clone, Copy)]
+pub struct ClientPolicy<'a> {
+    permissions: Option<&'a Permissions>,
+}
+
+impl FieldPolicy for ClientPolicy<'_> {
+    fn allows(&self, document_id: &str, field_path: &str, access: Access) -> bool {
+        self.permissions
+            .is_some_and(|permissions| permissions.allows(document_id, field_path, access))
+    }
+
+    fn allows_node(&self, document_id: &str, field_path: &str, access: Access) -> bool {
+        self.permissions
+            .is_some_and(|permissions| permissions.allows_node(document_id, field_path, access))
+    }
+}
+
+fn filter_delta<P: FieldPolicy>(policy: &P, delta: &SyncDelta, access: Access) -> SyncDelta {
+    if access == Access::Read {
+        return delta.redact(policy);
+    }
+
+    let allowed = |path: &FieldPath| policy.allows(&delta.document_id, path, access);
+    SyncDelta {
+        document_id: delta.document_id.clone(),
+        fields: delta
+            .fields
+            .iter()
+            .filter(|(path, _)| allowed(path))
+            .map(|(path, field)| (path.clone(), field.clone()))
+            .collect(),
+        registers: delta
+            .registers
+            .iter()
+            .filter(|(path, _)| allowed(path))
+            .map(|(path, register)| (path.clone(), register.clone()))
+            .collect(),
+        base_version: delta.base_version.clone(),
+        new_version: delta.new_version.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use serde_json::json;
+
+    fn rule(can_read: bool, can_write: bool) -> FieldPermission {
+        FieldPermission {
+            can_read,
+            can_write,
+        }
+    }
+
+    fn permissions() -> Permissions {
+        let mut doc = DocumentPermission {
+            can_read: true,
+            can_write: true,
+            ..Default::default()
+        };
+        doc.fields.insert("user".to_string(), rule(true, false));
+        doc.fields
+            .insert("user.notes".to_string(), rule(true, true));
+        doc.fields.insert("secrets".to_string(), rule(false, false));
+
+        let mut permissions = Permissions::default();
+        permissions.documents.insert("doc-1".to_string(), doc);
+        permissions
+    }
+
+    #[test]
+    fn test_nearest_rule_applies() {
+        let permissions = permissions();
+
+        assert!(permissions.allows("doc-1", "title", Access::Write));
+        assert!(!permissions.allows("doc-1", "user.name", Access::Write));
+        assert!(permissions.allows("doc-1", "user.notes[0]", Access::Write));
+        assert!(permissions.allows("doc-1", "user", Access::Read));
+        assert!(!permissions.allows("doc-1", "secrets.key", Access::Read));
+
+        // Writing the parent would overwrite the read-only child
+        let mut doc = DocumentPermission {
+            can_read: true,
+            can_write: true,
+            ..Default::default()
+        };
+        doc.fields
+            .insert("profile.id".to_string(), rule(true, false));
+        assert!(!doc.allows_field("profile", Access::Write));
+        assert!(doc.allows_field("profile.bio", Access::Write));
+
+        // A field rule cannot lift a read-only document
+        doc.can_write = false;
+        doc.fields.insert("bio".to_string(), rule(true, true));
+        assert!(!doc.allows_field("bio", Access::Write));
+    }
+
+    #[test]
+    fn test_filter_delta() {
+        let mut acl = AccessControl::new();
+        acl.grant("alice".to_string(), permissions());
+
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field("title".to_string(), json!("T"), 1, "bob".to_string());
+        doc.set_field("user.name".to_string(), json!("Ann"), 1, "bob".to_string());
+        doc.set_field("secrets.key".to_string(), json!("k"), 1, "bob".to_string());
+        let delta = SyncDelta::compute(&Document::new("doc-1".to_string()), &doc).unwrap();
+
+        let readable = acl.filter_delta("alice", &delta, Access::Read);
+        let paths: Vec<&str> = readable.fields.keys().map(String::as_str).collect();
+        assert_eq!(paths, vec!["title", "user.name"]);
+        assert_eq!(readable.new_version, delta.new_version);
+
+        let writable = acl.filter_delta("alice", &delta, Access::Write);
+        let paths: Vec<&str> = writable.fields.keys().map(String::as_str).collect();
+        assert_eq!(paths, vec!["title"]);
+
+        assert!(acl.filter_delta("mallory", &delta, Access::Read).is_empty());
+        acl.revoke("alice");
+        assert!(!acl.can_read("alice", "doc-1", "title"));
+    }
+}
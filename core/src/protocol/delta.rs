@@ -1,168 +1,33 @@
-// Delta computation - Calculate minimal changes between document states
+// Delta conversion - Map deltas to and from the protocol format
 //!
-//! This module computes deltas (minimal change sets) between document states
-//! for efficient synchronization over the network.
+//! Deltas themselves are computed and applied by [`crate::sync::delta`];
+//! this module converts a [`SyncDelta`] to and from the protobuf `Delta`
+//! message, so the binary protocol and the JSON path share one delta model.
+//!
+//! Each changed field becomes a protobuf `Field`: a value, or a tombstone
+//! carrying the delete timestamp. Multi-value fields become `Register`
+//! messages.
 
-use crate::document::{Document, Field as DocField};
+use crate::document::Field as DocField;
 use crate::error::{Result, SyncError};
+use crate::protocol::serialize::{json_to_protocol_value, protocol_value_to_json};
 use crate::protocol::*;
-use crate::sync::VectorClock;
+use crate::sync::{Delta as SyncDelta, Dot, MVEntry, MVRegister, VectorClock};
 use std::collections::HashMap;
 
-/// Represents a change in a single field
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct FieldChange {
-    /// Path to the field (e.g., "user.name")
-    pub path: String,
-
-    /// Field with its metadata
-    pub field: DocField,
-
-    /// Whether this is a deletion
-    pub is_delete: bool,
-}
-
-/// A delta represents changes between two document states
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct DocumentDelta {
-    /// Document ID
-    pub document_id: String,
-
-    /// Field changes
-    pub changes: Vec<FieldChange>,
-
-    /// Base version (before changes)
-    pub base_version: VectorClock,
-
-    /// New version (after changes)
-    pub new_version: VectorClock,
-}
-
-impl DocumentDelta {
-    /// Create a new empty delta
-    pub fn new(document_id: String) -> Self {
-        Self {
-            document_id,
-            changes: Vec::new(),
-            base_version: VectorClock::new(),
-            new_version: VectorClock::new(),
-        }
-    }
-
-    /// Compute delta between two documents
-    ///
-    /// Returns the minimal set of changes to transform `from` into `to`
-    pub fn compute(from: &Document, to: &Document) -> Result<Self> {
-        if from.id() != to.id() {
-            return Err(SyncError::InvalidOperation(
-                "Cannot compute delta between different documents".to_string(),
-            ));
-        }
-
-        let mut delta = DocumentDelta::new(from.id().to_string());
-        delta.base_version = from.version().clone();
-        delta.new_version = to.version().clone();
-
-        // Find all changed, added, and removed fields
-        let from_fields = from.fields();
-        let to_fields = to.fields();
-
-        // Check for new or modified fields
-        for (path, to_field) in to_fields {
-            if let Some(from_field) = from_fields.get(path) {
-                // Field exists in both - check if changed
-                if from_field.value != to_field.value || from_field.timestamp != to_field.timestamp
-                {
-                    delta.changes.push(FieldChange {
-                        path: path.clone(),
-                        field: to_field.clone(),
-                        is_delete: false,
-                    });
-                }
-            } else {
-                // New field in 'to'
-                delta.changes.push(FieldChange {
-                    path: path.clone(),
-                    field: to_field.clone(),
-                    is_delete: false,
-                });
-            }
-        }
-
-        // Check for removed fields (tombstones)
-        for (path, from_field) in from_fields {
-            if !to_fields.contains_key(path) {
-                delta.changes.push(FieldChange {
-                    path: path.clone(),
-                    field: from_field.clone(),
-                    is_delete: true,
-                });
-            }
-        }
-
-        Ok(delta)
-    }
-
-    /// Apply this delta to a document
-    pub fn apply_to(&self, document: &mut Document, _client_id: &str) -> Result<()> {
-        if document.id() != &self.document_id {
-            return Err(SyncError::InvalidOperation(
-                "Cannot apply delta to different document".to_string(),
-            ));
-        }
-
-        for change in &self.changes {
-            if !change.is_delete {
-                // Use the field's original timestamp
-                let clock = change.field.timestamp.clock;
-                let original_client = &change.field.timestamp.client_id;
-                document.set_field(
-                    change.path.clone(),
-                    change.field.value.clone(),
-                    clock,
-                    original_client.clone(),
-                );
-            } else {
-                document.delete_field(&change.path);
-            }
-        }
-
-        Ok(())
-    }
-
+impl SyncDelta {
     /// Convert to protocol format
     pub fn to_protocol(&self) -> Delta {
         let changes = self
-            .changes
+            .fields
             .iter()
-            .map(|change| {
-                // Convert to protocol Field
-                Field {
-                    path: Some(FieldPath {
-                        segments: vec![change.path.clone()],
-                    }),
-                    timestamp: Some(Timestamp {
-                        millis: change.field.timestamp.clock as i64,
-                        client_id: Some(ClientId {
-                            id: change.field.timestamp.client_id.clone(),
-                        }),
-                    }),
-                    content: if change.is_delete {
-                        Some(field::Content::Tombstone(Tombstone {
-                            deleted_at: Some(Timestamp {
-                                millis: chrono::Utc::now().timestamp_millis(),
-                                client_id: Some(ClientId {
-                                    id: change.field.timestamp.client_id.clone(),
-                                }),
-                            }),
-                        }))
-                    } else {
-                        Some(field::Content::Value(
-                            crate::protocol::serialize::json_to_protocol_value(&change.field.value),
-                        ))
-                    },
-                }
-            })
+            .map(|(path, field)| field_to_protocol(path, field))
+            .collect();
+
+        let registers = self
+            .registers
+            .iter()
+            .map(|(path, register)| register_to_protocol(path, register))
             .collect();
 
         Delta {
@@ -174,10 +39,14 @@ impl DocumentDelta {
             changes,
             client_id: None,
             created_at: None,
+            registers,
         }
     }
 
     /// Create from protocol format
+    ///
+    /// `client_id` stands in for timestamps that carry no client ID. A path
+    /// listed more than once keeps its newest write.
     pub fn from_protocol(proto: &Delta, client_id: &str) -> Result<Self> {
         let document_id = proto
             .document_id
@@ -197,58 +66,170 @@ impl DocumentDelta {
             .map(vector_clock_from_protocol)
             .unwrap_or_default();
 
-        let changes = proto
-            .changes
+        let mut delta = SyncDelta::new(document_id, Default::default(), new_version)
+            .with_base_version(base_version);
+
+        for change in &proto.changes {
+            let (path, field) = field_from_protocol(change, client_id)?;
+            let newer = delta
+                .fields
+                .get(&path)
+                .is_none_or(|existing| field.timestamp.is_newer_than(&existing.timestamp));
+            if newer {
+                delta.fields.insert(path, field);
+            }
+        }
+
+        for register in &proto.registers {
+            let (path, register) = register_from_protocol(register, client_id)?;
+            delta.registers.entry(path).or_default().merge(&register);
+        }
+
+        Ok(delta)
+    }
+}
+
+fn timestamp_to_protocol(timestamp: &crate::sync::Timestamp) -> Timestamp {
+    Timestamp {
+        millis: timestamp.clock as i64,
+        client_id: Some(ClientId {
+            id: timestamp.client_id.clone(),
+        }),
+    }
+}
+
+fn path_from_protocol(path: Option<&FieldPath>) -> Result<String> {
+    path.filter(|p| !p.segments.is_empty())
+        .map(|p| crate::path::from_segments(&p.segments))
+        .ok_or_else(|| SyncError::Protocol("Missing field path".to_string()))
+}
+
+fn field_to_protocol(path: &str, field: &DocField) -> Field {
+    let content = if field.is_deleted() {
+        field::Content::Tombstone(Tombstone {
+            deleted_at: Some(timestamp_to_protocol(&field.timestamp)),
+        })
+    } else {
+        field::Content::Value(json_to_protocol_value(&field.value))
+    };
+
+    Field {
+        path: Some(FieldPath {
+            segments: crate::path::to_segments(path),
+        }),
+        timestamp: Some(timestamp_to_protocol(&field.timestamp)),
+        content: Some(content),
+        redacted: field.is_redacted(),
+    }
+}
+
+fn field_from_protocol(proto: &Field, client_id: &str) -> Result<(String, DocField)> {
+    let path = path_from_protocol(proto.path.as_ref())?;
+
+    let timestamp_proto = proto
+        .timestamp
+        .as_ref()
+        .ok_or_else(|| SyncError::Protocol("Missing timestamp".to_string()))?;
+
+    let to_timestamp = |proto: &Timestamp| {
+        crate::sync::Timestamp::new(
+            proto.millis as u64,
+            proto
+                .client_id
+                .as_ref()
+                .map(|c| c.id.clone())
+                .unwrap_or_else(|| client_id.to_string()),
+        )
+    };
+
+    let field = match &proto.content {
+        Some(field::Content::Tombstone(tombstone)) => {
+            // Prefer the tombstone's own deletion time
+            let deleted_at = tombstone.deleted_at.as_ref().unwrap_or(timestamp_proto);
+            DocField::tombstone(to_timestamp(deleted_at))
+        }
+        Some(field::Content::Value(v)) => {
+            DocField::new(protocol_value_to_json(v)?, to_timestamp(timestamp_proto))
+        }
+        None => DocField::new(serde_json::Value::Null, to_timestamp(timestamp_proto)),
+    };
+
+    Ok((
+        path,
+        DocField {
+            redacted: proto.redacted,
+            ..field
+        },
+    ))
+}
+
+fn register_to_protocol(path: &str, register: &MVRegister) -> Register {
+    Register {
+        path: Some(FieldPath {
+            segments: crate::path::to_segments(path),
+        }),
+        entries: register
+            .entries()
             .iter()
-            .map(|field| {
-                let path = field
-                    .path
-                    .as_ref()
-                    .and_then(|p| p.segments.first())
-                    .ok_or_else(|| SyncError::Protocol("Missing field path".to_string()))?
-                    .clone();
-
-                let timestamp_proto = field
-                    .timestamp
-                    .as_ref()
-                    .ok_or_else(|| SyncError::Protocol("Missing timestamp".to_string()))?;
-
-                let timestamp = crate::sync::Timestamp::new(
-                    timestamp_proto.millis as u64,
-                    timestamp_proto
+            .map(|entry| RegisterEntry {
+                client_id: Some(ClientId {
+                    id: entry.dot.client_id.clone(),
+                }),
+                counter: entry.dot.counter as i64,
+                value: Some(json_to_protocol_value(&entry.value)),
+                timestamp: entry.timestamp.as_ref().map(timestamp_to_protocol),
+            })
+            .collect(),
+        context: Some(vector_clock_to_protocol(register.context())),
+    }
+}
+
+fn register_from_protocol(proto: &Register, client_id: &str) -> Result<(String, MVRegister)> {
+    let path = path_from_protocol(proto.path.as_ref())?;
+
+    let entries = proto
+        .entries
+        .iter()
+        .map(|entry| {
+            let writer = entry
+                .client_id
+                .as_ref()
+                .map(|c| c.id.clone())
+                .unwrap_or_else(|| client_id.to_string());
+            let value = match &entry.value {
+                Some(value) => protocol_value_to_json(value)?,
+                None => serde_json::Value::Null,
+            };
+
+            let timestamp = entry.timestamp.as_ref().map(|timestamp| {
+                crate::sync::Timestamp::new(
+                    timestamp.millis as u64,
+                    timestamp
                         .client_id
                         .as_ref()
-                        .map(|c| c.id.clone())
-                        .unwrap_or_else(|| client_id.to_string()),
-                );
-
-                let is_delete = matches!(field.content, Some(field::Content::Tombstone(_)));
-
-                let value = if let Some(field::Content::Value(v)) = &field.content {
-                    crate::protocol::serialize::protocol_value_to_json(v)?
-                } else {
-                    serde_json::Value::Null
-                };
-
-                Ok(FieldChange {
-                    path,
-                    field: DocField { value, timestamp },
-                    is_delete,
-                })
+                        .map_or_else(|| writer.clone(), |c| c.id.clone()),
+                )
+            });
+
+            Ok(MVEntry {
+                dot: Dot::new(writer, entry.counter as u64),
+                value,
+                timestamp,
             })
-            .collect::<Result<Vec<_>>>()?;
-
-        Ok(Self {
-            document_id,
-            changes,
-            base_version,
-            new_version,
         })
-    }
+        .collect::<Result<Vec<_>>>()?;
+
+    let context = proto
+        .context
+        .as_ref()
+        .map(vector_clock_from_protocol)
+        .unwrap_or_default();
+
+    Ok((path, MVRegister::from_parts(entries, context)))
 }
 
 /// Convert VectorClock to protocol format
-fn vector_clock_to_protocol(vc: &VectorClock) -> crate::protocol::VectorClock {
+pub(crate) fn vector_clock_to_protocol(vc: &VectorClock) -> crate::protocol::VectorClock {
     let mut clocks = HashMap::new();
     for (client_id, clock) in &vc.clocks {
         clocks.insert(client_id.clone(), *clock as i64);
@@ -258,7 +239,7 @@ fn vector_clock_to_protocol(vc: &VectorClock) -> crate::protocol::VectorClock {
 }
 
 /// Convert protocol VectorClock to internal format
-fn vector_clock_from_protocol(proto: &crate::protocol::VectorClock) -> VectorClock {
+pub(crate) fn vector_clock_from_protocol(proto: &crate::protocol::VectorClock) -> VectorClock {
     let mut vc = VectorClock::new();
     for (client_id, clock) in &proto.clocks {
         vc.update(client_id, *clock as u64);
@@ -269,68 +250,177 @@ fn vector_clock_from_protocol(proto: &crate::protocol::VectorClock) -> VectorClo
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::document::{Document, FieldMode};
+    use serde_json::json;
 
     #[test]
     fn test_delta_computation() {
         let mut doc1 = Document::new("doc-1".to_string());
-        doc1.set_field(
-            "name".to_string(),
-            serde_json::json!("Alice"),
-            1,
-            "client1".to_string(),
-        );
-        doc1.set_field(
-            "age".to_string(),
-            serde_json::json!(30),
-            2,
-            "client1".to_string(),
-        );
+        doc1.set_field("name".to_string(), json!("Alice"), 1, "client1".to_string());
+        doc1.set_field("age".to_string(), json!(30), 2, "client1".to_string());
+
+        let mut doc2 = doc1.clone();
+        doc2.set_field("age".to_string(), json!(31), 3, "client1".to_string());
+        doc2.set_field("city".to_string(), json!("NYC"), 4, "client1".to_string());
+
+        let delta = SyncDelta::compute(&doc1, &doc2).unwrap();
+
+        // Should have 2 changes: age modified, city added
+        assert_eq!(delta.len(), 2);
+
+        let other = Document::new("doc-2".to_string());
+        assert!(SyncDelta::compute(&doc1, &other).is_err());
+    }
+
+    #[test]
+    fn test_delta_protocol_conversion() {
+        let mut doc1 = Document::new("doc-1".to_string());
+        doc1.set_field("name".to_string(), json!("Bob"), 1, "client1".to_string());
+        doc1.version.update(&"client1".to_string(), 1);
 
         let mut doc2 = doc1.clone();
+        doc2.set_field("name".to_string(), json!("Alice"), 2, "client1".to_string());
         doc2.set_field(
-            "age".to_string(),
-            serde_json::json!(31),
+            "tags".to_string(),
+            json!(["a", 1, null]),
             3,
-            "client1".to_string(),
-        );
-        doc2.set_field(
-            "city".to_string(),
-            serde_json::json!("NYC"),
-            4,
-            "client1".to_string(),
+            "client2".to_string(),
         );
+        doc2.delete_field("gone".to_string(), 4, "client2".to_string());
+        doc2.version.update(&"client1".to_string(), 2);
+        doc2.version.update(&"client2".to_string(), 4);
 
-        let delta = DocumentDelta::compute(&doc1, &doc2).unwrap();
+        let delta = SyncDelta::compute(&doc1, &doc2).unwrap();
+        assert_eq!(delta.base_version, doc1.version);
+        assert_eq!(delta.new_version, doc2.version);
 
-        // Should have 2 changes: age modified, city added
-        assert_eq!(delta.changes.len(), 2);
+        // Convert to protocol and back: nothing is lost
+        let proto = delta.to_protocol();
+        assert_eq!(proto.changes.len(), 3);
+        let delta2 = SyncDelta::from_protocol(&proto, "client1").unwrap();
+        assert_eq!(delta2, delta);
+
+        // And the JSON form carries the same delta
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(serde_json::from_str::<SyncDelta>(&json).unwrap(), delta);
     }
 
     #[test]
-    fn test_delta_protocol_conversion() {
+    fn test_delta_tombstone_roundtrip() {
         let mut doc1 = Document::new("doc-1".to_string());
-        doc1.set_field(
+        doc1.set_field("name".to_string(), json!("Bob"), 1, "client1".to_string());
+
+        let mut doc2 = doc1.clone();
+        doc2.delete_field("name".to_string(), 2, "client2".to_string());
+
+        let delta = SyncDelta::compute(&doc1, &doc2).unwrap();
+        assert_eq!(delta.len(), 1);
+        assert_eq!(delta.deleted_paths().collect::<Vec<_>>(), vec!["name"]);
+
+        // Tombstone carries the delete timestamp over the wire
+        let proto = delta.to_protocol();
+        match &proto.changes[0].content {
+            Some(field::Content::Tombstone(tombstone)) => {
+                let deleted_at = tombstone.deleted_at.as_ref().unwrap();
+                assert_eq!(deleted_at.millis, 2);
+                assert_eq!(deleted_at.client_id.as_ref().unwrap().id, "client2");
+            }
+            other => panic!("Expected tombstone, got {:?}", other),
+        }
+
+        let delta2 = SyncDelta::from_protocol(&proto, "client1").unwrap();
+        assert!(delta2.fields["name"].is_deleted());
+        assert_eq!(delta2.fields["name"].timestamp.clock, 2);
+
+        let mut replica = doc1.clone();
+        delta2.apply_to(&mut replica).unwrap();
+        assert_eq!(replica.get_field(&"name".to_string()), None);
+    }
+
+    #[test]
+    fn test_delta_stale_changes_lose() {
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field("name".to_string(), json!("Alice"), 5, "client1".to_string());
+        doc.set_field("role".to_string(), json!("admin"), 5, "client1".to_string());
+
+        let mut fields = std::collections::BTreeMap::new();
+        fields.insert(
             "name".to_string(),
-            serde_json::json!("Bob"),
+            DocField::tombstone(crate::sync::Timestamp::new(3, "client2".to_string())),
+        );
+        fields.insert(
+            "role".to_string(),
+            DocField::new(
+                json!("guest"),
+                crate::sync::Timestamp::new(4, "client2".to_string()),
+            ),
+        );
+        let delta = SyncDelta::new("doc-1".to_string(), fields, VectorClock::new());
+
+        // Older writes lose, on the protobuf path as on the JSON path
+        let delta = SyncDelta::from_protocol(&delta.to_protocol(), "client2").unwrap();
+        delta.apply_to(&mut doc).unwrap();
+        assert_eq!(doc.get_field(&"name".to_string()), Some(&json!("Alice")));
+        assert_eq!(doc.get_field(&"role".to_string()), Some(&json!("admin")));
+
+        let mut other = Document::new("doc-2".to_string());
+        assert!(delta.apply_to(&mut other).is_err());
+    }
+
+    #[test]
+    fn test_delta_nested_path_segments() {
+        let from = Document::new("doc-1".to_string());
+        let mut to = from.clone();
+        to.set_field(
+            "items[0].name".to_string(),
+            json!("Widget"),
             1,
             "client1".to_string(),
         );
 
-        let mut doc2 = doc1.clone();
-        doc2.set_field(
-            "name".to_string(),
-            serde_json::json!("Alice"),
-            2,
-            "client1".to_string(),
+        let delta = SyncDelta::compute(&from, &to).unwrap();
+        let proto = delta.to_protocol();
+        assert_eq!(
+            proto.changes[0].path.as_ref().unwrap().segments,
+            vec!["items", "[0]", "name"]
         );
 
-        let delta = DocumentDelta::compute(&doc1, &doc2).unwrap();
+        let delta2 = SyncDelta::from_protocol(&proto, "client1").unwrap();
+        assert!(delta2.fields.contains_key("items[0].name"));
 
-        // Convert to protocol and back
-        let proto = delta.to_protocol();
-        let delta2 = DocumentDelta::from_protocol(&proto, "client1").unwrap();
+        let mut replica = from.clone();
+        delta2.apply_to(&mut replica).unwrap();
+        assert_eq!(
+            replica.to_json(),
+            json!({ "items": [{ "name": "Widget" }] })
+        );
+    }
 
-        assert_eq!(delta.document_id, delta2.document_id);
-        assert_eq!(delta.changes.len(), delta2.changes.len());
+    #[test]
+    fn test_delta_register_roundtrip() {
+        let mut base = Document::new("doc-1".to_string());
+        base.set_field_mode("assignee".to_string(), FieldMode::MultiValue)
+            .unwrap();
+
+        let mut alice = base.clone();
+        let mut bob = base.clone();
+        alice.set_field(
+            "assignee".to_string(),
+            json!("alice"),
+            1,
+            "alice".to_string(),
+        );
+        bob.set_field("assignee".to_string(), json!("bob"), 1, "bob".to_string());
+        alice.merge(&bob);
+
+        let delta = SyncDelta::compute(&base, &alice).unwrap();
+        let delta2 = SyncDelta::from_protocol(&delta.to_protocol(), "server").unwrap();
+        assert_eq!(delta2, delta);
+
+        delta2.apply_to(&mut base).unwrap();
+        assert_eq!(
+            base.get_field_values(&"assignee".to_string()),
+            vec![&json!("alice"), &json!("bob")]
+        );
     }
 }
@@ -41,6 +41,8 @@ pub struct DocumentId {
 pub struct FieldPath {
     /// Dot-separated path (e.g., "user.profile.name")
     /// Or array of path segments for nested objects
+    /// (e.g., \["user", "profile", "name"\]); array indices are
+    /// encoded as "\[n\]" segments (e.g., \["items", "[0\]"])
     #[prost(string, repeated, tag = "1")]
     pub segments: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
@@ -168,6 +170,10 @@ pub struct Field {
     /// Last-write timestamp for LWW resolution
     #[prost(message, optional, tag = "4")]
     pub timestamp: ::core::option::Option<Timestamp>,
+    /// Parts of the value were cut out for the recipient; the full value
+    /// wins over it when merged back
+    #[prost(bool, tag = "5")]
+    pub redacted: bool,
     /// Current value (or tombstone if deleted)
     #[prost(oneof = "field::Content", tags = "2, 3")]
     pub content: ::core::option::Option<field::Content>,
@@ -227,12 +233,46 @@ pub struct Delta {
     /// Timestamp when delta was created
     #[prost(message, optional, tag = "6")]
     pub created_at: ::core::option::Option<Timestamp>,
+    /// Changed multi-value fields
+    #[prost(message, repeated, tag = "7")]
+    pub registers: ::prost::alloc::vec::Vec<Register>,
+}
+/// Multi-value register: a field keeping all concurrent writes
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Register {
+    /// Field path within document
+    #[prost(message, optional, tag = "1")]
+    pub path: ::core::option::Option<FieldPath>,
+    /// Current values, one per unresolved concurrent write
+    #[prost(message, repeated, tag = "2")]
+    pub entries: ::prost::alloc::vec::Vec<RegisterEntry>,
+    /// All writes seen, overwritten ones included
+    #[prost(message, optional, tag = "3")]
+    pub context: ::core::option::Option<VectorClock>,
+}
+/// One value of a multi-value register
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterEntry {
+    /// Client that wrote the value
+    #[prost(message, optional, tag = "1")]
+    pub client_id: ::core::option::Option<ClientId>,
+    /// Per-client write counter (with client_id, identifies the write)
+    #[prost(int64, tag = "2")]
+    pub counter: i64,
+    /// Value written
+    #[prost(message, optional, tag = "3")]
+    pub value: ::core::option::Option<Value>,
+    /// Timestamp of the write, if it was made with one
+    #[prost(message, optional, tag = "4")]
+    pub timestamp: ::core::option::Option<Timestamp>,
 }
 /// Checkpoint for resuming sync
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SyncCheckpoint {
-    /// Client's current vector clock
+    /// Client's current vector clock (for a single document)
     #[prost(message, optional, tag = "1")]
     pub version: ::core::option::Option<VectorClock>,
     /// Last sync timestamp
@@ -241,6 +281,12 @@ pub struct SyncCheckpoint {
     /// Documents synced (optional, for partial sync)
     #[prost(message, repeated, tag = "3")]
     pub documents: ::prost::alloc::vec::Vec<DocumentId>,
+    /// Version of each synced document, by document ID
+    #[prost(map = "string, message", tag = "4")]
+    pub versions: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        VectorClock,
+    >,
 }
 /// Text operation for CRDT text editing (Tier 2)
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -269,6 +315,9 @@ pub struct TextOperation {
     /// Timestamp
     #[prost(message, optional, tag = "8")]
     pub timestamp: ::core::option::Option<Timestamp>,
+    /// Right origin of an insert (Fugue CRDT); parent_id is the left origin
+    #[prost(string, tag = "9")]
+    pub right_origin: ::prost::alloc::string::String,
 }
 /// Nested message and enum types in `TextOperation`.
 pub mod text_operation {
@@ -370,7 +419,7 @@ pub mod set_operation {
 }
 /// Counter operation for PN-Counter CRDT (Tier 3)
 #[derive(serde::Serialize, serde::Deserialize)]
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CounterOperation {
     #[prost(enumeration = "counter_operation::OpType", tag = "1")]
     pub op_type: i32,
@@ -380,6 +429,10 @@ pub struct CounterOperation {
     /// Client performing operation
     #[prost(message, optional, tag = "3")]
     pub client_id: ::core::option::Option<ClientId>,
+    /// Full per-replica state. When set, it replaces op_type/amount, which
+    /// only carry the total value for older readers.
+    #[prost(message, repeated, tag = "4")]
+    pub entries: ::prost::alloc::vec::Vec<CounterEntry>,
 }
 /// Nested message and enum types in `CounterOperation`.
 pub mod counter_operation {
@@ -422,6 +475,17 @@ pub mod counter_operation {
         }
     }
 }
+/// Increments and decrements of one replica of a PN-Counter
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct CounterEntry {
+    #[prost(string, tag = "1")]
+    pub replica_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub positive: i64,
+    #[prost(int64, tag = "3")]
+    pub negative: i64,
+}
 /// Generic CRDT operation wrapper (Tier 3)
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -528,6 +592,10 @@ pub struct SyncRequest {
     /// Maximum deltas to receive in response
     #[prost(int32, tag = "6")]
     pub max_deltas: i32,
+    /// next_page_token of the previous response, to fetch the next page
+    /// (document_ids, full_sync and checkpoint are then taken from the token)
+    #[prost(string, tag = "7")]
+    pub page_token: ::prost::alloc::string::String,
 }
 /// Server responds with changes
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -755,3 +823,273 @@ pub struct Pong {
     #[prost(message, optional, tag = "2")]
     pub pong_sent_at: ::core::option::Option<Timestamp>,
 }
+/// Authentication request
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AuthRequest {
+    #[prost(enumeration = "auth_request::AuthMethod", tag = "1")]
+    pub method: i32,
+    /// Client information
+    #[prost(message, optional, tag = "4")]
+    pub client_info: ::core::option::Option<ClientInfo>,
+    /// Credentials (method-specific)
+    #[prost(oneof = "auth_request::Credentials", tags = "2, 3")]
+    pub credentials: ::core::option::Option<auth_request::Credentials>,
+}
+/// Nested message and enum types in `AuthRequest`.
+pub mod auth_request {
+    /// Authentication method
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum AuthMethod {
+        /// JWT token authentication
+        Jwt = 0,
+        /// API key authentication
+        ApiKey = 1,
+        /// Anonymous (read-only)
+        Anonymous = 2,
+    }
+    impl AuthMethod {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Jwt => "JWT",
+                Self::ApiKey => "API_KEY",
+                Self::Anonymous => "ANONYMOUS",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "JWT" => Some(Self::Jwt),
+                "API_KEY" => Some(Self::ApiKey),
+                "ANONYMOUS" => Some(Self::Anonymous),
+                _ => None,
+            }
+        }
+    }
+    /// Credentials (method-specific)
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    pub enum Credentials {
+        /// JWT token
+        #[prost(string, tag = "2")]
+        JwtToken(::prost::alloc::string::String),
+        /// API key
+        #[prost(string, tag = "3")]
+        ApiKey(::prost::alloc::string::String),
+    }
+}
+/// Client information
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClientInfo {
+    /// Client ID (persistent across sessions)
+    #[prost(message, optional, tag = "1")]
+    pub client_id: ::core::option::Option<ClientId>,
+    #[prost(enumeration = "client_info::ClientType", tag = "2")]
+    pub client_type: i32,
+    /// Client version
+    #[prost(string, tag = "3")]
+    pub version: ::prost::alloc::string::String,
+    /// Platform details (e.g., "Chrome 120.0", "iOS 17.2")
+    #[prost(string, tag = "4")]
+    pub platform: ::prost::alloc::string::String,
+}
+/// Nested message and enum types in `ClientInfo`.
+pub mod client_info {
+    /// Client type
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum ClientType {
+        Web = 0,
+        Mobile = 1,
+        Desktop = 2,
+        Server = 3,
+    }
+    impl ClientType {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Web => "WEB",
+                Self::Mobile => "MOBILE",
+                Self::Desktop => "DESKTOP",
+                Self::Server => "SERVER",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "WEB" => Some(Self::Web),
+                "MOBILE" => Some(Self::Mobile),
+                "DESKTOP" => Some(Self::Desktop),
+                "SERVER" => Some(Self::Server),
+                _ => None,
+            }
+        }
+    }
+}
+/// Authentication response
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthResponse {
+    /// Authentication status
+    #[prost(enumeration = "Status", tag = "1")]
+    pub status: i32,
+    /// Error message if authentication failed
+    #[prost(string, tag = "2")]
+    pub error_message: ::prost::alloc::string::String,
+    /// Session token (if authenticated)
+    #[prost(string, tag = "3")]
+    pub session_token: ::prost::alloc::string::String,
+    /// Session expiry (Unix timestamp)
+    #[prost(int64, tag = "4")]
+    pub expires_at: i64,
+    /// User/account information
+    #[prost(message, optional, tag = "5")]
+    pub user_info: ::core::option::Option<UserInfo>,
+    /// Permissions granted
+    #[prost(message, optional, tag = "6")]
+    pub permissions: ::core::option::Option<Permissions>,
+}
+/// User information
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserInfo {
+    /// User ID
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// User email (optional)
+    #[prost(string, tag = "2")]
+    pub email: ::prost::alloc::string::String,
+    /// User display name (optional)
+    #[prost(string, tag = "3")]
+    pub display_name: ::prost::alloc::string::String,
+    /// Account metadata
+    #[prost(map = "string, string", tag = "4")]
+    pub metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+/// Permissions (RBAC)
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Permissions {
+    /// Document-level permissions
+    #[prost(map = "string, message", tag = "1")]
+    pub documents: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        DocumentPermission,
+    >,
+    /// Global permissions
+    #[prost(bool, tag = "2")]
+    pub can_create_documents: bool,
+    #[prost(bool, tag = "3")]
+    pub can_delete_documents: bool,
+    #[prost(bool, tag = "4")]
+    pub can_manage_users: bool,
+}
+/// Permission for a specific document
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DocumentPermission {
+    /// Can read document
+    #[prost(bool, tag = "1")]
+    pub can_read: bool,
+    /// Can write (modify) document
+    #[prost(bool, tag = "2")]
+    pub can_write: bool,
+    /// Can delete document
+    #[prost(bool, tag = "3")]
+    pub can_delete: bool,
+    /// Can share document with others
+    #[prost(bool, tag = "4")]
+    pub can_share: bool,
+    /// Field-level permissions (optional)
+    #[prost(map = "string, message", tag = "5")]
+    pub fields: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        FieldPermission,
+    >,
+}
+/// Permission for a specific field
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FieldPermission {
+    /// Can read field
+    #[prost(bool, tag = "1")]
+    pub can_read: bool,
+    /// Can write field
+    #[prost(bool, tag = "2")]
+    pub can_write: bool,
+}
+/// Token refresh request
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RefreshRequest {
+    /// Current session token
+    #[prost(string, tag = "1")]
+    pub session_token: ::prost::alloc::string::String,
+    /// Optional: refresh token (for long-lived sessions)
+    #[prost(string, tag = "2")]
+    pub refresh_token: ::prost::alloc::string::String,
+}
+/// Token refresh response
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RefreshResponse {
+    /// Status
+    #[prost(enumeration = "Status", tag = "1")]
+    pub status: i32,
+    /// New session token
+    #[prost(string, tag = "2")]
+    pub session_token: ::prost::alloc::string::String,
+    /// New expiry time
+    #[prost(int64, tag = "3")]
+    pub expires_at: i64,
+}
+/// Logout request
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LogoutRequest {
+    /// Session token to invalidate
+    #[prost(string, tag = "1")]
+    pub session_token: ::prost::alloc::string::String,
+}
+/// Logout response
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LogoutResponse {
+    /// Status
+    #[prost(enumeration = "Status", tag = "1")]
+    pub status: i32,
+}
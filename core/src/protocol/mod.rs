@@ -5,6 +5,7 @@
 //! - Serialization/deserialization for CRDTs
 //! - Delta computation and sync primitives
 //! - WebSocket message handling
+//! - Authentication messages and field-level access checks
 
 // Include generated protocol buffer code
 #[allow(clippy::all)]
@@ -24,3 +25,6 @@ pub mod delta;
 
 // Sync coordinator
 pub mod sync;
+
+// Permission checks
+pub mod auth;
@@ -11,16 +11,48 @@ use prost::Message;
 // Import CRDTs only if their features are enabled
 #[cfg(feature = "counters")]
 use crate::crdt::PNCounter;
+#[cfg(feature = "counters")]
+use crate::ClientID;
+#[cfg(feature = "counters")]
+use std::collections::HashMap;
 
 #[cfg(feature = "sets")]
 use crate::crdt::ORSet;
+#[cfg(feature = "sets")]
+use crate::crdt::UniqueTag;
+#[cfg(feature = "sets")]
+use crate::sync::VectorClock;
+
+#[cfg(feature = "text-crdt")]
+use crate::crdt::text_fugue::{FugueBlock, FugueOp, NodeId, NodeIdRange};
 
 /// Serialize a PN-Counter to protocol format
+///
+/// Carries the increments and decrements of every replica in `entries`, so
+/// the receiver can merge it like the original. `op_type`/`amount` still
+/// hold the total value for readers that predate `entries`.
 #[cfg(feature = "counters")]
 pub fn serialize_pn_counter(counter: &PNCounter, client_id: &str) -> CounterOperation {
     // Get the current value
     let value = counter.value();
 
+    let mut replicas: Vec<&ClientID> = counter
+        .positive()
+        .keys()
+        .chain(counter.negative().keys())
+        .collect();
+    replicas.sort();
+    replicas.dedup();
+
+    let entries = replicas
+        .into_iter()
+        .map(|replica| CounterEntry {
+            replica_id: replica.clone(),
+            positive: counter.positive().get(replica).copied().unwrap_or(0),
+            negative: counter.negative().get(replica).copied().unwrap_or(0),
+        })
+        .collect();
+
     CounterOperation {
         op_type: if value >= 0 {
             counter_operation::OpType::Increment as i32
@@ -31,12 +63,40 @@ pub fn serialize_pn_counter(counter: &PNCounter, client_id: &str) -> CounterOper
         client_id: Some(ClientId {
             id: client_id.to_string(),
         }),
+        entries,
     }
 }
 
 /// Deserialize a PN-Counter from protocol format
+///
+/// `client_id` is the replica the counter will write as. Operations
+/// without `entries` come from older writers: only their total is known,
+/// so it is rebuilt as a single increment or decrement by `client_id`, and
+/// must not be merged with other replicas of the counter.
 #[cfg(feature = "counters")]
 pub fn deserialize_pn_counter(op: &CounterOperation, client_id: &str) -> Result<PNCounter> {
+    if !op.entries.is_empty() {
+        let mut positive = HashMap::new();
+        let mut negative = HashMap::new();
+
+        for entry in &op.entries {
+            if entry.positive < 0 || entry.negative < 0 {
+                return Err(SyncError::Protocol(format!(
+                    "Invalid counter entry for {}: negative count",
+                    entry.replica_id
+                )));
+            }
+            positive.insert(entry.replica_id.clone(), entry.positive);
+            negative.insert(entry.replica_id.clone(), entry.negative);
+        }
+
+        return Ok(PNCounter::from_parts(
+            client_id.to_string(),
+            positive,
+            negative,
+        ));
+    }
+
     let mut counter = PNCounter::new(client_id.to_string());
 
     match counter_operation::OpType::try_from(op.op_type) {
@@ -61,49 +121,118 @@ pub fn deserialize_pn_counter(op: &CounterOperation, client_id: &str) -> Result<
 }
 
 /// Serialize an OR-Set to protocol format
+///
+/// Emits every operation the set holds: one `ADD` per tag, with the tag in
+/// `tag`, and one `REMOVE` per remove, with the remove's own tag in `tag`
+/// and the tags it removed in `remove_tags`. Tags are written in the
+/// `replica@timestamp:sequence` form. The receiver rebuilds a set that
+/// merges exactly like this one.
 #[cfg(feature = "sets")]
 pub fn serialize_or_set<T>(set: &ORSet<T>, _client_id: &str) -> Vec<SetOperation>
 where
     T: serde::Serialize + Clone + Eq + std::hash::Hash,
 {
+    serialize_or_set_since(set, &VectorClock::new())
+}
+
+/// Serialize the OR-Set operations a peer at `since` has not seen
+///
+/// `since` is the peer's `ORSet::version`; the operations are those of
+/// `ORSet::delta_since`. Removed tags without a tagged remove (from sets
+/// saved before removes were tagged) cannot be dated, so they are always
+/// sent, in a `REMOVE` with an empty `tag`.
+#[cfg(feature = "sets")]
+pub fn serialize_or_set_since<T>(set: &ORSet<T>, since: &VectorClock) -> Vec<SetOperation>
+where
+    T: serde::Serialize + Clone + Eq + std::hash::Hash,
+{
+    let delta = set.delta_since(since);
     let mut operations = Vec::new();
 
-    for element in set.iter() {
+    for (element, tag) in delta.adds() {
         // Serialize element to JSON for Value encoding
         if let Ok(json_value) = serde_json::to_value(element) {
             operations.push(SetOperation {
                 op_type: set_operation::OpType::Add as i32,
                 element: Some(json_to_protocol_value(&json_value)),
-                tag: format!("{}", chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0)),
+                tag: tag.to_string(),
                 remove_tags: vec![],
             });
         }
     }
 
+    for (remove, tags) in delta.removes() {
+        operations.push(SetOperation {
+            op_type: set_operation::OpType::Remove as i32,
+            element: None,
+            tag: remove.to_string(),
+            remove_tags: tags.iter().map(UniqueTag::to_string).collect(),
+        });
+    }
+
+    let untagged: Vec<String> = delta.untagged_removes().map(UniqueTag::to_string).collect();
+    if !untagged.is_empty() {
+        operations.push(SetOperation {
+            op_type: set_operation::OpType::Remove as i32,
+            element: None,
+            tag: String::new(),
+            remove_tags: untagged,
+        });
+    }
+
     operations
 }
 
 /// Deserialize an OR-Set from protocol format
+///
+/// `client_id` is the replica the set will write as.
 #[cfg(feature = "sets")]
 pub fn deserialize_or_set<T>(operations: &[SetOperation], client_id: &str) -> Result<ORSet<T>>
 where
     T: serde::de::DeserializeOwned + Eq + std::hash::Hash + Clone + serde::Serialize,
 {
     let mut set = ORSet::new(client_id.to_string());
+    apply_or_set_operations(&mut set, operations)?;
+    Ok(set)
+}
+
+/// Apply OR-Set operations from protocol format to an existing set
+///
+/// Operations may arrive in any order and more than once.
+#[cfg(feature = "sets")]
+pub fn apply_or_set_operations<T>(set: &mut ORSet<T>, operations: &[SetOperation]) -> Result<()>
+where
+    T: serde::de::DeserializeOwned + Eq + std::hash::Hash + Clone + serde::Serialize,
+{
+    let parse = |s: &str| {
+        s.parse::<UniqueTag>()
+            .map_err(|_| SyncError::Protocol(format!("Invalid set operation tag: {}", s)))
+    };
 
     for op in operations {
         match set_operation::OpType::try_from(op.op_type) {
             Ok(set_operation::OpType::Add) => {
-                if let Some(value) = &op.element {
-                    let json_value = protocol_value_to_json(value)?;
-                    let element: T = serde_json::from_value(json_value).map_err(|e| {
-                        SyncError::Protocol(format!("Failed to deserialize element: {}", e))
-                    })?;
-                    set.add(element);
-                }
+                let value = op.element.as_ref().ok_or_else(|| {
+                    SyncError::Protocol("Set add operation without element".to_string())
+                })?;
+                let json_value = protocol_value_to_json(value)?;
+                let element: T = serde_json::from_value(json_value).map_err(|e| {
+                    SyncError::Protocol(format!("Failed to deserialize element: {}", e))
+                })?;
+                set.apply_add(element, parse(&op.tag)?);
             }
             Ok(set_operation::OpType::Remove) => {
-                // Handle remove operations if needed
+                let remove = if op.tag.is_empty() {
+                    None
+                } else {
+                    Some(parse(&op.tag)?)
+                };
+                let tags = op
+                    .remove_tags
+                    .iter()
+                    .map(|tag| parse(tag))
+                    .collect::<Result<_>>()?;
+                set.apply_remove(remove, tags);
             }
             Err(_) => {
                 return Err(SyncError::Protocol(
@@ -113,7 +242,100 @@ where
         }
     }
 
-    Ok(set)
+    Ok(())
+}
+
+/// Serialize a FugueText operation to protocol format
+///
+/// An insert becomes one `TextOperation` whose `op_id` is the block's
+/// NodeId and whose `parent_id`/`right_origin` are its origins. A delete
+/// becomes one `TextOperation` per deleted range, all sharing the delete's
+/// `op_id`, with the range given by its first NodeId in `parent_id` plus
+/// `length`. `position` is left at 0: operations are placed by NodeId,
+/// never by index.
+#[cfg(feature = "text-crdt")]
+pub fn serialize_fugue_op(op: &FugueOp) -> Vec<TextOperation> {
+    let origin = |id: &Option<NodeId>| id.as_ref().map(NodeId::to_string).unwrap_or_default();
+
+    match op {
+        FugueOp::Insert(block) => vec![TextOperation {
+            op_type: text_operation::OpType::Insert as i32,
+            content: block.text.clone(),
+            op_id: block.id.to_string(),
+            parent_id: origin(&block.left_origin),
+            right_origin: origin(&block.right_origin),
+            client_id: Some(ClientId {
+                id: block.id.client_id.clone(),
+            }),
+            ..Default::default()
+        }],
+        FugueOp::Delete { id, ranges } => ranges
+            .iter()
+            .map(|range| TextOperation {
+                op_type: text_operation::OpType::Delete as i32,
+                length: range.len() as i64,
+                op_id: id.to_string(),
+                parent_id: range.first().to_string(),
+                client_id: Some(ClientId {
+                    id: id.client_id.clone(),
+                }),
+                ..Default::default()
+            })
+            .collect(),
+    }
+}
+
+/// Deserialize a FugueText operation from protocol format
+#[cfg(feature = "text-crdt")]
+pub fn deserialize_fugue_op(op: &TextOperation) -> Result<FugueOp> {
+    let parse = |s: &str| {
+        s.parse::<NodeId>()
+            .map_err(|e| SyncError::Protocol(format!("Invalid text operation: {}", e)))
+    };
+    let origin = |s: &str| -> Result<Option<NodeId>> {
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            parse(s).map(Some)
+        }
+    };
+
+    let fugue_op = match text_operation::OpType::try_from(op.op_type) {
+        Ok(text_operation::OpType::Insert) => FugueOp::Insert(FugueBlock::new(
+            parse(&op.op_id)?,
+            op.content.clone(),
+            origin(&op.parent_id)?,
+            origin(&op.right_origin)?,
+        )),
+        Ok(text_operation::OpType::Delete) => {
+            let first = parse(&op.parent_id)?;
+            // Untrusted input: the range end must fit before validate() runs
+            let end = u64::try_from(op.length)
+                .ok()
+                .and_then(|length| length.checked_sub(1))
+                .and_then(|span| first.clock.checked_add(span))
+                .ok_or_else(|| {
+                    SyncError::Protocol(format!(
+                        "Invalid text operation: delete length {} from clock {}",
+                        op.length, first.clock
+                    ))
+                })?;
+            FugueOp::Delete {
+                id: parse(&op.op_id)?,
+                ranges: vec![NodeIdRange::new(first.client_id, first.clock, end)],
+            }
+        }
+        Err(_) => {
+            return Err(SyncError::Protocol(
+                "Invalid text operation type".to_string(),
+            ))
+        }
+    };
+
+    fugue_op
+        .validate()
+        .map_err(|e| SyncError::Protocol(format!("Invalid text operation: {}", e)))?;
+    Ok(fugue_op)
 }
 
 /// Convert serde_json::Value to protocol::Value
@@ -240,6 +462,62 @@ mod tests {
         assert_eq!(op.amount, 3);
     }
 
+    #[test]
+    #[cfg(feature = "counters")]
+    fn test_pn_counter_roundtrip_keeps_replicas() {
+        let mut alice = PNCounter::new("alice".to_string());
+        let mut bob = PNCounter::new("bob".to_string());
+        alice.increment(5);
+        bob.increment(3);
+        bob.decrement(1);
+        alice.merge(&bob);
+
+        let bytes = encode_message(&serialize_pn_counter(&alice, "alice")).unwrap();
+        let decoded: CounterOperation = decode_message(&bytes).unwrap();
+        let received = deserialize_pn_counter(&decoded, "carol").unwrap();
+
+        assert_eq!(received.value(), 7);
+        assert_eq!(received.replica_id(), "carol");
+        assert_eq!(received.positive().get("bob"), Some(&3));
+        assert_eq!(received.negative().get("bob"), Some(&1));
+
+        // Merging the received state counts nothing twice
+        let mut merged = bob.clone();
+        merged.merge(&received);
+        merged.merge(&alice);
+        assert_eq!(merged.value(), 7);
+
+        let mut carol = received.clone();
+        carol.increment(2);
+        alice.merge(&carol);
+        assert_eq!(alice.value(), 9);
+    }
+
+    #[test]
+    #[cfg(feature = "counters")]
+    fn test_pn_counter_rejects_negative_counts() {
+        let op = CounterOperation {
+            entries: vec![CounterEntry {
+                replica_id: "alice".to_string(),
+                positive: -1,
+                negative: 0,
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            deserialize_pn_counter(&op, "bob"),
+            Err(SyncError::Protocol(_))
+        ));
+
+        // Older writers only send the total
+        let legacy = CounterOperation {
+            op_type: counter_operation::OpType::Decrement as i32,
+            amount: 4,
+            ..Default::default()
+        };
+        assert_eq!(deserialize_pn_counter(&legacy, "bob").unwrap().value(), -4);
+    }
+
     #[test]
     #[cfg(feature = "sets")]
     fn test_or_set_serialization() {
@@ -250,4 +528,133 @@ mod tests {
         let ops = serialize_or_set(&set, "client1");
         assert_eq!(ops.len(), 2);
     }
+
+    #[test]
+    #[cfg(feature = "sets")]
+    fn test_or_set_roundtrip_keeps_tags_and_removes() {
+        fn sorted(set: &ORSet<String>) -> Vec<String> {
+            let mut items: Vec<String> = set.iter().cloned().collect();
+            items.sort();
+            items
+        }
+
+        let mut alice = ORSet::new("alice".to_string());
+        alice.add("apple".to_string());
+        alice.add("pear".to_string());
+        let mut bob = alice.clone();
+        bob.remove(&"apple".to_string());
+        alice.add("apple".to_string());
+        alice.remove(&"pear".to_string());
+
+        let wire: Vec<SetOperation> = serialize_or_set(&alice, "alice")
+            .iter()
+            .map(|op| decode_message(&encode_message(op).unwrap()).unwrap())
+            .collect();
+        let received: ORSet<String> = deserialize_or_set(&wire, "carol").unwrap();
+        assert_eq!(sorted(&received), sorted(&alice));
+        assert_eq!(received.version(), alice.version());
+
+        // Bob's remove did not see Alice's second add: apple stays
+        let mut expected = alice.clone();
+        expected.merge(&bob);
+        let mut merged = received.clone();
+        merged.merge(&bob);
+        assert_eq!(sorted(&merged), vec!["apple".to_string()]);
+        assert_eq!(sorted(&merged), sorted(&expected));
+    }
+
+    #[test]
+    #[cfg(feature = "sets")]
+    fn test_or_set_sends_only_unseen_operations() {
+        let mut alice = ORSet::new("alice".to_string());
+        alice.add("apple".to_string());
+        let mut bob: ORSet<String> =
+            deserialize_or_set(&serialize_or_set(&alice, "alice"), "bob").unwrap();
+
+        alice.remove(&"apple".to_string());
+        alice.add("kiwi".to_string());
+
+        let delta = serialize_or_set_since(&alice, &bob.version());
+        assert_eq!(delta.len(), 2);
+        apply_or_set_operations(&mut bob, &delta).unwrap();
+        assert!(!bob.contains(&"apple".to_string()));
+        assert!(bob.contains(&"kiwi".to_string()));
+
+        assert!(serialize_or_set_since(&alice, &bob.version()).is_empty());
+
+        let bad = SetOperation {
+            op_type: set_operation::OpType::Remove as i32,
+            tag: "no-sequence".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            apply_or_set_operations(&mut bob, &[bad]),
+            Err(SyncError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "text-crdt")]
+    fn test_fugue_op_roundtrip() {
+        use crate::crdt::FugueText;
+
+        let mut alice = FugueText::new("alice".to_string());
+        let mut bob = FugueText::new("bob".to_string());
+
+        let mut ops = vec![alice.insert(0, "Hello World").unwrap()];
+        ops.push(alice.insert(5, ",").unwrap());
+        ops.push(alice.delete(7, 5).unwrap());
+
+        for op in &ops {
+            let wire = serialize_fugue_op(op);
+            for text_op in &wire {
+                let bytes = encode_message(text_op).unwrap();
+                let decoded: TextOperation = decode_message(&bytes).unwrap();
+                bob.apply_op(&deserialize_fugue_op(&decoded).unwrap())
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(bob.to_string(), "Hello, ");
+        assert_eq!(bob.to_string(), alice.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "text-crdt")]
+    fn test_fugue_op_rejects_bad_ids() {
+        let op = TextOperation {
+            op_type: text_operation::OpType::Delete as i32,
+            op_id: "client1@3:0".to_string(),
+            parent_id: "not-a-node-id".to_string(),
+            length: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            deserialize_fugue_op(&op),
+            Err(SyncError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "text-crdt")]
+    fn test_fugue_op_rejects_out_of_range_deletes() {
+        let delete = |parent_id: String, length: i64| TextOperation {
+            op_type: text_operation::OpType::Delete as i32,
+            op_id: "client1@3:0".to_string(),
+            parent_id,
+            length,
+            ..Default::default()
+        };
+
+        for op in [
+            delete(format!("client1@{}:0", u64::MAX), 2),
+            delete("client1@1:0".to_string(), 0),
+            delete("client1@1:0".to_string(), -1),
+        ] {
+            assert!(matches!(
+                deserialize_fugue_op(&op),
+                Err(SyncError::Protocol(_))
+            ));
+        }
+    }
 }
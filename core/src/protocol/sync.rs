@@ -1,7 +1,1401 @@
-// Sync coordinator module
+// Sync coordinator - Server-side sync session logic
 //!
-//! This module provides sync coordination logic
+//! This module drives the sync protocol defined in `sync.proto` without
+//! owning any transport. Callers feed in the messages a client sent
+//! (`SyncRequest`, `SubscribeRequest`, `SyncAck`, ...) and deliver whatever
+//! comes back: direct replies are returned from each handler, while
+//! real-time notifications for other subscribers are queued and collected
+//! with [`SyncCoordinator::take_notifications`]. The whole flow can therefore
+//! run in-process, with no sockets involved.
+//!
+//! Documents and their delta logs live in a [`Storage`] backend:
+//! [`MemoryStorage`] by default, or any other passed to
+//! [`SyncCoordinator::with_storage`]. Every accepted delta is logged with the
+//! document version after it was applied, so versions along a log only grow
+//! and the deltas a client is missing are the ones its checkpoint does not
+//! dominate. If the backend has folded older deltas into a snapshot (as
+//! `FileStorage` does on compaction), a client whose checkpoint predates
+//! them is sent the whole document instead. Checkpoints, subscriptions and
+//! unacked notifications are kept in memory.
+//!
+//! With an [`AccessControl`] attached, uploads that write a field the
+//! client may not write are rejected with `PERMISSION_DENIED`, and every
+//! delta a client receives is redacted to what it may read.
+//!
+//! # Example
+//!
+//! ```rust
+//! use synckit_core::protocol::sync::SyncCoordinator;
+//! use synckit_core::protocol::{DocumentId, SubscribeRequest, SyncRequest};
+//! use synckit_core::sync::Delta;
+//! use synckit_core::Document;
+//!
+//! let mut coordinator = SyncCoordinator::new();
+//!
+//! // Bob subscribes to live updates
+//! coordinator
+//!     .handle_subscribe(
+//!         "bob",
+//!         &SubscribeRequest {
+//!             document_ids: vec![DocumentId { id: "doc-1".to_string() }],
+//!         },
+//!     )
+//!     .unwrap();
+//!
+//! // Alice uploads a change
+//! let mut doc = Document::new("doc-1".to_string());
+//! doc.set_field("title".to_string(), serde_json::json!("Hi"), 1, "alice".to_string());
+//! let delta = Delta::compute(&Document::new("doc-1".to_string()), &doc).unwrap();
+//!
+//! coordinator.handle_sync_request(
+//!     "alice",
+//!     &SyncRequest {
+//!         pending_deltas: vec![delta.to_protocol()],
+//!         ..Default::default()
+//!     },
+//! );
+//!
+//! // Bob gets notified
+//! let outbound = coordinator.take_notifications();
+//! assert_eq!(outbound.len(), 1);
+//! assert_eq!(outbound[0].client_id, "bob");
+//! ```
+
+use crate::document::Document;
+use crate::error::{Result, SyncError};
+use crate::protocol::auth::{AccessControl, ClientPolicy};
+use crate::protocol::delta::{vector_clock_from_protocol, vector_clock_to_protocol};
+use crate::protocol::{
+    ws_message, ClientId, Delta, DocumentId, Status, SubscribeRequest, SubscriptionConfirm,
+    SyncAck, SyncCheckpoint, SyncNotification, SyncRequest, SyncResponse, UnsubscribeRequest,
+    WsMessage,
+};
+use crate::storage::{MemoryStorage, Storage};
+use crate::sync::{Delta as SyncDelta, VectorClock};
+use crate::{ClientID, DocumentID};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// Page size used when a request doesn't set `max_deltas`
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Notifications a client may leave unacked before the oldest are dropped
+pub const DEFAULT_MAX_PENDING_ACKS: usize = 1_000;
+
+/// A notification queued for delivery to a subscriber
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outbound {
+    /// Recipient client
+    pub client_id: ClientID,
+
+    /// Notification to deliver
+    pub notification: SyncNotification,
+}
+
+/// An accepted delta in a document's log
+#[derive(Debug, Clone)]
+struct LogEntry {
+    /// Client that uploaded the delta, if this coordinator accepted it
+    origin: Option<ClientID>,
+
+    /// The delta, with versions rewritten onto the log's version chain
+    delta: SyncDelta,
+}
+
+impl LogEntry {
+    /// Convert for a recipient, None if redaction leaves nothing to send
+    fn to_protocol(&self, reader: Option<ClientPolicy>) -> Option<Delta> {
+        let mut proto = match reader {
+            Some(reader) => {
+                let redacted = self.delta.redact(&reader);
+                if redacted.is_empty() {
+                    return None;
+                }
+                redacted.to_protocol()
+            }
+            None => self.delta.to_protocol(),
+        };
+        proto.client_id = self.origin.clone().map(|id| ClientId { id });
+        Some(proto)
+    }
+}
+
+/// A notification waiting for the recipient's ack
+#[derive(Debug, Clone)]
+struct PendingAck {
+    notification_id: String,
+    document_id: DocumentID,
+    base_version: VectorClock,
+    new_version: VectorClock,
+}
+
+/// Where a paged sync continues, sent to the client as an opaque token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PageToken {
+    /// Whether the pages are a full sync
+    full_sync: bool,
+
+    /// Documents left to sync, starting with the one the page ended in
+    document_ids: Vec<DocumentID>,
+
+    /// How far the first of them got
+    checkpoint: VectorClock,
+}
+
+impl PageToken {
+    fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("page token serializes");
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn decode(token: &str) -> Result<Self> {
+        let invalid = || SyncError::Protocol("Invalid page token".to_string());
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| invalid())?;
+        serde_json::from_slice(&json).map_err(|_| invalid())
+    }
+}
+
+/// Deltas collected from one document for a sync response
+struct Collected {
+    deltas: Vec<Delta>,
+    checkpoint: VectorClock,
+    /// Some deltas did not fit the page
+    has_more: bool,
+}
+
+/// Transport-agnostic sync coordinator
+///
+/// Keeps the authoritative copy of each document and its delta log in a
+/// [`Storage`] backend, and per-client checkpoints and subscriptions in
+/// memory.
+#[derive(Debug, Clone)]
+pub struct SyncCoordinator<S: Storage = MemoryStorage> {
+    /// Documents and their delta logs
+    storage: S,
+
+    /// Clients subscribed to each document
+    subscribers: BTreeMap<DocumentID, BTreeSet<ClientID>>,
+
+    /// Uploader of each delta this coordinator logged, by the version it
+    /// produced, in log order
+    origins: HashMap<DocumentID, Vec<(VectorClock, ClientID)>>,
+
+    /// What each client has seen, per document
+    checkpoints: HashMap<ClientID, HashMap<DocumentID, VectorClock>>,
+
+    /// Notifications awaiting acks, per client, oldest first
+    pending_acks: HashMap<ClientID, VecDeque<PendingAck>>,
+
+    /// Unacked notifications kept per client
+    max_pending_acks: usize,
+
+    /// Notifications not yet collected by the transport
+    outbox: Vec<Outbound>,
+
+    /// Counter for notification IDs
+    next_notification_id: u64,
+
+    /// Page size when a request doesn't specify one
+    page_size: usize,
+
+    /// Client permissions, if access is restricted
+    access: Option<AccessControl>,
+}
+
+impl SyncCoordinator {
+    /// Create a new coordinator with no documents, kept in memory
+    pub fn new() -> Self {
+        Self::with_storage(MemoryStorage::new())
+    }
+}
+
+impl<S: Storage> SyncCoordinator<S> {
+    /// Create a coordinator serving the documents in `storage`
+    ///
+    /// Deltas already in the store are served without the ID of the client
+    /// that uploaded them, and are sent back to that client as well.
+    pub fn with_storage(storage: S) -> Self {
+        Self {
+            storage,
+            subscribers: BTreeMap::new(),
+            origins: HashMap::new(),
+            checkpoints: HashMap::new(),
+            pending_acks: HashMap::new(),
+            max_pending_acks: DEFAULT_MAX_PENDING_ACKS,
+            outbox: Vec::new(),
+            next_notification_id: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+            access: None,
+        }
+    }
+
+    /// Set the page size used when a request doesn't set `max_deltas`
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Set how many unacked notifications are kept per client
+    ///
+    /// Beyond that, the oldest are forgotten: acking them fails, and the
+    /// next sync request sends what they carried again.
+    pub fn with_max_pending_acks(mut self, max_pending_acks: usize) -> Self {
+        self.max_pending_acks = max_pending_acks.max(1);
+        self
+    }
+
+    /// Restrict what clients may read and write
+    ///
+    /// Clients without a grant can neither upload nor receive anything.
+    pub fn with_access_control(mut self, access: AccessControl) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Get the client permissions, to grant or revoke access
+    ///
+    /// Checkpoints move past what a client could not read, so a client
+    /// granted more access needs a full sync to receive it.
+    pub fn access_control_mut(&mut self) -> Option<&mut AccessControl> {
+        self.access.as_mut()
+    }
+
+    /// Get the storage backend
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Get the storage backend mutably, e.g. to compact it
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Load the coordinator's copy of a document
+    pub fn document(&self, document_id: &str) -> Result<Option<Document>> {
+        self.storage.load_document(document_id)
+    }
+
+    /// Get the checkpoint recorded for a client on a document
+    pub fn checkpoint(&self, client_id: &str, document_id: &str) -> Option<&VectorClock> {
+        self.checkpoints
+            .get(client_id)
+            .and_then(|docs| docs.get(document_id))
+    }
+
+    /// Get the clients subscribed to a document
+    pub fn subscribers(&self, document_id: &str) -> Vec<&ClientID> {
+        self.subscribers
+            .get(document_id)
+            .map(|subscribers| subscribers.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Handle a sync request from a client
+    ///
+    /// Pending deltas are applied first (all or nothing), then the response
+    /// carries the deltas the client hasn't seen, at most `max_deltas` of
+    /// them. When the page fills up, `has_more` is set and
+    /// `next_page_token` says where the next page starts: sending it back
+    /// as `page_token` continues the same sync, full or not, over the
+    /// remaining documents.
+    ///
+    /// The new checkpoint holds the version reached in each document. A
+    /// request checkpoint is honored for single-document requests (it is a
+    /// per-document vector clock). Multi-document requests use the
+    /// checkpoints recorded by the coordinator.
+    ///
+    /// Storage failures are reported as `INTERNAL_ERROR` and leave the
+    /// client's checkpoints untouched; uploads applied before the failure
+    /// are kept.
+    pub fn handle_sync_request(&mut self, client_id: &str, request: &SyncRequest) -> SyncResponse {
+        let mut response = SyncResponse {
+            request_id: request.request_id.clone(),
+            status: Status::Ok as i32,
+            ..Default::default()
+        };
+
+        if let Err(err) = self.sync(client_id, request, &mut response) {
+            response = SyncResponse {
+                request_id: request.request_id.clone(),
+                status: match err {
+                    SyncError::PermissionDenied(_) => Status::PermissionDenied,
+                    SyncError::StorageError(_) => Status::InternalError,
+                    _ => Status::InvalidRequest,
+                } as i32,
+                error_message: err.to_string(),
+                ..Default::default()
+            };
+        }
+
+        response
+    }
+
+    /// Subscribe a client to real-time notifications
+    ///
+    /// Unknown documents confirm an empty version, and later uploads reach
+    /// the subscriber.
+    pub fn handle_subscribe(
+        &mut self,
+        client_id: &str,
+        request: &SubscribeRequest,
+    ) -> Result<SubscriptionConfirm> {
+        let mut confirm = SubscriptionConfirm::default();
+
+        for document_id in &request.document_ids {
+            let version = self
+                .storage
+                .load_document(&document_id.id)?
+                .map(|document| document.version().clone())
+                .unwrap_or_default();
+            confirm
+                .versions
+                .insert(document_id.id.clone(), vector_clock_to_protocol(&version));
+            confirm.document_ids.push(document_id.clone());
+        }
+
+        for document_id in &request.document_ids {
+            self.subscribers
+                .entry(document_id.id.clone())
+                .or_default()
+                .insert(client_id.to_string());
+        }
+
+        Ok(confirm)
+    }
+
+    /// Unsubscribe a client from real-time notifications
+    pub fn handle_unsubscribe(&mut self, client_id: &str, request: &UnsubscribeRequest) {
+        for document_id in &request.document_ids {
+            if let Some(subscribers) = self.subscribers.get_mut(&document_id.id) {
+                subscribers.remove(client_id);
+            }
+        }
+    }
+
+    /// Handle a client's acknowledgement of a notification
+    ///
+    /// The checkpoint only advances when the client had already seen
+    /// everything before the notified delta; otherwise the gap is filled by
+    /// the next sync request.
+    pub fn handle_ack(&mut self, client_id: &str, ack: &SyncAck) -> Result<()> {
+        let pending = self
+            .pending_acks
+            .get_mut(client_id)
+            .and_then(|queue| {
+                let index = queue
+                    .iter()
+                    .position(|pending| pending.notification_id == ack.notification_id)?;
+                queue.remove(index)
+            })
+            .ok_or_else(|| {
+                SyncError::Protocol(format!("Unknown notification: {}", ack.notification_id))
+            })?;
+
+        let checkpoint = self
+            .checkpoints
+            .entry(client_id.to_string())
+            .or_default()
+            .entry(pending.document_id)
+            .or_default();
+
+        if checkpoint.dominates(&pending.base_version) {
+            checkpoint.merge(&pending.new_version);
+        }
+
+        Ok(())
+    }
+
+    /// Forget a client's subscriptions and unacked notifications
+    ///
+    /// Checkpoints are kept so a reconnecting client resumes where it left off.
+    pub fn disconnect(&mut self, client_id: &str) {
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.remove(client_id);
+        }
+        self.pending_acks.remove(client_id);
+        self.outbox
+            .retain(|outbound| outbound.client_id != client_id);
+    }
+
+    /// Take all notifications queued since the last call
+    pub fn take_notifications(&mut self) -> Vec<Outbound> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Handle a WebSocket envelope from a client
+    ///
+    /// Returns the direct reply, if the message has one. Notifications for
+    /// other clients are queued as usual.
+    pub fn handle_message(
+        &mut self,
+        client_id: &str,
+        message: &WsMessage,
+    ) -> Result<Option<WsMessage>> {
+        use ws_message::{Payload, Type};
+
+        let reply = |kind: Type, payload: Option<Payload>| WsMessage {
+            r#type: kind as i32,
+            timestamp: None,
+            payload,
+        };
+
+        match &message.payload {
+            Some(Payload::SyncRequest(request)) => {
+                let response = self.handle_sync_request(client_id, request);
+                Ok(Some(reply(
+                    Type::SyncResponse,
+                    Some(Payload::SyncResponse(response)),
+                )))
+            }
+            Some(Payload::Subscribe(request)) => {
+                let confirm = self.handle_subscribe(client_id, request)?;
+                Ok(Some(reply(
+                    Type::Subscribed,
+                    Some(Payload::Subscribed(confirm)),
+                )))
+            }
+            Some(Payload::Unsubscribe(request)) => {
+                self.handle_unsubscribe(client_id, request);
+                Ok(None)
+            }
+            Some(Payload::Ack(ack)) => {
+                self.handle_ack(client_id, ack)?;
+                Ok(None)
+            }
+            None if message.r#type == Type::Ping as i32 => Ok(Some(reply(Type::Pong, None))),
+            _ => Err(SyncError::Protocol(format!(
+                "Unexpected message type: {}",
+                message.r#type
+            ))),
+        }
+    }
+
+    /// Apply a sync request's uploads, then fill the response
+    fn sync(
+        &mut self,
+        client_id: &str,
+        request: &SyncRequest,
+        response: &mut SyncResponse,
+    ) -> Result<()> {
+        // A bad page token rejects the upload too
+        let page = if request.page_token.is_empty() {
+            None
+        } else {
+            Some(PageToken::decode(&request.page_token)?)
+        };
+        self.accept_deltas(client_id, &request.pending_deltas)?;
+
+        let (document_ids, full_sync) = match &page {
+            Some(page) => (page.document_ids.clone(), page.full_sync),
+            None if request.document_ids.is_empty() => {
+                (self.storage.list_documents()?, request.full_sync)
+            }
+            None => (
+                request
+                    .document_ids
+                    .iter()
+                    .map(|id| id.id.clone())
+                    .collect(),
+                request.full_sync,
+            ),
+        };
+
+        let limit = if request.max_deltas > 0 {
+            request.max_deltas as usize
+        } else {
+            self.page_size
+        };
+
+        let request_version = request
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.version.as_ref())
+            .map(vector_clock_from_protocol);
+
+        let reader = self.access.as_ref().map(|acl| acl.for_client(client_id));
+        let recorded = self.checkpoints.get(client_id);
+        let mut new_checkpoint = SyncCheckpoint::default();
+        let mut reached = Vec::new();
+
+        for (index, document_id) in document_ids.iter().enumerate() {
+            let Some(document) = self.storage.load_document(document_id)? else {
+                continue;
+            };
+
+            // A fresh full sync starts over, whatever was recorded
+            let checkpoint = match (&page, &request_version) {
+                (Some(page), _) if index == 0 => page.checkpoint.clone(),
+                (None, _) if full_sync => VectorClock::new(),
+                (None, Some(version)) if document_ids.len() == 1 => version.clone(),
+                _ => recorded
+                    .and_then(|docs| docs.get(document_id))
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+
+            let budget = limit.saturating_sub(response.deltas.len());
+            let collected = if full_sync {
+                collect_snapshot(&document, checkpoint, budget, reader)
+            } else {
+                self.collect_log(&document, client_id, checkpoint, budget, reader)?
+            };
+
+            response.deltas.extend(collected.deltas);
+            new_checkpoint.versions.insert(
+                document_id.clone(),
+                vector_clock_to_protocol(&collected.checkpoint),
+            );
+            new_checkpoint.documents.push(DocumentId {
+                id: document_id.clone(),
+            });
+            reached.push((document_id.clone(), collected.checkpoint.clone()));
+
+            if collected.has_more {
+                response.has_more = true;
+                response.next_page_token = PageToken {
+                    full_sync,
+                    document_ids: document_ids[index..].to_vec(),
+                    checkpoint: collected.checkpoint,
+                }
+                .encode();
+                break;
+            }
+        }
+
+        // A single version only makes sense for a single document
+        if let [document] = new_checkpoint.documents.as_slice() {
+            new_checkpoint.version = new_checkpoint.versions.get(&document.id).cloned();
+        }
+        response.new_checkpoint = Some(new_checkpoint);
+
+        let client_checkpoints = self.checkpoints.entry(client_id.to_string()).or_default();
+        if full_sync && page.is_none() {
+            for document_id in &document_ids {
+                client_checkpoints.remove(document_id);
+            }
+        }
+        client_checkpoints.extend(reached);
+
+        Ok(())
+    }
+
+    /// Validate and apply uploaded deltas, logging and broadcasting each one
+    fn accept_deltas(&mut self, client_id: &str, deltas: &[Delta]) -> Result<()> {
+        // Decode everything up front so a bad delta rejects the whole upload
+        let decoded = deltas
+            .iter()
+            .map(|proto| SyncDelta::from_protocol(proto, client_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(acl) = &self.access {
+            let writer = acl.for_client(client_id);
+            for delta in &decoded {
+                delta.check_writes(&writer)?;
+            }
+        }
+
+        for delta in decoded {
+            if !delta.is_empty() {
+                self.accept_delta(client_id, delta)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn accept_delta(&mut self, client_id: &str, delta: SyncDelta) -> Result<()> {
+        let mut document = self
+            .storage
+            .load_document(&delta.document_id)?
+            .unwrap_or_else(|| Document::new(delta.document_id.clone()));
+
+        // Applying merges the uploaded version into the document's
+        let base_version = document.version().clone();
+        delta.apply_to(&mut document)?;
+
+        // Every accepted delta must advance the version, or clients that have
+        // seen the previous entry would never be sent this one
+        if base_version.dominates(document.version()) {
+            document.version.tick(&client_id.to_string());
+        }
+
+        let entry = LogEntry {
+            origin: Some(client_id.to_string()),
+            delta: SyncDelta {
+                base_version: base_version.clone(),
+                new_version: document.version().clone(),
+                ..delta
+            },
+        };
+
+        // Snapshot first: if the append fails, the version gap sends
+        // clients a snapshot rather than losing the change
+        self.storage.save_document(&document)?;
+        self.storage.append_delta(&entry.delta)?;
+        self.origins
+            .entry(entry.delta.document_id.clone())
+            .or_default()
+            .push((entry.delta.new_version.clone(), client_id.to_string()));
+
+        // The uploader has this change; advance it if it was caught up
+        let uploader = self
+            .checkpoints
+            .entry(client_id.to_string())
+            .or_default()
+            .entry(entry.delta.document_id.clone())
+            .or_default();
+        if uploader.dominates(&base_version) {
+            uploader.merge(&entry.delta.new_version);
+        }
+
+        let Some(subscribers) = self.subscribers.get(&entry.delta.document_id) else {
+            return Ok(());
+        };
+
+        let unredacted = entry.to_protocol(None);
+        for subscriber in subscribers {
+            if subscriber == client_id {
+                continue;
+            }
+
+            let reader = self.access.as_ref().map(|acl| acl.for_client(subscriber));
+            // Nothing readable: the next sync request moves the checkpoint on
+            let Some(proto) = (match reader {
+                Some(reader) => entry.to_protocol(Some(reader)),
+                None => unredacted.clone(),
+            }) else {
+                continue;
+            };
+
+            self.next_notification_id += 1;
+            let notification_id = format!("n-{}", self.next_notification_id);
+
+            let queue = self.pending_acks.entry(subscriber.clone()).or_default();
+            if queue.len() >= self.max_pending_acks {
+                queue.pop_front();
+            }
+            queue.push_back(PendingAck {
+                notification_id: notification_id.clone(),
+                document_id: entry.delta.document_id.clone(),
+                base_version: base_version.clone(),
+                new_version: entry.delta.new_version.clone(),
+            });
+            self.outbox.push(Outbound {
+                client_id: subscriber.clone(),
+                notification: SyncNotification {
+                    notification_id,
+                    delta: Some(proto),
+                    document_ids: vec![DocumentId {
+                        id: entry.delta.document_id.clone(),
+                    }],
+                },
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Collect logged deltas the client hasn't seen, up to `budget`
+    ///
+    /// The client's own uploads are skipped but still advance its
+    /// checkpoint. If the log no longer reaches back to the checkpoint, the
+    /// client gets a snapshot instead.
+    fn collect_log(
+        &self,
+        document: &Document,
+        client_id: &str,
+        mut checkpoint: VectorClock,
+        budget: usize,
+        reader: Option<ClientPolicy>,
+    ) -> Result<Collected> {
+        let logged = self.storage.deltas_since(document.id(), &checkpoint)?;
+
+        // Each delta's base version is the previous one's new version, so a
+        // checkpoint that misses the first base missed a compacted delta
+        let reaches_back = match logged.first() {
+            Some(first) => checkpoint.dominates(&first.base_version),
+            None => checkpoint.dominates(document.version()),
+        };
+        if !reaches_back {
+            return Ok(collect_snapshot(document, checkpoint, budget, reader));
+        }
+
+        let origins = self
+            .origins
+            .get(document.id())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut deltas = Vec::new();
+        for delta in logged {
+            let new_version = delta.new_version.clone();
+            let entry = LogEntry {
+                origin: origin(origins, &new_version).cloned(),
+                delta,
+            };
+
+            if entry.origin.as_deref() != Some(client_id) {
+                if deltas.len() >= budget {
+                    return Ok(Collected {
+                        deltas,
+                        checkpoint,
+                        has_more: true,
+                    });
+                }
+                deltas.extend(entry.to_protocol(reader));
+            }
+            checkpoint.merge(&new_version);
+        }
+
+        Ok(Collected {
+            deltas,
+            checkpoint,
+            has_more: false,
+        })
+    }
+}
+
+impl Default for SyncCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find who uploaded the logged delta that produced `version`
+fn origin<'a>(
+    origins: &'a [(VectorClock, ClientID)],
+    version: &VectorClock,
+) -> Option<&'a ClientID> {
+    // Logged versions only grow, so the earlier ones form a prefix
+    let index =
+        origins.partition_point(|(logged, _)| logged != version && version.dominates(logged));
+    origins
+        .get(index)
+        .filter(|(logged, _)| logged == version)
+        .map(|(_, client_id)| client_id)
+}
+
+/// Collect the whole document as a single snapshot delta
+fn collect_snapshot(
+    document: &Document,
+    checkpoint: VectorClock,
+    budget: usize,
+    reader: Option<ClientPolicy>,
+) -> Collected {
+    if document.fields().is_empty() && document.registers().is_empty() {
+        return Collected {
+            deltas: Vec::new(),
+            checkpoint: document.version().clone(),
+            has_more: false,
+        };
+    }
+
+    if budget == 0 {
+        return Collected {
+            deltas: Vec::new(),
+            checkpoint,
+            has_more: true,
+        };
+    }
+
+    let empty = Document::new(document.id().clone());
+    // Same document ID on both sides, so compute can't fail
+    let mut snapshot =
+        SyncDelta::compute(&empty, document).expect("snapshot delta of the same document");
+    if let Some(reader) = reader {
+        snapshot = snapshot.redact(&reader);
+    }
+
+    Collected {
+        deltas: if snapshot.is_empty() {
+            Vec::new()
+        } else {
+            vec![snapshot.to_protocol()]
+        },
+        checkpoint: document.version().clone(),
+        has_more: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn upload(
+        doc_id: &str,
+        client: &str,
+        clock: u64,
+        field: &str,
+        value: serde_json::Value,
+    ) -> Delta {
+        let mut doc = Document::new(doc_id.to_string());
+        doc.set_field(field.to_string(), value, clock, client.to_string());
+        doc.version.update(&client.to_string(), clock);
+        SyncDelta::compute(&Document::new(doc_id.to_string()), &doc)
+            .unwrap()
+            .to_protocol()
+    }
+
+    fn push<S: Storage>(
+        coordinator: &mut SyncCoordinator<S>,
+        client: &str,
+        deltas: Vec<Delta>,
+    ) -> SyncResponse {
+        coordinator.handle_sync_request(
+            client,
+            &SyncRequest {
+                pending_deltas: deltas,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn pull<S: Storage>(
+        coordinator: &mut SyncCoordinator<S>,
+        client: &str,
+        max_deltas: i32,
+    ) -> SyncResponse {
+        coordinator.handle_sync_request(
+            client,
+            &SyncRequest {
+                max_deltas,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn next_page(
+        coordinator: &mut SyncCoordinator,
+        client: &str,
+        previous: &SyncResponse,
+    ) -> SyncResponse {
+        coordinator.handle_sync_request(
+            client,
+            &SyncRequest {
+                page_token: previous.next_page_token.clone(),
+                max_deltas: 2,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn ids(ids: &[&str]) -> Vec<DocumentId> {
+        ids.iter()
+            .map(|id| DocumentId { id: id.to_string() })
+            .collect()
+    }
+
+    #[test]
+    fn test_upload_then_pull() {
+        let mut coordinator = SyncCoordinator::new();
+
+        let response = push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "title", json!("Hello"))],
+        );
+        assert_eq!(response.status, Status::Ok as i32);
+        // Alice's own change isn't echoed back
+        assert!(response.deltas.is_empty());
+
+        let response = pull(&mut coordinator, "bob", 0);
+        assert_eq!(response.deltas.len(), 1);
+        assert!(!response.has_more);
+
+        let mut replica = Document::new("doc-1".to_string());
+        for delta in &response.deltas {
+            SyncDelta::from_protocol(delta, "server")
+                .unwrap()
+                .apply_to(&mut replica)
+                .unwrap();
+        }
+        assert_eq!(
+            replica.to_json(),
+            coordinator.document("doc-1").unwrap().unwrap().to_json()
+        );
+
+        // Nothing new on the next pull
+        assert!(pull(&mut coordinator, "bob", 0).deltas.is_empty());
+    }
+
+    #[test]
+    fn test_pagination_resumes_from_checkpoint() {
+        let mut coordinator = SyncCoordinator::new();
+        for clock in 1..=5 {
+            push(
+                &mut coordinator,
+                "alice",
+                vec![upload("doc-1", "alice", clock, "count", json!(clock))],
+            );
+        }
+
+        let first = pull(&mut coordinator, "bob", 2);
+        assert_eq!(first.deltas.len(), 2);
+        assert!(first.has_more);
+        assert!(!first.next_page_token.is_empty());
+
+        // Without the token, the recorded checkpoint picks up as well
+        let second = pull(&mut coordinator, "bob", 2);
+        assert_eq!(second.deltas.len(), 2);
+        assert!(second.has_more);
+
+        let third = next_page(&mut coordinator, "bob", &second);
+        assert_eq!(third.deltas.len(), 1);
+        assert!(!third.has_more);
+        assert!(third.next_page_token.is_empty());
+
+        let version = coordinator
+            .document("doc-1")
+            .unwrap()
+            .unwrap()
+            .version()
+            .clone();
+        assert_eq!(coordinator.checkpoint("bob", "doc-1"), Some(&version));
+    }
+
+    #[test]
+    fn test_page_token_continues_full_sync() {
+        let mut coordinator = SyncCoordinator::new();
+        for doc in ["doc-1", "doc-2", "doc-3"] {
+            push(
+                &mut coordinator,
+                "alice",
+                vec![upload(doc, "alice", 1, "title", json!(doc))],
+            );
+        }
+
+        let request = SyncRequest {
+            full_sync: true,
+            max_deltas: 2,
+            ..Default::default()
+        };
+        let first = coordinator.handle_sync_request("bob", &request);
+        assert_eq!(first.deltas.len(), 2);
+        assert!(first.has_more);
+
+        // The follow-up sends the remaining snapshot, not the first two again
+        let second = next_page(&mut coordinator, "bob", &first);
+        assert_eq!(second.status, Status::Ok as i32);
+        assert_eq!(second.deltas.len(), 1);
+        assert_eq!(second.deltas[0].document_id.as_ref().unwrap().id, "doc-3");
+        assert!(!second.has_more);
+
+        // A token that does not decode is refused along with the upload
+        let response = coordinator.handle_sync_request(
+            "bob",
+            &SyncRequest {
+                page_token: "not a token".to_string(),
+                pending_deltas: vec![upload("doc-4", "bob", 1, "title", json!("T"))],
+                ..Default::default()
+            },
+        );
+        assert_eq!(response.status, Status::InvalidRequest as i32);
+        assert!(coordinator.document("doc-4").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_checkpoint_versions_are_per_document() {
+        let mut coordinator = SyncCoordinator::new();
+        push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "title", json!("A"))],
+        );
+        push(
+            &mut coordinator,
+            "carol",
+            vec![upload("doc-2", "carol", 1, "title", json!("C"))],
+        );
+
+        let checkpoint = pull(&mut coordinator, "bob", 0).new_checkpoint.unwrap();
+        assert!(checkpoint.version.is_none());
+        assert_eq!(checkpoint.versions.len(), 2);
+        let doc1 = vector_clock_from_protocol(&checkpoint.versions["doc-1"]);
+        assert_eq!(
+            &doc1,
+            coordinator.document("doc-1").unwrap().unwrap().version()
+        );
+        assert_eq!(doc1.get(&"carol".to_string()), 0);
+    }
+
+    #[test]
+    fn test_unacked_notifications_are_capped() {
+        let mut coordinator = SyncCoordinator::new().with_max_pending_acks(2);
+        coordinator
+            .handle_subscribe(
+                "bob",
+                &SubscribeRequest {
+                    document_ids: ids(&["doc-1"]),
+                },
+            )
+            .unwrap();
+        for clock in 1..=3 {
+            push(
+                &mut coordinator,
+                "alice",
+                vec![upload("doc-1", "alice", clock, "count", json!(clock))],
+            );
+        }
+
+        let outbound = coordinator.take_notifications();
+        assert_eq!(outbound.len(), 3);
+        let ack = |outbound: &Outbound| SyncAck {
+            notification_id: outbound.notification.notification_id.clone(),
+            version: None,
+        };
+
+        // The oldest was dropped; the next sync request covers it
+        assert!(coordinator.handle_ack("bob", &ack(&outbound[0])).is_err());
+        assert!(coordinator.handle_ack("bob", &ack(&outbound[2])).is_ok());
+        assert_eq!(pull(&mut coordinator, "bob", 0).deltas.len(), 3);
+    }
+
+    #[test]
+    fn test_request_checkpoint_is_honored() {
+        let mut coordinator = SyncCoordinator::new();
+        for clock in 1..=3 {
+            push(
+                &mut coordinator,
+                "alice",
+                vec![upload("doc-1", "alice", clock, "count", json!(clock))],
+            );
+        }
+
+        // Bob claims to have seen alice's first two changes
+        let mut version = VectorClock::new();
+        version.update(&"alice".to_string(), 2);
+        let response = coordinator.handle_sync_request(
+            "bob",
+            &SyncRequest {
+                checkpoint: Some(SyncCheckpoint {
+                    version: Some(vector_clock_to_protocol(&version)),
+                    ..Default::default()
+                }),
+                document_ids: ids(&["doc-1"]),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(response.deltas.len(), 1);
+        assert_eq!(
+            response.new_checkpoint.unwrap().version.unwrap().clocks["alice"],
+            3
+        );
+    }
+
+    #[test]
+    fn test_full_sync_sends_snapshot() {
+        let mut coordinator = SyncCoordinator::new();
+        push(
+            &mut coordinator,
+            "alice",
+            vec![
+                upload("doc-1", "alice", 1, "a", json!(1)),
+                upload("doc-1", "alice", 2, "b", json!(2)),
+            ],
+        );
+
+        let response = coordinator.handle_sync_request(
+            "alice",
+            &SyncRequest {
+                full_sync: true,
+                ..Default::default()
+            },
+        );
+
+        // One snapshot, even for the uploader
+        assert_eq!(response.deltas.len(), 1);
+        assert_eq!(response.deltas[0].changes.len(), 2);
+    }
+
+    #[test]
+    fn test_full_sync_sends_multi_value_fields() {
+        use crate::document::FieldMode;
+
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field_mode("owner".to_string(), FieldMode::MultiValue)
+            .unwrap();
+        doc.set_field("owner".to_string(), json!("alice"), 1, "alice".to_string());
+        doc.version.update(&"alice".to_string(), 1);
+        let delta = SyncDelta::compute(&Document::new("doc-1".to_string()), &doc).unwrap();
+
+        let mut coordinator = SyncCoordinator::new();
+        push(&mut coordinator, "alice", vec![delta.to_protocol()]);
+
+        let response = coordinator.handle_sync_request(
+            "bob",
+            &SyncRequest {
+                full_sync: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(response.deltas.len(), 1);
+        assert!(response.deltas[0].changes.is_empty());
+        assert_eq!(response.deltas[0].registers.len(), 1);
+    }
+
+    #[test]
+    fn test_subscribers_notified_and_ack_advances_checkpoint() {
+        let mut coordinator = SyncCoordinator::new();
+        coordinator
+            .handle_subscribe(
+                "alice",
+                &SubscribeRequest {
+                    document_ids: ids(&["doc-1"]),
+                },
+            )
+            .unwrap();
+        let confirm = coordinator
+            .handle_subscribe(
+                "bob",
+                &SubscribeRequest {
+                    document_ids: ids(&["doc-1"]),
+                },
+            )
+            .unwrap();
+        assert!(confirm.versions.contains_key("doc-1"));
+
+        push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "title", json!("Hi"))],
+        );
+
+        let outbound = coordinator.take_notifications();
+        assert_eq!(outbound.len(), 1);
+        assert_eq!(outbound[0].client_id, "bob");
+        assert!(coordinator.take_notifications().is_empty());
+
+        let notification = &outbound[0].notification;
+        coordinator
+            .handle_ack(
+                "bob",
+                &SyncAck {
+                    notification_id: notification.notification_id.clone(),
+                    version: None,
+                },
+            )
+            .unwrap();
+
+        // Acked notification won't be resent
+        assert!(pull(&mut coordinator, "bob", 0).deltas.is_empty());
+
+        // Acks are single-use and per-client
+        let ack = SyncAck {
+            notification_id: notification.notification_id.clone(),
+            version: None,
+        };
+        assert!(coordinator.handle_ack("bob", &ack).is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_and_disconnect_stop_notifications() {
+        let mut coordinator = SyncCoordinator::new();
+        coordinator
+            .handle_subscribe(
+                "bob",
+                &SubscribeRequest {
+                    document_ids: ids(&["doc-1"]),
+                },
+            )
+            .unwrap();
+        coordinator
+            .handle_subscribe(
+                "carol",
+                &SubscribeRequest {
+                    document_ids: ids(&["doc-1"]),
+                },
+            )
+            .unwrap();
+
+        coordinator.handle_unsubscribe(
+            "bob",
+            &UnsubscribeRequest {
+                document_ids: ids(&["doc-1"]),
+            },
+        );
+        coordinator.disconnect("carol");
+
+        push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "title", json!("Hi"))],
+        );
+        assert!(coordinator.take_notifications().is_empty());
+        assert!(coordinator.subscribers("doc-1").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_upload_is_rejected_atomically() {
+        let mut coordinator = SyncCoordinator::new();
+        let mut bad = upload("doc-1", "alice", 2, "b", json!(2));
+        bad.document_id = None;
+
+        let response = push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "a", json!(1)), bad],
+        );
+
+        assert_eq!(response.status, Status::InvalidRequest as i32);
+        assert!(!response.error_message.is_empty());
+        assert!(coordinator.document("doc-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_access_control_guards_uploads_and_redacts_deltas() {
+        use crate::protocol::{DocumentPermission, FieldPermission, Permissions};
+
+        let grant = |can_write: bool, hidden: &[&str]| {
+            let mut doc = DocumentPermission {
+                can_read: true,
+                can_write,
+                ..Default::default()
+            };
+            for path in hidden {
+                doc.fields
+                    .insert(path.to_string(), FieldPermission::default());
+            }
+            let mut permissions = Permissions::default();
+            permissions.documents.insert("doc-1".to_string(), doc);
+            permissions
+        };
+
+        let mut acl = AccessControl::new();
+        acl.grant("alice".to_string(), grant(true, &[]));
+        acl.grant("bob".to_string(), grant(false, &["user.salary"]));
+        let mut coordinator = SyncCoordinator::new().with_access_control(acl);
+        coordinator
+            .handle_subscribe(
+                "bob",
+                &SubscribeRequest {
+                    document_ids: vec![DocumentId {
+                        id: "doc-1".to_string(),
+                    }],
+                },
+            )
+            .unwrap();
+
+        let user = json!({ "name": "Ann", "salary": 100 });
+        let response = push(
+            &mut coordinator,
+            "alice",
+            vec![upload("doc-1", "alice", 1, "user", user)],
+        );
+        assert_eq!(response.status, Status::Ok as i32);
+
+        // Bob only sees what he may read, live and when pulling
+        let redacted = |delta: &Delta| {
+            let delta = SyncDelta::from_protocol(delta, "bob").unwrap();
+            assert!(delta.fields["user"].is_redacted());
+            delta.fields["user"].value.clone()
+        };
+        let outbound = coordinator.take_notifications();
+        assert_eq!(
+            redacted(outbound[0].notification.delta.as_ref().unwrap()),
+            json!({ "name": "Ann" })
+        );
+        let response = pull(&mut coordinator, "bob", 0);
+        assert_eq!(redacted(&response.deltas[0]), json!({ "name": "Ann" }));
+
+        // Bob may not write, and clients without a grant get nothing
+        let response = push(
+            &mut coordinator,
+            "bob",
+            vec![upload("doc-1", "bob", 1, "title", json!("T"))],
+        );
+        assert_eq!(response.status, Status::PermissionDenied as i32);
+        assert!(coordinator
+            .document("doc-1")
+            .unwrap()
+            .unwrap()
+            .get_field(&"title".to_string())
+            .is_none());
+        assert!(pull(&mut coordinator, "mallory", 0).deltas.is_empty());
+
+        coordinator
+            .access_control_mut()
+            .unwrap()
+            .grant("mallory".to_string(), grant(false, &[]));
+        let response = coordinator.handle_sync_request(
+            "mallory",
+            &SyncRequest {
+                full_sync: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(response.deltas.len(), 1);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_compacted_log_falls_back_to_snapshot() {
+        use crate::storage::FileStorage;
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::open(dir.path())
+            .unwrap()
+            .with_compaction_threshold(2);
+        let mut coordinator = SyncCoordinator::with_storage(storage);
+        for (clock, field) in ["a", "b", "c"].into_iter().enumerate() {
+            push(
+                &mut coordinator,
+                "alice",
+                vec![upload("doc-1", "alice", clock as u64 + 1, field, json!(1))],
+            );
+        }
+
+        // The first two deltas were folded into the snapshot
+        let response = pull(&mut coordinator, "bob", 0);
+        assert_eq!(response.deltas.len(), 1);
+        assert_eq!(response.deltas[0].changes.len(), 3);
+        assert!(pull(&mut coordinator, "alice", 0).deltas.is_empty());
+
+        // A new coordinator serves what the old one stored
+        drop(coordinator);
+        let mut coordinator = SyncCoordinator::with_storage(FileStorage::open(dir.path()).unwrap());
+        let response = pull(&mut coordinator, "carol", 0);
+        assert_eq!(response.status, Status::Ok as i32);
+        assert_eq!(response.deltas[0].changes.len(), 3);
+    }
+
+    #[test]
+    fn test_handle_message_envelopes() {
+        use ws_message::{Payload, Type};
+
+        let mut coordinator = SyncCoordinator::new();
+
+        let ping = WsMessage {
+            r#type: Type::Ping as i32,
+            ..Default::default()
+        };
+        let pong = coordinator.handle_message("bob", &ping).unwrap().unwrap();
+        assert_eq!(pong.r#type, Type::Pong as i32);
+
+        let subscribe = WsMessage {
+            r#type: Type::Subscribe as i32,
+            timestamp: None,
+            payload: Some(Payload::Subscribe(SubscribeRequest {
+                document_ids: ids(&["doc-1"]),
+            })),
+        };
+        let reply = coordinator
+            .handle_message("bob", &subscribe)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(reply.payload, Some(Payload::Subscribed(_))));
 
-pub struct SyncCoordinator {
-    // TODO: Implement sync coordinator
+        // Server-to-client messages are rejected
+        let bogus = WsMessage {
+            r#type: Type::Subscribed as i32,
+            timestamp: None,
+            payload: Some(Payload::Subscribed(SubscriptionConfirm::default())),
+        };
+        assert!(coordinator.handle_message("bob", &bogus).is_err());
+    }
 }
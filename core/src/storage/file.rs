@@ -0,0 +1,751 @@
+//! File-backed storage with a write-ahead log
+//!
+//! Each document gets its own directory under the storage root:
+//!
+//! ```text
+//! <root>/<hex(document_id)>/snapshot.json   latest compacted Document
+//! <root>/<hex(document_id)>/wal.log         deltas appended since then
+//! ```
+//!
+//! IDs longer than 100 bytes would overflow file name limits once
+//! hex-encoded, so their directory is named `h<hash>` instead, with the
+//! original ID stored next to the snapshot in an `id` file. An ID whose hash
+//! collides with a stored one is refused with a storage error.
+//!
+//! Every `append_delta` writes one framed record (`len: u32`, `crc32: u32`,
+//! JSON payload, little-endian) and fsyncs before returning, so a delta is
+//! durable once the call succeeds. Loading a document replays the log over
+//! the snapshot. Replay is safe even if the snapshot already contains some of
+//! the logged changes, because applying a delta is an idempotent LWW merge.
+//!
+//! A crash can leave a torn record at the end of the log: one that is
+//! incomplete, or whose checksum fails with nothing after it. `open`
+//! truncates the log back to the last good record. A bad record followed by
+//! more data is corruption rather than a torn write, and reading the log
+//! fails instead of dropping the records behind it. An append that fails
+//! without a crash truncates its own partial record right away, so later
+//! appends are not stranded behind it.
+//!
+//! Once a log reaches the compaction threshold it is folded into a new
+//! snapshot: the snapshot is written to a temporary file, fsynced and renamed
+//! into place before the log is truncated. Deltas folded into a snapshot are
+//! no longer returned by `deltas_since`, so replicas whose checkpoint
+//! predates a compaction need a full sync. Compaction triggered by an append
+//! never fails the append, which is already durable: the error is kept for
+//! `take_compaction_errors` and the next append tries again.
+
+use super::Storage;
+use crate::document::Document;
+use crate::error::{Result, SyncError};
+use crate::sync::{apply_delta, Delta, VectorClock};
+use crate::DocumentID;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Log length (in records) that triggers compaction by default
+pub const DEFAULT_COMPACTION_THRESHOLD: usize = 1000;
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.json.tmp";
+const ID_FILE: &str = "id";
+const ID_TMP_FILE: &str = "id.tmp";
+const LOG_FILE: &str = "wal.log";
+const RECORD_HEADER_LEN: usize = 8;
+
+/// Longest document ID (in bytes) whose directory is its hex encoding
+const MAX_HEX_ID_LEN: usize = 100;
+
+/// Storage backend persisting snapshots and a delta log to a directory
+#[derive(Debug)]
+pub struct FileStorage {
+    /// Storage root directory
+    root: PathBuf,
+
+    /// Records currently in each document's log
+    log_lengths: HashMap<DocumentID, usize>,
+
+    /// Log length that triggers compaction
+    compaction_threshold: usize,
+
+    /// Failed compactions triggered by appends, not yet taken
+    compaction_errors: Vec<(DocumentID, SyncError)>,
+}
+
+impl FileStorage {
+    /// Open (or create) a store rooted at `root`
+    ///
+    /// Recovers every document log, truncating torn records left behind by
+    /// a crash. Fails if a log is corrupt before its last record.
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root).map_err(io_error("create storage directory"))?;
+
+        let mut storage = Self {
+            root,
+            log_lengths: HashMap::new(),
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+            compaction_errors: Vec::new(),
+        };
+
+        for document_id in storage.list_documents()? {
+            let log_path = storage.log_path(&document_id)?;
+            let recovered = recover_log(&log_path)?;
+            storage.log_lengths.insert(document_id, recovered);
+        }
+
+        Ok(storage)
+    }
+
+    /// Set the log length that triggers compaction
+    pub fn with_compaction_threshold(mut self, threshold: usize) -> Self {
+        self.compaction_threshold = threshold.max(1);
+        self
+    }
+
+    /// Fold a document's log into a new snapshot
+    ///
+    /// Returns the number of log records folded.
+    pub fn compact(&mut self, document_id: &str) -> Result<usize> {
+        let Some(document) = self.load_document(document_id)? else {
+            return Ok(0);
+        };
+
+        // Snapshot first: if we crash before the truncate, replaying the
+        // old log over the new snapshot is harmless
+        self.save_document(&document)?;
+
+        let log_path = self.log_path(document_id)?;
+        let folded = match OpenOptions::new().write(true).open(&log_path) {
+            Ok(file) => {
+                let folded = read_log(&log_path)?.0.len();
+                file.set_len(0).map_err(io_error("truncate log"))?;
+                file.sync_all().map_err(io_error("sync log"))?;
+                folded
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(io_error("open log")(err)),
+        };
+
+        self.log_lengths.insert(document_id.to_string(), 0);
+        Ok(folded)
+    }
+
+    /// Take the errors of compactions triggered by `append_delta`
+    ///
+    /// The appends themselves succeeded; their documents are compacted
+    /// again on the next append.
+    pub fn take_compaction_errors(&mut self) -> Vec<(DocumentID, SyncError)> {
+        std::mem::take(&mut self.compaction_errors)
+    }
+
+    /// Get a document's directory
+    ///
+    /// Fails if the directory's hashed name belongs to another ID.
+    fn document_dir(&self, document_id: &str) -> Result<PathBuf> {
+        let dir = self.root.join(dir_name(document_id));
+        if document_id.len() > MAX_HEX_ID_LEN {
+            match fs::read_to_string(dir.join(ID_FILE)) {
+                Ok(stored) if stored != document_id => {
+                    return Err(SyncError::StorageError(format!(
+                        "Document ID collides with stored document {}",
+                        stored
+                    )));
+                }
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(io_error("read document ID")(err)),
+            }
+        }
+        Ok(dir)
+    }
+
+    fn snapshot_path(&self, document_id: &str) -> Result<PathBuf> {
+        Ok(self.document_dir(document_id)?.join(SNAPSHOT_FILE))
+    }
+
+    fn log_path(&self, document_id: &str) -> Result<PathBuf> {
+        Ok(self.document_dir(document_id)?.join(LOG_FILE))
+    }
+
+    /// Create a document's directory, making the new entry durable
+    fn ensure_document_dir(&self, document_id: &str) -> Result<PathBuf> {
+        let dir = self.document_dir(document_id)?;
+        if !dir.exists() {
+            fs::create_dir_all(&dir).map_err(io_error("create document directory"))?;
+            sync_dir(&self.root)?;
+        }
+
+        // Also done for an existing directory, in case a crash cut creation short
+        if document_id.len() > MAX_HEX_ID_LEN && !dir.join(ID_FILE).exists() {
+            write_atomically(&dir, ID_TMP_FILE, ID_FILE, document_id.as_bytes())?;
+        }
+        Ok(dir)
+    }
+}
+
+impl Storage for FileStorage {
+    fn load_document(&self, document_id: &str) -> Result<Option<Document>> {
+        let snapshot = match fs::read(self.snapshot_path(document_id)?) {
+            Ok(bytes) => Some(serde_json::from_slice::<Document>(&bytes).map_err(|err| {
+                SyncError::StorageError(format!("Corrupt snapshot for {}: {}", document_id, err))
+            })?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(io_error("read snapshot")(err)),
+        };
+
+        let (deltas, _) = read_log(&self.log_path(document_id)?)?;
+        if snapshot.is_none() && deltas.is_empty() {
+            return Ok(None);
+        }
+
+        let mut document = snapshot.unwrap_or_else(|| Document::new(document_id.to_string()));
+        for delta in &deltas {
+            apply_delta(&mut document, delta);
+        }
+
+        Ok(Some(document))
+    }
+
+    fn save_document(&mut self, document: &Document) -> Result<()> {
+        let dir = self.ensure_document_dir(document.id())?;
+        let bytes = serde_json::to_vec(document)
+            .map_err(|err| SyncError::SerializationError(err.to_string()))?;
+
+        write_atomically(&dir, SNAPSHOT_TMP_FILE, SNAPSHOT_FILE, &bytes)
+    }
+
+    fn append_delta(&mut self, delta: &Delta) -> Result<()> {
+        if delta.document_id.is_empty() {
+            return Err(SyncError::StorageError(
+                "Cannot append delta without a document ID".to_string(),
+            ));
+        }
+
+        let dir = self.ensure_document_dir(&delta.document_id)?;
+        let payload = serde_json::to_vec(delta)
+            .map_err(|err| SyncError::SerializationError(err.to_string()))?;
+
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32(&payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))
+            .map_err(io_error("open log"))?;
+        append_record(&mut file, &record)?;
+
+        let length = self
+            .log_lengths
+            .entry(delta.document_id.clone())
+            .or_insert(0);
+        *length += 1;
+
+        if *length >= self.compaction_threshold {
+            if let Err(err) = self.compact(&delta.document_id) {
+                self.compaction_errors
+                    .push((delta.document_id.clone(), err));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn deltas_since(&self, document_id: &str, since: &VectorClock) -> Result<Vec<Delta>> {
+        let (deltas, _) = read_log(&self.log_path(document_id)?)?;
+        Ok(deltas
+            .into_iter()
+            .filter(|delta| !since.dominates(&delta.new_version))
+            .collect())
+    }
+
+    fn list_documents(&self) -> Result<Vec<DocumentID>> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.root).map_err(io_error("list documents"))? {
+            let entry = entry.map_err(io_error("list documents"))?;
+            if !entry.path().is_dir() {
+                continue;
+            }
+
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if name.starts_with('h') {
+                // Hashed names keep their ID in a file, written after the
+                // directory: without it, nothing was stored yet
+                match fs::read_to_string(entry.path().join(ID_FILE)) {
+                    Ok(id) => ids.push(id),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(io_error("read document ID")(err)),
+                }
+            } else if let Some(id) = decode_id(&name) {
+                // Skip anything that isn't one of our encoded directories
+                ids.push(id);
+            }
+        }
+
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn delete_document(&mut self, document_id: &str) -> Result<bool> {
+        self.log_lengths.remove(document_id);
+
+        match fs::remove_dir_all(self.document_dir(document_id)?) {
+            Ok(()) => {
+                sync_dir(&self.root)?;
+                Ok(true)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(io_error("delete document")(err)),
+        }
+    }
+}
+
+/// Read every intact record from a log
+///
+/// Returns the decoded deltas and the byte length of the intact prefix,
+/// which leaves out a torn last record. A missing log reads as empty; a bad
+/// record before the last one is an error.
+fn read_log(path: &Path) -> Result<(Vec<Delta>, usize)> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(err) => return Err(io_error("read log")(err)),
+    };
+
+    let mut deltas = Vec::new();
+    let mut offset = 0;
+
+    while bytes.len() - offset >= RECORD_HEADER_LEN {
+        let header = &bytes[offset..offset + RECORD_HEADER_LEN];
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        let start = offset + RECORD_HEADER_LEN;
+        let end = start + len;
+        let Some(payload) = bytes.get(start..end) else {
+            break; // torn: payload cut short
+        };
+
+        let corrupt = || {
+            SyncError::StorageError(format!(
+                "Corrupt log record at byte {} of {}",
+                offset,
+                path.display()
+            ))
+        };
+        if crc32(payload) != checksum {
+            if end == bytes.len() {
+                break; // torn: partially written payload
+            }
+            return Err(corrupt());
+        }
+
+        deltas.push(serde_json::from_slice::<Delta>(payload).map_err(|_| corrupt())?);
+        offset = end;
+    }
+
+    Ok((deltas, offset))
+}
+
+/// Append a record and sync it, or cut off whatever part of it was written
+fn append_record(file: &mut File, record: &[u8]) -> Result<()> {
+    let good_len = file.metadata().map_err(io_error("stat log"))?.len();
+
+    if let Err(err) = file.write_all(record).and_then(|()| file.sync_data()) {
+        // Best effort: if this fails too, recovery on open still drops the
+        // torn record
+        let _ = file.set_len(good_len).and_then(|()| file.sync_data());
+        return Err(io_error("append to log")(err));
+    }
+
+    Ok(())
+}
+
+/// Truncate a log to its intact prefix, returning the number of records kept
+fn recover_log(path: &Path) -> Result<usize> {
+    let (deltas, valid_len) = read_log(path)?;
+
+    match OpenOptions::new().write(true).open(path) {
+        Ok(file) => {
+            let actual_len = file.metadata().map_err(io_error("stat log"))?.len();
+            if actual_len > valid_len as u64 {
+                file.set_len(valid_len as u64)
+                    .map_err(io_error("truncate torn log"))?;
+                file.sync_all().map_err(io_error("sync log"))?;
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(io_error("open log")(err)),
+    }
+
+    Ok(deltas.len())
+}
+
+/// Write a file with write-then-rename, so a crash never leaves it
+/// half-written
+fn write_atomically(dir: &Path, tmp_name: &str, name: &str, bytes: &[u8]) -> Result<()> {
+    let tmp_path = dir.join(tmp_name);
+    let mut file = File::create(&tmp_path).map_err(io_error("create file"))?;
+    file.write_all(bytes).map_err(io_error("write file"))?;
+    file.sync_all().map_err(io_error("sync file"))?;
+    fs::rename(&tmp_path, dir.join(name)).map_err(io_error("replace file"))?;
+    sync_dir(dir)
+}
+
+/// Make directory entry changes (creates, renames) durable
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(io_error("sync directory"))
+}
+
+/// Directories can't be opened for syncing on this platform
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn io_error(context: &'static str) -> impl Fn(io::Error) -> SyncError {
+    move |err| SyncError::StorageError(format!("Failed to {}: {}", context, err))
+}
+
+/// Name a document's directory: its hex-encoded ID, or a hash of long IDs
+fn dir_name(document_id: &str) -> String {
+    if document_id.len() > MAX_HEX_ID_LEN {
+        format!("h{:032x}", fnv1a_128(document_id.as_bytes()))
+    } else {
+        encode_id(document_id)
+    }
+}
+
+/// Hex-encode a document ID into a safe directory name
+fn encode_id(document_id: &str) -> String {
+    document_id
+        .bytes()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn decode_id(name: &str) -> Option<DocumentID> {
+    let pairs = name.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+
+    let bytes = pairs
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+/// FNV-1a (128-bit) hash of a long document ID
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u128).wrapping_mul(PRIME)
+    })
+}
+
+/// CRC-32 (IEEE) lookup table
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE) checksum used to detect torn log records
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::compute_delta;
+    use serde_json::json;
+
+    fn edit(doc: &mut Document, field: &str, value: serde_json::Value, clock: u64) -> Delta {
+        let before = doc.clone();
+        doc.set_field(field.to_string(), value, clock, "client1".to_string());
+        doc.version.tick(&"client1".to_string());
+        compute_delta(&before, doc)
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_id_encoding_roundtrip() {
+        let id = "users/42:profile";
+        assert_eq!(decode_id(&encode_id(id)).as_deref(), Some(id));
+        assert_eq!(decode_id("zz"), None);
+    }
+
+    #[test]
+    fn test_reopen_replays_log_over_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+
+        {
+            let mut storage = FileStorage::open(dir.path()).unwrap();
+            edit(&mut doc, "title", json!("Draft"), 1);
+            storage.save_document(&doc).unwrap();
+
+            let delta = edit(&mut doc, "title", json!("Final"), 2);
+            storage.append_delta(&delta).unwrap();
+            let delta = edit(&mut doc, "author", json!("Ada"), 3);
+            storage.append_delta(&delta).unwrap();
+        }
+
+        let storage = FileStorage::open(dir.path()).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+        assert_eq!(loaded.version(), doc.version());
+        assert_eq!(storage.list_documents().unwrap(), vec!["doc-1"]);
+    }
+
+    #[test]
+    fn test_recovers_from_torn_last_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let log_path;
+
+        {
+            let mut storage = FileStorage::open(dir.path()).unwrap();
+            for (clock, field) in [(1, "a"), (2, "b")] {
+                let delta = edit(&mut doc, field, json!(clock), clock);
+                storage.append_delta(&delta).unwrap();
+            }
+            log_path = storage.log_path("doc-1").unwrap();
+        }
+        let intact_len = fs::metadata(&log_path).unwrap().len();
+
+        // Simulate a crash halfway through writing a third record
+        let mut crashed = doc.clone();
+        let torn = edit(&mut crashed, "c", json!(3), 3);
+        let payload = serde_json::to_vec(&torn).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        file.write_all(&(payload.len() as u32).to_le_bytes())
+            .unwrap();
+        file.write_all(&crc32(&payload).to_le_bytes()).unwrap();
+        file.write_all(&payload[..payload.len() / 2]).unwrap();
+        drop(file);
+
+        let mut storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(fs::metadata(&log_path).unwrap().len(), intact_len);
+
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+
+        // The log stays usable after recovery
+        let delta = edit(&mut doc, "c", json!(3), 3);
+        storage.append_delta(&delta).unwrap();
+        let reopened = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(
+            reopened.load_document("doc-1").unwrap().unwrap().to_json(),
+            doc.to_json()
+        );
+        assert_eq!(
+            reopened
+                .deltas_since("doc-1", &VectorClock::new())
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_corrupt_last_record_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let log_path;
+
+        {
+            let mut storage = FileStorage::open(dir.path()).unwrap();
+            let delta = edit(&mut doc, "a", json!(1), 1);
+            storage.append_delta(&delta).unwrap();
+            let mut lost = doc.clone();
+            let delta = edit(&mut lost, "b", json!(2), 2);
+            storage.append_delta(&delta).unwrap();
+            log_path = storage.log_path("doc-1").unwrap();
+        }
+
+        // Flip the last payload byte so the checksum no longer matches
+        let mut bytes = fs::read(&log_path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+        fs::write(&log_path, bytes).unwrap();
+
+        let storage = FileStorage::open(dir.path()).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+    }
+
+    #[test]
+    fn test_corrupt_record_mid_log_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let log_path;
+
+        {
+            let mut storage = FileStorage::open(dir.path()).unwrap();
+            for (clock, field) in [(1, "a"), (2, "b")] {
+                let delta = edit(&mut doc, field, json!(clock), clock);
+                storage.append_delta(&delta).unwrap();
+            }
+            log_path = storage.log_path("doc-1").unwrap();
+        }
+
+        // Flip a byte of the first payload; the second record is still intact
+        let mut bytes = fs::read(&log_path).unwrap();
+        bytes[RECORD_HEADER_LEN] ^= 0xFF;
+        fs::write(&log_path, &bytes).unwrap();
+
+        let err = FileStorage::open(dir.path()).unwrap_err();
+        assert!(matches!(err, SyncError::StorageError(_)));
+        assert_eq!(fs::read(&log_path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_long_ids_are_hashed() {
+        let dir = tempfile::tempdir().unwrap();
+        let long_id = "x".repeat(300);
+        let mut doc = Document::new(long_id.clone());
+
+        {
+            let mut storage = FileStorage::open(dir.path()).unwrap();
+            edit(&mut doc, "title", json!("Draft"), 1);
+            storage.save_document(&doc).unwrap();
+            let delta = edit(&mut doc, "title", json!("Final"), 2);
+            storage.append_delta(&delta).unwrap();
+        }
+
+        let mut storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(storage.list_documents().unwrap(), vec![long_id.clone()]);
+        let loaded = storage.load_document(&long_id).unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+
+        // A colliding ID is refused rather than mixed into the directory
+        let hashed = storage.document_dir(&long_id).unwrap();
+        fs::write(hashed.join(ID_FILE), "y".repeat(300)).unwrap();
+        let err = storage.save_document(&doc).unwrap_err();
+        assert!(matches!(err, SyncError::StorageError(_)));
+    }
+
+    #[test]
+    fn test_compaction_folds_log_into_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::open(dir.path())
+            .unwrap()
+            .with_compaction_threshold(3);
+        let mut doc = Document::new("doc-1".to_string());
+
+        for clock in 1..=4 {
+            let delta = edit(&mut doc, "count", json!(clock), clock);
+            storage.append_delta(&delta).unwrap();
+        }
+
+        // Three records were folded, one logged since
+        let remaining = storage.deltas_since("doc-1", &VectorClock::new()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(storage.snapshot_path("doc-1").unwrap().exists());
+
+        let reopened = FileStorage::open(dir.path()).unwrap();
+        let loaded = reopened.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+        assert_eq!(loaded.version(), doc.version());
+    }
+
+    #[test]
+    fn test_failed_compaction_keeps_the_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::open(dir.path())
+            .unwrap()
+            .with_compaction_threshold(2);
+        let mut doc = Document::new("doc-1".to_string());
+
+        // A directory in the way of the temporary snapshot fails compaction
+        storage.ensure_document_dir("doc-1").unwrap();
+        fs::create_dir(
+            storage
+                .document_dir("doc-1")
+                .unwrap()
+                .join(SNAPSHOT_TMP_FILE),
+        )
+        .unwrap();
+
+        for clock in 1..=2 {
+            let delta = edit(&mut doc, "count", json!(clock), clock);
+            storage.append_delta(&delta).unwrap();
+        }
+
+        let errors = storage.take_compaction_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "doc-1");
+        assert!(storage.take_compaction_errors().is_empty());
+
+        let logged = storage.deltas_since("doc-1", &VectorClock::new()).unwrap();
+        assert_eq!(logged.len(), 2);
+
+        // Once the way is clear, the next append compacts
+        fs::remove_dir(
+            storage
+                .document_dir("doc-1")
+                .unwrap()
+                .join(SNAPSHOT_TMP_FILE),
+        )
+        .unwrap();
+        let delta = edit(&mut doc, "count", json!(3), 3);
+        storage.append_delta(&delta).unwrap();
+        assert!(storage.take_compaction_errors().is_empty());
+        assert!(storage
+            .deltas_since("doc-1", &VectorClock::new())
+            .unwrap()
+            .is_empty());
+
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+    }
+
+    #[test]
+    fn test_delete_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::open(dir.path()).unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let delta = edit(&mut doc, "a", json!(1), 1);
+        storage.append_delta(&delta).unwrap();
+
+        assert!(storage.delete_document("doc-1").unwrap());
+        assert!(!storage.delete_document("doc-1").unwrap());
+        assert!(storage.load_document("doc-1").unwrap().is_none());
+        assert!(storage.list_documents().unwrap().is_empty());
+    }
+}
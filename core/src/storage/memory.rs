@@ -0,0 +1,166 @@
+//! In-memory storage backend
+//!
+//! Keeps everything in hash maps. Nothing survives the process, which makes
+//! it a good fit for tests, servers that rebuild state from peers, and
+//! replicas that don't need offline persistence.
+
+use super::Storage;
+use crate::document::Document;
+use crate::error::{Result, SyncError};
+use crate::sync::{Delta, VectorClock};
+use crate::DocumentID;
+use std::collections::{BTreeSet, HashMap};
+
+/// Storage backend that lives entirely in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    /// Latest snapshot per document
+    documents: HashMap<DocumentID, Document>,
+
+    /// Append-only delta log per document
+    deltas: HashMap<DocumentID, Vec<Delta>>,
+}
+
+impl MemoryStorage {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_document(&self, document_id: &str) -> Result<Option<Document>> {
+        Ok(self.documents.get(document_id).cloned())
+    }
+
+    fn save_document(&mut self, document: &Document) -> Result<()> {
+        self.documents
+            .insert(document.id().clone(), document.clone());
+        Ok(())
+    }
+
+    fn append_delta(&mut self, delta: &Delta) -> Result<()> {
+        if delta.document_id.is_empty() {
+            return Err(SyncError::StorageError(
+                "Cannot append delta without a document ID".to_string(),
+            ));
+        }
+
+        self.deltas
+            .entry(delta.document_id.clone())
+            .or_default()
+            .push(delta.clone());
+        Ok(())
+    }
+
+    fn deltas_since(&self, document_id: &str, since: &VectorClock) -> Result<Vec<Delta>> {
+        Ok(self
+            .deltas
+            .get(document_id)
+            .map(|log| {
+                log.iter()
+                    .filter(|delta| !since.dominates(&delta.new_version))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn list_documents(&self) -> Result<Vec<DocumentID>> {
+        let ids: BTreeSet<&DocumentID> = self.documents.keys().chain(self.deltas.keys()).collect();
+        Ok(ids.into_iter().cloned().collect())
+    }
+
+    fn delete_document(&mut self, document_id: &str) -> Result<bool> {
+        let had_snapshot = self.documents.remove(document_id).is_some();
+        let had_log = self.deltas.remove(document_id).is_some();
+        Ok(had_snapshot || had_log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::compute_delta;
+    use serde_json::json;
+
+    fn edit(
+        doc: &Document,
+        field: &str,
+        value: serde_json::Value,
+        clock: u64,
+    ) -> (Document, Delta) {
+        let mut next = doc.clone();
+        next.set_field(field.to_string(), value, clock, "client1".to_string());
+        next.version.tick(&"client1".to_string());
+        let delta = compute_delta(doc, &next);
+        (next, delta)
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut storage = MemoryStorage::new();
+        assert!(storage.load_document("doc-1").unwrap().is_none());
+
+        let (doc, _) = edit(&Document::new("doc-1".to_string()), "title", json!("Hi"), 1);
+        storage.save_document(&doc).unwrap();
+
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+        assert_eq!(loaded.version(), doc.version());
+    }
+
+    #[test]
+    fn test_deltas_since_checkpoint() {
+        let mut storage = MemoryStorage::new();
+        let doc = Document::new("doc-1".to_string());
+        let (doc, first) = edit(&doc, "a", json!(1), 1);
+        let (doc, second) = edit(&doc, "b", json!(2), 2);
+        let (_, third) = edit(&doc, "c", json!(3), 3);
+
+        for delta in [&first, &second, &third] {
+            storage.append_delta(delta).unwrap();
+        }
+
+        let all = storage.deltas_since("doc-1", &VectorClock::new()).unwrap();
+        assert_eq!(all, vec![first.clone(), second.clone(), third.clone()]);
+
+        let rest = storage.deltas_since("doc-1", &first.new_version).unwrap();
+        assert_eq!(rest, vec![second, third.clone()]);
+
+        assert!(storage
+            .deltas_since("doc-1", &third.new_version)
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .deltas_since("missing", &VectorClock::new())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let mut storage = MemoryStorage::new();
+        let (doc_b, _) = edit(&Document::new("doc-b".to_string()), "x", json!(1), 1);
+        let (_, delta_a) = edit(&Document::new("doc-a".to_string()), "y", json!(2), 1);
+
+        storage.save_document(&doc_b).unwrap();
+        storage.append_delta(&delta_a).unwrap();
+        assert_eq!(storage.list_documents().unwrap(), vec!["doc-a", "doc-b"]);
+
+        assert!(storage.delete_document("doc-a").unwrap());
+        assert!(!storage.delete_document("doc-a").unwrap());
+        assert_eq!(storage.list_documents().unwrap(), vec!["doc-b"]);
+    }
+
+    #[test]
+    fn test_errors_are_retryable_storage_errors() {
+        let mut storage = MemoryStorage::new();
+        let err = storage
+            .append_delta(&Delta::empty(String::new(), VectorClock::new()))
+            .unwrap_err();
+
+        assert!(matches!(err, SyncError::StorageError(_)));
+        assert!(err.is_retryable());
+    }
+}
@@ -1,12 +1,67 @@
 //! Storage abstraction layer
 //!
-//! Phase 2 implementation:
-//! - Storage trait
-//! - In-memory storage (for testing)
+//! Persistence for document snapshots and their delta history:
+//! - `Storage` trait implemented by every backend
+//! - In-memory storage (for testing and ephemeral replicas)
+//! - File storage with a write-ahead log (native targets)
+//! - SQLite storage (`sqlite` feature)
 //!
 //! Future:
 //! - IndexedDB adapter
 //! - OPFS adapter
-//! - SQLite adapter
+//!
+//! Backends report failures as `SyncError::StorageError`, which is
+//! retryable.
+
+pub mod memory;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use memory::MemoryStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileStorage;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+use crate::document::Document;
+use crate::error::Result;
+use crate::sync::{Delta, VectorClock};
+use crate::DocumentID;
+
+/// Persistent store for documents and their deltas
+///
+/// A backend keeps the latest snapshot of each document plus an
+/// append-only log of deltas, so replicas can catch up from a checkpoint
+/// without shipping the whole document.
+pub trait Storage {
+    /// Load the latest snapshot of a document
+    ///
+    /// Returns `Ok(None)` if the document has never been saved.
+    fn load_document(&self, document_id: &str) -> Result<Option<Document>>;
+
+    /// Save a document snapshot, replacing any previous one
+    fn save_document(&mut self, document: &Document) -> Result<()>;
+
+    /// Append a delta to its document's log
+    fn append_delta(&mut self, delta: &Delta) -> Result<()>;
+
+    /// Read logged deltas not yet covered by `since`, in append order
+    ///
+    /// A delta is covered when `since` dominates its version, so passing an
+    /// empty clock returns the whole log.
+    fn deltas_since(&self, document_id: &str, since: &VectorClock) -> Result<Vec<Delta>>;
+
+    /// List the IDs of all stored documents (snapshots or logs), sorted
+    fn list_documents(&self) -> Result<Vec<DocumentID>>;
 
-// Placeholder for Phase 2
+    /// Delete a document's snapshot and delta log
+    ///
+    /// Returns whether anything was stored for the document.
+    fn delete_document(&mut self, document_id: &str) -> Result<bool>;
+}
@@ -0,0 +1,825 @@
+//! SQLite storage backend
+//!
+//! Stores documents field by field, so a single field (with its LWW
+//! `Timestamp`) can be read or inspected with plain SQL, multi-value fields
+//! as one JSON-encoded register per path, alongside opaque
+//! blobs for text/counter/set CRDTs and a delta log indexed by the version
+//! vector entries each delta advances. Works against a database file or
+//! `:memory:`.
+//!
+//! Clocks are stored as 8-byte big-endian blobs rather than integers: a
+//! `u64` clock past `i64::MAX` (as HLC-packed timestamps can be) doesn't fit
+//! an SQLite integer, and blobs compare bytewise, which is clock order.
+//!
+//! The schema is versioned with `PRAGMA user_version`. Opening a database
+//! applies any migrations it hasn't seen yet, in one transaction; databases
+//! written by a newer version of this crate are refused.
+
+use super::Storage;
+use crate::document::{Document, Field};
+use crate::error::{Result, SyncError};
+use crate::sync::{Delta, Timestamp, VectorClock};
+use crate::{ClientID, DocumentID};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Schema migrations, applied in order; entry `n` upgrades version `n` to `n + 1`
+const MIGRATIONS: &[&str] = &[
+    // 1: documents, fields, CRDT blobs and the delta log
+    "CREATE TABLE documents (
+        id TEXT PRIMARY KEY,
+        version TEXT NOT NULL
+    );
+    CREATE TABLE fields (
+        document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        value TEXT NOT NULL,
+        clock INTEGER NOT NULL,
+        client_id TEXT NOT NULL,
+        deleted INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (document_id, path)
+    );
+    CREATE TABLE crdts (
+        document_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        kind TEXT NOT NULL CHECK (kind IN ('text', 'counter', 'set')),
+        data BLOB NOT NULL,
+        PRIMARY KEY (document_id, name)
+    );
+    CREATE TABLE deltas (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        document_id TEXT NOT NULL,
+        client_id TEXT NOT NULL,
+        clock INTEGER NOT NULL,
+        version TEXT NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX deltas_by_document ON deltas (document_id, seq);
+    CREATE INDEX deltas_by_client ON deltas (document_id, client_id, clock);",
+    // 2: multi-value fields
+    "CREATE TABLE registers (
+        document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        register TEXT NOT NULL,
+        PRIMARY KEY (document_id, path)
+    );",
+    // 3: redacted values
+    "ALTER TABLE fields ADD COLUMN redacted INTEGER NOT NULL DEFAULT 0;",
+    // 4: blob clocks, and the delta log indexed by version vector entries.
+    // printf('%016x') reads integers as two's complement, so clocks that
+    // wrapped negative come back as the u64 they were
+    "CREATE TABLE fields_v4 (
+        document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        value TEXT NOT NULL,
+        clock BLOB NOT NULL,
+        client_id TEXT NOT NULL,
+        deleted INTEGER NOT NULL DEFAULT 0,
+        redacted INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (document_id, path)
+    );
+    INSERT INTO fields_v4
+        SELECT document_id, path, value, unhex(printf('%016x', clock)), client_id, deleted,
+            redacted
+        FROM fields;
+    DROP TABLE fields;
+    ALTER TABLE fields_v4 RENAME TO fields;
+    CREATE TABLE delta_clocks (
+        seq INTEGER NOT NULL REFERENCES deltas(seq) ON DELETE CASCADE,
+        document_id TEXT NOT NULL,
+        client_id TEXT NOT NULL,
+        clock BLOB NOT NULL,
+        PRIMARY KEY (seq, client_id)
+    );
+    INSERT INTO delta_clocks
+        SELECT deltas.seq, deltas.document_id, entry.key, unhex(printf('%016x', entry.value))
+        FROM deltas, json_each(deltas.payload, '$.new_version.clocks') AS entry
+        WHERE entry.value > coalesce(
+            json_extract(deltas.payload, '$.base_version.clocks.\"' || entry.key || '\"'),
+            0
+        );
+    CREATE INDEX delta_clocks_by_client ON delta_clocks (document_id, client_id, clock);
+    DROP INDEX deltas_by_client;
+    ALTER TABLE deltas DROP COLUMN client_id;
+    ALTER TABLE deltas DROP COLUMN clock;",
+];
+
+/// Current schema version
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Kind of CRDT stored in a blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrdtKind {
+    /// Text CRDT (e.g. FugueText)
+    Text,
+
+    /// Counter CRDT (e.g. PNCounter)
+    Counter,
+
+    /// Set CRDT (e.g. ORSet)
+    Set,
+}
+
+impl CrdtKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CrdtKind::Text => "text",
+            CrdtKind::Counter => "counter",
+            CrdtKind::Set => "set",
+        }
+    }
+
+    fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "text" => Ok(CrdtKind::Text),
+            "counter" => Ok(CrdtKind::Counter),
+            "set" => Ok(CrdtKind::Set),
+            other => Err(SyncError::StorageError(format!(
+                "Unknown CRDT kind: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Storage backend backed by a SQLite database
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// Open (or create) a database file, migrating it to the current schema
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path).map_err(sql_error)?;
+        // journal_mode returns the resulting mode as a row
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .map_err(sql_error)?;
+        Self::with_connection(conn)
+    }
+
+    /// Open a private in-memory database
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)
+            .map_err(sql_error)?;
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    /// Schema version of the open database
+    pub fn schema_version(&self) -> Result<usize> {
+        user_version(&self.conn)
+    }
+
+    /// Save a CRDT blob under a name within a document
+    ///
+    /// Blobs are opaque to the store; callers pick the encoding.
+    pub fn save_crdt(
+        &mut self,
+        document_id: &str,
+        name: &str,
+        kind: CrdtKind,
+        data: &[u8],
+    ) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT INTO crdts (document_id, name, kind, data) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (document_id, name) DO UPDATE SET kind = ?3, data = ?4",
+                params![document_id, name, kind.as_str(), data],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    /// Load a CRDT blob saved with `save_crdt`
+    pub fn load_crdt(&self, document_id: &str, name: &str) -> Result<Option<(CrdtKind, Vec<u8>)>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT kind, data FROM crdts WHERE document_id = ?1 AND name = ?2",
+                params![document_id, name],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()
+            .map_err(sql_error)?;
+
+        match row {
+            Some((kind, data)) => Ok(Some((CrdtKind::parse(&kind)?, data))),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the deltas that advance one client's clock past `after_clock`
+    ///
+    /// A delta counts for every client whose version vector entry it
+    /// advances, at that entry's new value, so each client's deltas come
+    /// back in clock order. All entries count when the base version is
+    /// unknown.
+    pub fn deltas_by_client(
+        &self,
+        document_id: &str,
+        client_id: &str,
+        after_clock: u64,
+    ) -> Result<Vec<Delta>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT deltas.payload FROM delta_clocks
+                 JOIN deltas ON deltas.seq = delta_clocks.seq
+                 WHERE delta_clocks.document_id = ?1 AND delta_clocks.client_id = ?2
+                    AND delta_clocks.clock > ?3
+                 ORDER BY delta_clocks.clock, deltas.seq",
+            )
+            .map_err(sql_error)?;
+
+        let payloads = stmt
+            .query_map(
+                params![document_id, client_id, clock_to_sql(after_clock)],
+                |row| row.get::<_, String>(0),
+            )
+            .map_err(sql_error)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+
+        payloads.iter().map(|payload| decode(payload)).collect()
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_document(&self, document_id: &str) -> Result<Option<Document>> {
+        let version = self
+            .conn
+            .query_row(
+                "SELECT version FROM documents WHERE id = ?1",
+                params![document_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+
+        let Some(version) = version else {
+            return Ok(None);
+        };
+
+        let mut document = Document::new(document_id.to_string());
+        document.version = decode::<VectorClock>(&version)?;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT path, value, clock, client_id, deleted, redacted FROM fields
+                 WHERE document_id = ?1",
+            )
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map(params![document_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                    row.get::<_, ClientID>(3)?,
+                    row.get::<_, bool>(4)?,
+                    row.get::<_, bool>(5)?,
+                ))
+            })
+            .map_err(sql_error)?;
+
+        for row in rows {
+            let (path, value, clock, client_id, deleted, redacted) = row.map_err(sql_error)?;
+            let timestamp = Timestamp::new(clock_from_sql(&clock)?, client_id);
+            let field = if deleted {
+                Field::tombstone(timestamp)
+            } else {
+                Field {
+                    redacted,
+                    ..Field::new(decode(&value)?, timestamp)
+                }
+            };
+            document.fields.insert(path, field);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, register FROM registers WHERE document_id = ?1")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map(params![document_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sql_error)?;
+
+        for row in rows {
+            let (path, register) = row.map_err(sql_error)?;
+            document.registers.insert(path, decode(&register)?);
+        }
+
+        Ok(Some(document))
+    }
+
+    fn save_document(&mut self, document: &Document) -> Result<()> {
+        let tx = self.conn.transaction().map_err(sql_error)?;
+
+        tx.execute(
+            "INSERT INTO documents (id, version) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET version = ?2",
+            params![document.id(), encode(document.version())?],
+        )
+        .map_err(sql_error)?;
+        for sql in [
+            "DELETE FROM fields WHERE document_id = ?1",
+            "DELETE FROM registers WHERE document_id = ?1",
+        ] {
+            tx.execute(sql, params![document.id()]).map_err(sql_error)?;
+        }
+
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO fields
+                        (document_id, path, value, clock, client_id, deleted, redacted)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(sql_error)?;
+            for (path, field) in document.fields() {
+                insert
+                    .execute(params![
+                        document.id(),
+                        path,
+                        encode(&field.value)?,
+                        clock_to_sql(field.timestamp.clock),
+                        field.timestamp.client_id,
+                        field.is_deleted(),
+                        field.is_redacted(),
+                    ])
+                    .map_err(sql_error)?;
+            }
+
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO registers (document_id, path, register)
+                     VALUES (?1, ?2, ?3)",
+                )
+                .map_err(sql_error)?;
+            for (path, register) in document.registers() {
+                insert
+                    .execute(params![document.id(), path, encode(register)?])
+                    .map_err(sql_error)?;
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    }
+
+    fn append_delta(&mut self, delta: &Delta) -> Result<()> {
+        if delta.document_id.is_empty() {
+            return Err(SyncError::StorageError(
+                "Cannot append delta without a document ID".to_string(),
+            ));
+        }
+
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        tx.execute(
+            "INSERT INTO deltas (document_id, version, payload) VALUES (?1, ?2, ?3)",
+            params![
+                delta.document_id,
+                encode(&delta.new_version)?,
+                encode(delta)?
+            ],
+        )
+        .map_err(sql_error)?;
+        let seq = tx.last_insert_rowid();
+
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO delta_clocks (seq, document_id, client_id, clock)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(sql_error)?;
+            for (client_id, &clock) in delta.new_version.clocks() {
+                if clock > delta.base_version.get(client_id) {
+                    insert
+                        .execute(params![
+                            seq,
+                            delta.document_id,
+                            client_id,
+                            clock_to_sql(clock)
+                        ])
+                        .map_err(sql_error)?;
+                }
+            }
+        }
+
+        tx.commit().map_err(sql_error)
+    }
+
+    fn deltas_since(&self, document_id: &str, since: &VectorClock) -> Result<Vec<Delta>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT payload FROM deltas WHERE document_id = ?1 ORDER BY seq")
+            .map_err(sql_error)?;
+
+        let payloads = stmt
+            .query_map(params![document_id], |row| row.get::<_, String>(0))
+            .map_err(sql_error)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+
+        let mut deltas = Vec::new();
+        for payload in &payloads {
+            let delta: Delta = decode(payload)?;
+            if !since.dominates(&delta.new_version) {
+                deltas.push(delta);
+            }
+        }
+
+        Ok(deltas)
+    }
+
+    fn list_documents(&self) -> Result<Vec<DocumentID>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id FROM documents
+                 UNION SELECT document_id FROM deltas
+                 UNION SELECT document_id FROM crdts
+                 ORDER BY 1",
+            )
+            .map_err(sql_error)?;
+
+        let ids = stmt
+            .query_map([], |row| row.get::<_, DocumentID>(0))
+            .map_err(sql_error)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+
+        Ok(ids)
+    }
+
+    fn delete_document(&mut self, document_id: &str) -> Result<bool> {
+        let tx = self.conn.transaction().map_err(sql_error)?;
+
+        // Fields and registers go with the document row (ON DELETE CASCADE)
+        let mut removed = 0;
+        for sql in [
+            "DELETE FROM documents WHERE id = ?1",
+            "DELETE FROM deltas WHERE document_id = ?1",
+            "DELETE FROM crdts WHERE document_id = ?1",
+        ] {
+            removed += tx.execute(sql, params![document_id]).map_err(sql_error)?;
+        }
+
+        tx.commit().map_err(sql_error)?;
+        Ok(removed > 0)
+    }
+}
+
+/// Bring the schema up to date
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = user_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(SyncError::StorageError(format!(
+            "Database schema version {} is newer than supported version {}",
+            current, SCHEMA_VERSION
+        )));
+    }
+    if current == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.transaction().map_err(sql_error)?;
+    for migration in &MIGRATIONS[current..] {
+        tx.execute_batch(migration).map_err(sql_error)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION as i64)
+        .map_err(sql_error)?;
+    tx.commit().map_err(sql_error)
+}
+
+fn user_version(conn: &Connection) -> Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map(|version| version as usize)
+        .map_err(sql_error)
+}
+
+fn sql_error(err: rusqlite::Error) -> SyncError {
+    SyncError::StorageError(format!("SQLite error: {}", err))
+}
+
+fn clock_to_sql(clock: u64) -> [u8; 8] {
+    clock.to_be_bytes()
+}
+
+fn clock_from_sql(bytes: &[u8]) -> Result<u64> {
+    let bytes = <[u8; 8]>::try_from(bytes)
+        .map_err(|_| SyncError::StorageError(format!("Invalid stored clock: {:?}", bytes)))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+fn encode<T: serde::Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|err| SyncError::SerializationError(err.to_string()))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(json: &str) -> Result<T> {
+    serde_json::from_str(json).map_err(|err| SyncError::DeserializationError(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::compute_delta;
+    use serde_json::json;
+
+    fn edit(
+        doc: &mut Document,
+        field: &str,
+        value: serde_json::Value,
+        clock: u64,
+        client: &str,
+    ) -> Delta {
+        let before = doc.clone();
+        doc.set_field(field.to_string(), value, clock, client.to_string());
+        doc.version.tick(&client.to_string());
+        compute_delta(&before, doc)
+    }
+
+    #[test]
+    fn test_document_roundtrip_preserves_timestamps() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        edit(&mut doc, "user.name", json!("Ada"), 3, "client1");
+        edit(&mut doc, "tags[0]", json!("x"), 4, "client2");
+        doc.delete_field("user.name".to_string(), 5, "client2".to_string());
+
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+
+        assert_eq!(loaded.fields(), doc.fields());
+        assert_eq!(loaded.version(), doc.version());
+        assert_eq!(loaded.to_json(), doc.to_json());
+        assert!(storage.load_document("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_document_roundtrip_preserves_multi_value_fields() {
+        use crate::document::FieldMode;
+
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field_mode("assignee".to_string(), FieldMode::MultiValue)
+            .unwrap();
+        let mut other = doc.clone();
+        doc.set_field(
+            "assignee".to_string(),
+            json!("alice"),
+            1,
+            "alice".to_string(),
+        );
+        other.set_field("assignee".to_string(), json!("bob"), 1, "bob".to_string());
+        doc.merge(&other);
+        edit(&mut doc, "title", json!("T"), 2, "alice");
+
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+
+        assert_eq!(loaded.registers(), doc.registers());
+        assert_eq!(
+            loaded.get_field_values(&"assignee".to_string()),
+            vec![&json!("alice"), &json!("bob")]
+        );
+        assert_eq!(loaded.to_json(), doc.to_json());
+
+        // Saving again replaces the registers
+        doc.set_field(
+            "assignee".to_string(),
+            json!("carol"),
+            3,
+            "alice".to_string(),
+        );
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.registers(), doc.registers());
+    }
+
+    #[test]
+    fn test_delta_log_queries() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let first = edit(&mut doc, "a", json!(1), 1, "alice");
+        let second = edit(&mut doc, "b", json!(2), 2, "bob");
+        let third = edit(&mut doc, "c", json!(3), 3, "alice");
+
+        for delta in [&first, &second, &third] {
+            storage.append_delta(delta).unwrap();
+        }
+
+        let since_first = storage.deltas_since("doc-1", &first.new_version).unwrap();
+        assert_eq!(since_first, vec![second.clone(), third.clone()]);
+
+        let by_alice = storage.deltas_by_client("doc-1", "alice", 1).unwrap();
+        assert_eq!(by_alice, vec![third]);
+    }
+
+    #[test]
+    fn test_clocks_past_i64_max_keep_their_order() {
+        let big = i64::MAX as u64 + 1;
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field("a".to_string(), json!(1), u64::MAX, "alice".to_string());
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.fields(), doc.fields());
+
+        let mut small = Delta::empty("doc-1".to_string(), VectorClock::new());
+        small.new_version.update(&"alice".to_string(), 5);
+        let mut large = Delta::empty("doc-1".to_string(), small.new_version.clone());
+        large.new_version.update(&"alice".to_string(), big);
+        for delta in [&large, &small] {
+            storage.append_delta(delta).unwrap();
+        }
+
+        let by_alice = storage.deltas_by_client("doc-1", "alice", 0).unwrap();
+        assert_eq!(by_alice, vec![small, large.clone()]);
+        assert_eq!(
+            storage.deltas_by_client("doc-1", "alice", 5).unwrap(),
+            vec![large]
+        );
+    }
+
+    #[test]
+    fn test_deltas_by_client_follow_version_entries() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        // Bob relays alice's write: the field is hers, the version entry his
+        let before = doc.clone();
+        doc.set_field("a".to_string(), json!(1), 9, "alice".to_string());
+        doc.version.tick(&"bob".to_string());
+        storage.append_delta(&compute_delta(&before, &doc)).unwrap();
+
+        assert!(storage
+            .deltas_by_client("doc-1", "alice", 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage.deltas_by_client("doc-1", "bob", 0).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_crdt_blobs() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        storage
+            .save_crdt("doc-1", "body", CrdtKind::Text, b"hello")
+            .unwrap();
+        storage
+            .save_crdt("doc-1", "body", CrdtKind::Text, b"hello world")
+            .unwrap();
+
+        assert_eq!(
+            storage.load_crdt("doc-1", "body").unwrap(),
+            Some((CrdtKind::Text, b"hello world".to_vec()))
+        );
+        assert!(storage.load_crdt("doc-1", "likes").unwrap().is_none());
+        assert_eq!(storage.list_documents().unwrap(), vec!["doc-1"]);
+    }
+
+    #[test]
+    fn test_delete_document_removes_everything() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let delta = edit(&mut doc, "a", json!(1), 1, "alice");
+        storage.save_document(&doc).unwrap();
+        storage.append_delta(&delta).unwrap();
+        storage
+            .save_crdt("doc-1", "likes", CrdtKind::Counter, &[1])
+            .unwrap();
+
+        assert!(storage.delete_document("doc-1").unwrap());
+        assert!(!storage.delete_document("doc-1").unwrap());
+        assert!(storage.list_documents().unwrap().is_empty());
+        assert!(storage
+            .deltas_since("doc-1", &VectorClock::new())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_file_database_persists_and_versions_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("synckit.db");
+        let mut doc = Document::new("doc-1".to_string());
+        edit(&mut doc, "title", json!("Hi"), 1, "alice");
+
+        {
+            let mut storage = SqliteStorage::open(&path).unwrap();
+            assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION);
+            storage.save_document(&doc).unwrap();
+        }
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.to_json(), doc.to_json());
+    }
+
+    #[test]
+    fn test_document_roundtrip_preserves_redacted_mark() {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        let mut doc = Document::new("doc-1".to_string());
+        let field = Field {
+            redacted: true,
+            ..Field::new(
+                json!({ "name": "Ann" }),
+                Timestamp::new(1, "alice".to_string()),
+            )
+        };
+        doc.merge_field("user".to_string(), field.clone());
+
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.fields().get("user"), Some(&field));
+    }
+
+    #[test]
+    fn test_migrates_version_1_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v1.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+        }
+
+        let mut storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION);
+
+        let mut doc = Document::new("doc-1".to_string());
+        doc.set_field_mode("owner".to_string(), crate::document::FieldMode::MultiValue)
+            .unwrap();
+        doc.set_field("owner".to_string(), json!("alice"), 1, "alice".to_string());
+        storage.save_document(&doc).unwrap();
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.registers(), doc.registers());
+    }
+
+    #[test]
+    fn test_migrates_integer_clocks_to_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v3.db");
+        let mut doc = Document::new("doc-1".to_string());
+        let delta = edit(&mut doc, "a", json!(1), 7, "alice");
+        {
+            let conn = Connection::open(&path).unwrap();
+            for migration in &MIGRATIONS[..3] {
+                conn.execute_batch(migration).unwrap();
+            }
+            conn.pragma_update(None, "user_version", 3).unwrap();
+            conn.execute(
+                "INSERT INTO documents (id, version) VALUES ('doc-1', ?1)",
+                params![encode(doc.version()).unwrap()],
+            )
+            .unwrap();
+            // A clock past i64::MAX, wrapped negative by the old schema
+            conn.execute(
+                "INSERT INTO fields (document_id, path, value, clock, client_id)
+                 VALUES ('doc-1', 'a', '1', ?1, 'alice')",
+                params![u64::MAX as i64],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO deltas (document_id, client_id, clock, version, payload)
+                 VALUES ('doc-1', 'alice', 7, ?1, ?2)",
+                params![encode(&delta.new_version).unwrap(), encode(&delta).unwrap()],
+            )
+            .unwrap();
+        }
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), SCHEMA_VERSION);
+        let loaded = storage.load_document("doc-1").unwrap().unwrap();
+        assert_eq!(loaded.fields()["a"].timestamp.clock, u64::MAX);
+        assert_eq!(
+            storage.deltas_by_client("doc-1", "alice", 0).unwrap(),
+            vec![delta]
+        );
+    }
+
+    #[test]
+    fn test_refuses_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("future.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION as i64 + 1)
+                .unwrap();
+        }
+
+        let err = SqliteStorage::open(&path).unwrap_err();
+        assert!(matches!(err, SyncError::StorageError(_)));
+    }
+}
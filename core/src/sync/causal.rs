@@ -0,0 +1,397 @@
+//! Causal delivery of deltas
+//!
+//! Applying a delta merges its version into the document's, so a delta
+//! that arrives before its predecessors would make the document claim
+//! changes it has never seen, and those predecessors would then look like
+//! duplicates. A [`CausalBuffer`] holds such deltas back until the
+//! document's version dominates their `base_version`, then applies them in
+//! causal order. Meanwhile it can list what is missing, so the transport
+//! can ask for it.
+
+use crate::document::Document;
+use crate::error::{Result, SyncError};
+use crate::sync::{apply_delta, Delta, VectorClock};
+use crate::ClientID;
+use std::collections::BTreeMap;
+
+/// Default limit on the number of buffered deltas
+pub const DEFAULT_MAX_DELTAS: usize = 1024;
+
+/// Default limit on the size of buffered deltas (JSON-encoded, in bytes)
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Consecutive clock values of one client that no delta has covered yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingRange {
+    /// Client whose changes are missing
+    pub client_id: ClientID,
+
+    /// First missing clock value
+    pub from: u64,
+
+    /// Last missing clock value (inclusive)
+    pub to: u64,
+}
+
+/// A delta waiting for its predecessors
+#[derive(Debug, Clone)]
+struct Pending {
+    delta: Delta,
+    size: usize,
+}
+
+/// Holds deltas back until everything they build on has been applied
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::sync::{compute_delta, CausalBuffer};
+/// use synckit_core::Document;
+/// use serde_json::json;
+///
+/// // Alice makes two edits in a row
+/// let mut alice = Document::new("doc-1".to_string());
+/// let v0 = alice.clone();
+/// alice.set_field("title".to_string(), json!("Draft"), 1, "alice".to_string());
+/// alice.version.tick(&"alice".to_string());
+/// let v1 = alice.clone();
+/// alice.set_field("title".to_string(), json!("Final"), 2, "alice".to_string());
+/// alice.version.tick(&"alice".to_string());
+///
+/// let first = compute_delta(&v0, &v1);
+/// let second = compute_delta(&v1, &alice);
+///
+/// // The second edit arrives first and waits
+/// let mut bob = Document::new("doc-1".to_string());
+/// let mut buffer = CausalBuffer::new();
+/// assert!(buffer.receive(&mut bob, second).unwrap().is_empty());
+/// assert_eq!(buffer.missing(bob.version())[0].from, 1);
+///
+/// // The first one releases both, in order
+/// assert_eq!(buffer.receive(&mut bob, first).unwrap().len(), 2);
+/// assert_eq!(bob.get_field(&"title".to_string()), Some(&json!("Final")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CausalBuffer {
+    /// Buffered deltas, by arrival
+    pending: BTreeMap<u64, Pending>,
+
+    /// Arrival number of the next buffered delta
+    next_seq: u64,
+
+    /// Total size of buffered deltas
+    bytes: usize,
+
+    /// Limit on the number of buffered deltas
+    max_deltas: usize,
+
+    /// Limit on the total size of buffered deltas
+    max_bytes: usize,
+}
+
+impl CausalBuffer {
+    /// Create an empty buffer with the default limits
+    pub fn new() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            next_seq: 0,
+            bytes: 0,
+            max_deltas: DEFAULT_MAX_DELTAS,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+
+    /// Set the limit on the number of buffered deltas
+    pub fn with_max_deltas(mut self, max_deltas: usize) -> Self {
+        self.max_deltas = max_deltas;
+        self
+    }
+
+    /// Set the limit on the total size of buffered deltas (JSON bytes)
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Receive a delta for `doc`, applying whatever has become deliverable
+    ///
+    /// The delta is applied right away if the document's version dominates
+    /// its `base_version`, and buffered otherwise. Applying it can make
+    /// buffered deltas deliverable in turn. Returns the deltas applied, in
+    /// the order they were applied; deltas the document has already seen
+    /// are dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `SyncError::InvalidOperation` if the delta is for another document
+    /// - `SyncError::BufferFull` if the delta would have to wait but the
+    ///   buffer is at its limit; the delta is dropped and should be asked
+    ///   for again once the missing deltas have arrived
+    pub fn receive(&mut self, doc: &mut Document, delta: Delta) -> Result<Vec<Delta>> {
+        if delta.document_id != *doc.id() {
+            return Err(SyncError::InvalidOperation(format!(
+                "Delta for {} cannot be delivered to {}",
+                delta.document_id,
+                doc.id()
+            )));
+        }
+
+        if is_seen(doc.version(), &delta) {
+            return Ok(Vec::new());
+        }
+
+        if !doc.version().dominates(&delta.base_version) {
+            self.hold(delta)?;
+            return Ok(Vec::new());
+        }
+
+        apply_delta(doc, &delta);
+        let mut delivered = vec![delta];
+        delivered.extend(self.release(doc));
+        Ok(delivered)
+    }
+
+    /// List the changes buffered deltas are waiting for
+    ///
+    /// For each client, the clock values after `version` up to what the
+    /// buffered deltas build on, minus those the buffered deltas bring
+    /// themselves. Ranges are sorted by client, then clock value.
+    pub fn missing(&self, version: &VectorClock) -> Vec<MissingRange> {
+        // Per client: clock values needed, and runs the buffer covers
+        let mut needed: BTreeMap<&ClientID, u64> = BTreeMap::new();
+        let mut covered: BTreeMap<&ClientID, Vec<(u64, u64)>> = BTreeMap::new();
+
+        for pending in self.pending.values() {
+            let delta = &pending.delta;
+            for (client_id, &clock) in delta.base_version.clocks() {
+                let entry = needed.entry(client_id).or_insert(0);
+                *entry = (*entry).max(clock);
+            }
+            for (client_id, &clock) in delta.new_version.clocks() {
+                let from = delta.base_version.get(client_id) + 1;
+                if from <= clock {
+                    covered.entry(client_id).or_default().push((from, clock));
+                }
+            }
+        }
+
+        let mut ranges = Vec::new();
+        for (client_id, up_to) in needed {
+            let mut next = version.get(client_id) + 1;
+            let mut runs = covered.remove(client_id).unwrap_or_default();
+            runs.sort_unstable();
+
+            for (from, to) in runs {
+                if next > up_to {
+                    break;
+                }
+                if from > next {
+                    ranges.push(MissingRange {
+                        client_id: client_id.clone(),
+                        from: next,
+                        to: (from - 1).min(up_to),
+                    });
+                }
+                next = next.max(to + 1);
+            }
+
+            if next <= up_to {
+                ranges.push(MissingRange {
+                    client_id: client_id.clone(),
+                    from: next,
+                    to: up_to,
+                });
+            }
+        }
+
+        ranges
+    }
+
+    /// Number of buffered deltas
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Check if no delta is waiting
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Total size of buffered deltas (JSON bytes)
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Drop all buffered deltas
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.bytes = 0;
+    }
+
+    fn hold(&mut self, delta: Delta) -> Result<()> {
+        let size = serde_json::to_vec(&delta)
+            .map_err(|e| SyncError::SerializationError(e.to_string()))?
+            .len();
+
+        if self.pending.len() >= self.max_deltas || self.bytes + size > self.max_bytes {
+            return Err(SyncError::BufferFull(format!(
+                "{} deltas ({} bytes) already waiting, limit {} deltas ({} bytes)",
+                self.pending.len(),
+                self.bytes,
+                self.max_deltas,
+                self.max_bytes
+            )));
+        }
+
+        self.bytes += size;
+        self.pending.insert(self.next_seq, Pending { delta, size });
+        self.next_seq += 1;
+        Ok(())
+    }
+
+    /// Apply buffered deltas until none is deliverable
+    fn release(&mut self, doc: &mut Document) -> Vec<Delta> {
+        let mut delivered = Vec::new();
+
+        loop {
+            // Drop what the document has seen meanwhile
+            let seen: Vec<u64> = self
+                .pending
+                .iter()
+                .filter(|(_, pending)| is_seen(doc.version(), &pending.delta))
+                .map(|(&seq, _)| seq)
+                .collect();
+            for seq in seen {
+                self.take(seq);
+            }
+
+            let ready = self
+                .pending
+                .iter()
+                .find(|(_, pending)| doc.version().dominates(&pending.delta.base_version))
+                .map(|(&seq, _)| seq);
+
+            let Some(seq) = ready else {
+                return delivered;
+            };
+
+            let delta = self.take(seq);
+            apply_delta(doc, &delta);
+            delivered.push(delta);
+        }
+    }
+
+    fn take(&mut self, seq: u64) -> Delta {
+        let pending = self.pending.remove(&seq).expect("buffered delta");
+        self.bytes -= pending.size;
+        pending.delta
+    }
+}
+
+impl Default for CausalBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check if a document at `version` has already seen everything in `delta`
+///
+/// Deltas that do not advance the version cannot be told apart from new
+/// ones, so they never count as seen (applying them again is harmless).
+fn is_seen(version: &VectorClock, delta: &Delta) -> bool {
+    !delta.base_version.dominates(&delta.new_version) && version.dominates(&delta.new_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Field;
+    use crate::sync::Timestamp;
+    use serde_json::json;
+
+    /// A delta from `client` writing `field` at clock `clock`, on top of `base`
+    fn delta(base: &[(&str, u64)], client: &str, clock: u64, field: &str) -> Delta {
+        let mut base_version = VectorClock::new();
+        for (client_id, value) in base {
+            base_version.update(&client_id.to_string(), *value);
+        }
+        let mut new_version = base_version.clone();
+        new_version.update(&client.to_string(), clock);
+
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            field.to_string(),
+            Field::new(json!(clock), Timestamp::new(clock, client.to_string())),
+        );
+        Delta::new("doc-1".to_string(), fields, new_version).with_base_version(base_version)
+    }
+
+    #[test]
+    fn test_releases_in_causal_order() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut buffer = CausalBuffer::new();
+
+        let d1 = delta(&[], "a", 1, "x");
+        let d2 = delta(&[("a", 1)], "a", 2, "x");
+        let d3 = delta(&[("a", 2)], "b", 1, "y");
+
+        assert!(buffer.receive(&mut doc, d3.clone()).unwrap().is_empty());
+        assert!(buffer.receive(&mut doc, d2.clone()).unwrap().is_empty());
+        assert_eq!(buffer.len(), 2);
+
+        let delivered = buffer.receive(&mut doc, d1.clone()).unwrap();
+        assert_eq!(delivered, vec![d1.clone(), d2, d3]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.bytes(), 0);
+        assert_eq!(doc.get_field(&"x".to_string()), Some(&json!(2)));
+
+        // Redelivery is dropped
+        assert!(buffer.receive(&mut doc, d1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reports_missing_ranges() {
+        let doc = Document::new("doc-1".to_string());
+        let mut buffer = CausalBuffer::new();
+        let mut scratch = doc.clone();
+
+        // Waiting on a:1..=2 (a:3..=4 is here) and a:5, plus b:1..=3
+        buffer
+            .receive(&mut scratch, delta(&[("a", 2)], "a", 4, "x"))
+            .unwrap();
+        buffer
+            .receive(&mut scratch, delta(&[("a", 5), ("b", 3)], "c", 1, "y"))
+            .unwrap();
+
+        let range = |client: &str, from, to| MissingRange {
+            client_id: client.to_string(),
+            from,
+            to,
+        };
+        assert_eq!(
+            buffer.missing(doc.version()),
+            vec![range("a", 1, 2), range("a", 5, 5), range("b", 1, 3)]
+        );
+    }
+
+    #[test]
+    fn test_buffer_limits() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut buffer = CausalBuffer::new().with_max_deltas(1);
+
+        buffer
+            .receive(&mut doc, delta(&[("a", 1)], "a", 2, "x"))
+            .unwrap();
+        let result = buffer.receive(&mut doc, delta(&[("a", 2)], "a", 3, "x"));
+        assert!(matches!(result, Err(SyncError::BufferFull(_))));
+        assert_eq!(buffer.len(), 1);
+
+        // Deliverable deltas never count against the limit
+        let delivered = buffer.receive(&mut doc, delta(&[], "a", 1, "x")).unwrap();
+        assert_eq!(delivered.len(), 2);
+
+        let mut tiny = CausalBuffer::new().with_max_bytes(10);
+        let result = tiny.receive(&mut doc, delta(&[("z", 1)], "a", 9, "x"));
+        assert!(matches!(result, Err(SyncError::BufferFull(_))));
+    }
+}
@@ -0,0 +1,154 @@
+//! Merge reports and custom conflict resolution
+//!
+//! `Document::merge` only says how many fields changed. The `*_with_report`
+//! merge variants also say, for every remote field, which write was kept
+//! and why, with both writes attached, so applications can audit what a
+//! merge overwrote. A [`ConflictResolver`] can be plugged into the merge to
+//! pick the winner for some paths instead of LWW.
+
+use crate::document::Field;
+use crate::FieldPath;
+use serde::{Deserialize, Serialize};
+
+/// Why a write won the merge of one field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MergeDecision {
+    /// The local write is newer, or a newer write to a parent covers the
+    /// remote one
+    KeptLocal,
+
+    /// The remote write is newer, or there was no local write
+    TookRemote,
+
+    /// Both writes have the same clock: the higher client ID won
+    TieBrokenByClientId,
+
+    /// Same clock and client: the tombstone won, or else the greater value
+    TieBrokenByValue,
+
+    /// A `ConflictResolver` picked the winner
+    Resolved,
+}
+
+/// The merge of one remote field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldMerge {
+    /// Canonical path of the field
+    pub path: FieldPath,
+
+    /// Local write before the merge, if any
+    pub local: Option<Field>,
+
+    /// Remote write
+    pub remote: Field,
+
+    /// Why the winner won
+    pub decision: MergeDecision,
+
+    /// Whether the remote write is now the local value
+    pub took_remote: bool,
+}
+
+impl FieldMerge {
+    /// Check if the merge replaced an existing local write
+    pub fn overwrote_local(&self) -> bool {
+        self.took_remote && self.local.is_some()
+    }
+}
+
+/// What a merge did, field by field
+///
+/// Lists every remote field that differs from the local one. Multi-value
+/// fields are merged too, but not listed: they keep concurrent writes
+/// instead of picking a winner.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// One entry per merged field, in merge order
+    pub fields: Vec<FieldMerge>,
+}
+
+impl MergeReport {
+    /// Number of fields that took the remote write
+    pub fn updated_count(&self) -> usize {
+        self.fields.iter().filter(|f| f.took_remote).count()
+    }
+
+    /// Fields where a remote write replaced a local one
+    pub fn overwritten(&self) -> impl Iterator<Item = &FieldMerge> {
+        self.fields.iter().filter(|f| f.overwrote_local())
+    }
+
+    /// Number of fields in the report
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Check if the merge brought nothing new
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// The write picked by a `ConflictResolver`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Winner {
+    /// Keep the local write
+    Local,
+
+    /// Take the remote write
+    Remote,
+}
+
+/// Picks the winner when a remote write meets a different local write
+///
+/// Called for LWW fields only, and not for writes a newer parent write
+/// already covers. Return `None` to fall back to LWW.
+///
+/// Replicas only converge if they all merge with the same resolver, and
+/// the resolver picks the same write whichever side it is on.
+/// Closures with the same signature are resolvers too.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::document::Field;
+/// use synckit_core::sync::Winner;
+/// use synckit_core::Document;
+/// use serde_json::json;
+/// use std::cmp::Ordering;
+///
+/// let mut local = Document::new("doc-1".to_string());
+/// local.set_field("priority".to_string(), json!(3), 1, "client1".to_string());
+///
+/// let mut remote = Document::new("doc-1".to_string());
+/// remote.set_field("priority".to_string(), json!(1), 2, "client2".to_string());
+///
+/// // Keep the higher priority, whoever wrote last
+/// let mut highest = |path: &str, local: &Field, remote: &Field| {
+///     if path != "priority" {
+///         return None;
+///     }
+///     match remote.value.as_i64().cmp(&local.value.as_i64()) {
+///         Ordering::Greater => Some(Winner::Remote),
+///         Ordering::Less => Some(Winner::Local),
+///         Ordering::Equal => None,
+///     }
+/// };
+///
+/// let report = local.merge_with_resolver(&remote, &mut highest);
+/// assert_eq!(local.get_field(&"priority".to_string()), Some(&json!(3)));
+/// assert!(!report.fields[0].took_remote);
+/// ```
+pub trait ConflictResolver {
+    /// Pick the winner of two writes to `path`, or `None` for LWW
+    fn resolve(&mut self, path: &str, local: &Field, remote: &Field) -> Option<Winner>;
+}
+
+impl<F> ConflictResolver for F
+where
+    F: FnMut(&str, &Field, &Field) -> Option<Winner>,
+{
+    fn resolve(&mut self, path: &str, local: &Field, remote: &Field) -> Option<Winner> {
+        self(path, local, remote)
+    }
+}
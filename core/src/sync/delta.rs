@@ -2,12 +2,22 @@
 //!
 //! Computes minimal changes between document states to reduce bandwidth usage.
 //! Only transmits fields that actually changed rather than full documents.
+//!
+//! [`Delta`] is the one change format: it is stored as JSON by the storage
+//! backends and by the lite build, and converted to and from the protobuf
+//! `Delta` message by the binary protocol (see [`crate::protocol::delta`]).
+//! Deletes travel as tombstone fields, and applying a delta is an LWW merge,
+//! so deltas can be applied in any order and more than once.
 
 use crate::document::{Document, Field};
-use crate::sync::VectorClock;
+use crate::error::{Result, SyncError};
+use crate::sync::policy::redact_value;
+use crate::sync::{
+    Access, ConflictResolver, FieldPolicy, MVEntry, MVRegister, MergeReport, VectorClock,
+};
 use crate::{DocumentID, FieldPath};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Represents changes between two document states
 ///
@@ -18,24 +28,38 @@ pub struct Delta {
     /// Document this delta applies to
     pub document_id: DocumentID,
 
-    /// Changed fields (only includes fields that differ)
-    pub fields: HashMap<FieldPath, Field>,
+    /// Changed fields (only includes fields that differ, tombstones included)
+    pub fields: BTreeMap<FieldPath, Field>,
+
+    /// Changed multi-value fields
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registers: BTreeMap<FieldPath, MVRegister>,
+
+    /// Vector clock of the state the delta was computed from
+    ///
+    /// Empty if unknown. Only informational: applying a delta is an LWW
+    /// merge, which does not need the receiver to be at this version.
+    #[serde(default)]
+    pub base_version: VectorClock,
 
     /// Vector clock after applying this delta
-    pub version: VectorClock,
+    #[serde(alias = "version")]
+    pub new_version: VectorClock,
 }
 
 impl Delta {
-    /// Create a new delta
+    /// Create a new delta with an unknown base version
     pub fn new(
         document_id: DocumentID,
-        fields: HashMap<FieldPath, Field>,
-        version: VectorClock,
+        fields: BTreeMap<FieldPath, Field>,
+        new_version: VectorClock,
     ) -> Self {
         Self {
             document_id,
             fields,
-            version,
+            registers: BTreeMap::new(),
+            base_version: VectorClock::new(),
+            new_version,
         }
     }
 
@@ -43,19 +67,178 @@ impl Delta {
     pub fn empty(document_id: DocumentID, version: VectorClock) -> Self {
         Self {
             document_id,
-            fields: HashMap::new(),
-            version,
+            fields: BTreeMap::new(),
+            registers: BTreeMap::new(),
+            base_version: version.clone(),
+            new_version: version,
+        }
+    }
+
+    /// Set the version the delta was computed from
+    pub fn with_base_version(mut self, base_version: VectorClock) -> Self {
+        self.base_version = base_version;
+        self
+    }
+
+    /// Compute the changes that turn `from` into `to`
+    ///
+    /// Same as [`compute_delta`], but fails instead of producing a
+    /// meaningless delta when the documents differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidOperation` if the document IDs differ.
+    pub fn compute(from: &Document, to: &Document) -> Result<Self> {
+        if from.id() != to.id() {
+            return Err(SyncError::InvalidOperation(
+                "Cannot compute delta between different documents".to_string(),
+            ));
+        }
+
+        Ok(compute_delta(from, to))
+    }
+
+    /// Apply the delta to a document
+    ///
+    /// Same as [`apply_delta`], but fails instead of panicking when the
+    /// delta is for another document.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidOperation` if the document IDs differ.
+    pub fn apply_to(&self, document: &mut Document) -> Result<()> {
+        self.check_document(document)?;
+        apply_delta(document, self);
+        Ok(())
+    }
+
+    /// Apply the delta for a client the policy restricts
+    ///
+    /// Nothing is applied unless the policy allows writing every changed
+    /// field.
+    ///
+    /// # Errors
+    ///
+    /// - `SyncError::InvalidOperation` if the document IDs differ
+    /// - `SyncError::PermissionDenied` if a changed field is not writable
+    pub fn apply_to_with_policy<P: FieldPolicy + ?Sized>(
+        &self,
+        document: &mut Document,
+        policy: &P,
+    ) -> Result<()> {
+        self.check_document(document)?;
+        self.check_writes(policy)?;
+        apply_delta(document, self);
+        Ok(())
+    }
+
+    /// Check that the policy allows writing every changed field
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::PermissionDenied` naming the first field that is
+    /// not writable.
+    pub fn check_writes<P: FieldPolicy + ?Sized>(&self, policy: &P) -> Result<()> {
+        let paths = self.fields.keys().chain(self.registers.keys());
+        for path in paths {
+            if !policy.allows(&self.document_id, path, Access::Write) {
+                return Err(SyncError::PermissionDenied(format!(
+                    "write to {} in document {}",
+                    path, self.document_id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy of the delta without what the policy does not let a client read
+    ///
+    /// Unreadable fields are dropped and readable values have their
+    /// unreadable parts cut out. Versions are kept, so the client still
+    /// moves on past this delta.
+    ///
+    /// Values cut down are marked [`Field::redacted`]: they keep the
+    /// original timestamp, and the mark makes them lose to the full value
+    /// when merged back, so the hidden parts are not wiped.
+    pub fn redact<P: FieldPolicy + ?Sized>(&self, policy: &P) -> Delta {
+        let id = &self.document_id;
+
+        let fields = self
+            .fields
+            .iter()
+            .filter_map(|(path, field)| {
+                let field = if field.is_deleted() {
+                    policy
+                        .allows_node(id, path, Access::Read)
+                        .then(|| field.clone())?
+                } else {
+                    let value = redact_value(policy, id, path, &field.value)?;
+                    Field {
+                        redacted: field.redacted || value != field.value,
+                        ..Field::new(value, field.timestamp.clone())
+                    }
+                };
+                Some((path.clone(), field))
+            })
+            .collect();
+
+        let registers = self
+            .registers
+            .iter()
+            .filter_map(|(path, register)| {
+                if !policy.allows_node(id, path, Access::Read) {
+                    return None;
+                }
+                let entries = register
+                    .entries()
+                    .iter()
+                    .filter_map(|entry| {
+                        Some(MVEntry {
+                            dot: entry.dot.clone(),
+                            value: redact_value(policy, id, path, &entry.value)?,
+                            timestamp: entry.timestamp.clone(),
+                        })
+                    })
+                    .collect();
+                let register = MVRegister::from_parts(entries, register.context().clone());
+                Some((path.clone(), register))
+            })
+            .collect();
+
+        Delta {
+            document_id: self.document_id.clone(),
+            fields,
+            registers,
+            base_version: self.base_version.clone(),
+            new_version: self.new_version.clone(),
         }
     }
 
     /// Check if delta is empty (no changes)
     pub fn is_empty(&self) -> bool {
-        self.fields.is_empty()
+        self.fields.is_empty() && self.registers.is_empty()
     }
 
     /// Get the number of changed fields
     pub fn len(&self) -> usize {
-        self.fields.len()
+        self.fields.len() + self.registers.len()
+    }
+
+    /// Iterate over changed paths that are deleted
+    pub fn deleted_paths(&self) -> impl Iterator<Item = &FieldPath> {
+        self.fields
+            .iter()
+            .filter(|(_, field)| field.is_deleted())
+            .map(|(path, _)| path)
+    }
+
+    pub(crate) fn check_document(&self, document: &Document) -> Result<()> {
+        if document.id() != &self.document_id {
+            return Err(SyncError::InvalidOperation(
+                "Cannot apply delta to different document".to_string(),
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -64,6 +247,10 @@ impl Delta {
 /// Returns a Delta containing only fields that changed between old and new.
 /// If documents have the same content, returns an empty delta.
 ///
+/// Fields deleted in `new` are sent as their tombstones. A field `old` has
+/// that `new` lacks altogether (and that no newer parent write covers) is
+/// sent as a tombstone with the old field's timestamp.
+///
 /// # Example
 /// ```ignore
 /// let old = Document::new("doc1");
@@ -74,34 +261,52 @@ impl Delta {
 /// assert_eq!(delta.len(), 1); // Only "title" field changed
 /// ```
 pub fn compute_delta(old: &Document, new: &Document) -> Delta {
-    let mut changed_fields = HashMap::new();
+    let mut changed_fields = BTreeMap::new();
 
     // Find all fields in new document
     for (field_path, new_field) in &new.fields {
-        match old.fields.get(field_path) {
-            Some(old_field) => {
-                // Field exists in both - check if it changed
-                if old_field != new_field {
-                    changed_fields.insert(field_path.clone(), new_field.clone());
-                }
-            }
-            None => {
-                // New field (didn't exist in old)
-                changed_fields.insert(field_path.clone(), new_field.clone());
-            }
+        if old.fields.get(field_path) != Some(new_field) {
+            // New field, or a modification (a delete flips the tombstone flag)
+            changed_fields.insert(field_path.clone(), new_field.clone());
+        }
+    }
+
+    // Fields missing from new entirely, with no tombstone recorded (paths
+    // dropped because a newer parent write covers them need none)
+    for (field_path, old_field) in &old.fields {
+        if !new.fields.contains_key(field_path)
+            && !new.is_shadowed(field_path, &old_field.timestamp)
+        {
+            changed_fields.insert(
+                field_path.clone(),
+                Field::tombstone(old_field.timestamp.clone()),
+            );
         }
     }
 
-    // Note: Deleted fields would be represented as tombstones in a full implementation
-    // For now, we only track additions and modifications
+    let mut delta = Delta::new(new.id.clone(), changed_fields, new.version.clone())
+        .with_base_version(old.version.clone());
+    for (field_path, new_register) in &new.registers {
+        if old.registers.get(field_path) != Some(new_register) {
+            delta
+                .registers
+                .insert(field_path.clone(), new_register.clone());
+        }
+    }
 
-    Delta::new(new.id.clone(), changed_fields, new.version.clone())
+    delta
 }
 
 /// Apply a delta to a document
 ///
 /// Updates the document with all changes from the delta using LWW merge semantics.
-/// If a field in the delta is newer, it replaces the local field.
+/// If a field in the delta is newer, it replaces the local field. Tombstones in
+/// the delta delete the local field under the same rule.
+///
+/// # Panics
+///
+/// Panics if the delta is for another document; `Delta::apply_to` returns
+/// an error instead.
 ///
 /// # Example
 /// ```ignore
@@ -113,40 +318,69 @@ pub fn apply_delta(doc: &mut Document, delta: &Delta) {
     // Verify we're applying to the correct document
     assert_eq!(doc.id, delta.document_id, "Delta document ID mismatch");
 
-    // Apply each changed field using LWW merge
+    // Apply each changed field (including tombstones) using LWW merge
     for (field_path, delta_field) in &delta.fields {
-        match doc.fields.get(field_path) {
-            Some(local_field) => {
-                // Field exists locally - use LWW merge
-                match delta_field.timestamp.cmp(&local_field.timestamp) {
-                    std::cmp::Ordering::Greater => {
-                        doc.fields.insert(field_path.clone(), delta_field.clone());
-                    }
-                    std::cmp::Ordering::Equal => {
-                        // Tie-breaking: use client_id comparison
-                        if delta_field.timestamp.client_id > local_field.timestamp.client_id {
-                            doc.fields.insert(field_path.clone(), delta_field.clone());
-                        }
-                    }
-                    std::cmp::Ordering::Less => {} // local is newer, keep local
-                }
-                // else: local is newer, keep local
-            }
-            None => {
-                // New field - insert it
-                doc.fields.insert(field_path.clone(), delta_field.clone());
-            }
-        }
+        doc.merge_field(field_path.clone(), delta_field.clone());
+    }
+    for (field_path, delta_register) in &delta.registers {
+        doc.merge_register(field_path.clone(), delta_register);
     }
 
     // Merge vector clocks
-    doc.version.merge(&delta.version);
+    doc.version.merge(&delta.new_version);
+}
+
+/// Apply a delta to a document and report every field decision
+///
+/// Same as `apply_delta`; see `Document::merge_with_report` for the report.
+/// Fields are reported in path order.
+pub fn apply_delta_with_report(doc: &mut Document, delta: &Delta) -> MergeReport {
+    apply_reporting(doc, delta, None)
+}
+
+/// Apply a delta to a document, letting `resolver` pick winners
+///
+/// See [`ConflictResolver`] for the rules a resolver must follow for
+/// replicas to converge.
+pub fn apply_delta_with_resolver(
+    doc: &mut Document,
+    delta: &Delta,
+    resolver: &mut dyn ConflictResolver,
+) -> MergeReport {
+    apply_reporting(doc, delta, Some(resolver))
+}
+
+fn apply_reporting(
+    doc: &mut Document,
+    delta: &Delta,
+    mut resolver: Option<&mut dyn ConflictResolver>,
+) -> MergeReport {
+    assert_eq!(doc.id, delta.document_id, "Delta document ID mismatch");
+
+    let mut report = MergeReport::default();
+    for (field_path, delta_field) in &delta.fields {
+        let resolver = resolver
+            .as_mut()
+            .map(|r| &mut **r as &mut dyn ConflictResolver);
+        report.fields.extend(doc.merge_field_with_report(
+            field_path.clone(),
+            delta_field.clone(),
+            resolver,
+        ));
+    }
+    for (field_path, delta_register) in &delta.registers {
+        doc.merge_register(field_path.clone(), delta_register);
+    }
+
+    doc.version.merge(&delta.new_version);
+    report
 }
 
 /// Merge two deltas into a single delta
 ///
 /// Combines changes from both deltas, using LWW semantics when the same field
-/// is modified in both deltas.
+/// is modified in both deltas. The result starts from `delta1`'s base
+/// version.
 ///
 /// Useful for combining multiple pending changes before transmission.
 pub fn merge_deltas(delta1: &Delta, delta2: &Delta) -> Delta {
@@ -162,17 +396,8 @@ pub fn merge_deltas(delta1: &Delta, delta2: &Delta) -> Delta {
         match merged_fields.get(field_path) {
             Some(field1) => {
                 // Field in both deltas - use LWW
-                match field2.timestamp.cmp(&field1.timestamp) {
-                    std::cmp::Ordering::Greater => {
-                        merged_fields.insert(field_path.clone(), field2.clone());
-                    }
-                    std::cmp::Ordering::Equal => {
-                        // Tie-breaking
-                        if field2.timestamp.client_id > field1.timestamp.client_id {
-                            merged_fields.insert(field_path.clone(), field2.clone());
-                        }
-                    }
-                    std::cmp::Ordering::Less => {} // field1 is newer, keep it
+                if field2.timestamp.is_newer_than(&field1.timestamp) {
+                    merged_fields.insert(field_path.clone(), field2.clone());
                 }
             }
             None => {
@@ -182,11 +407,23 @@ pub fn merge_deltas(delta1: &Delta, delta2: &Delta) -> Delta {
         }
     }
 
+    // Multi-value fields merge like in a document
+    let mut merged_registers = delta1.registers.clone();
+    for (field_path, register2) in &delta2.registers {
+        merged_registers
+            .entry(field_path.clone())
+            .or_default()
+            .merge(register2);
+    }
+
     // Merge vector clocks
-    let mut merged_version = delta1.version.clone();
-    merged_version.merge(&delta2.version);
+    let mut merged_version = delta1.new_version.clone();
+    merged_version.merge(&delta2.new_version);
 
-    Delta::new(delta1.document_id.clone(), merged_fields, merged_version)
+    let mut merged = Delta::new(delta1.document_id.clone(), merged_fields, merged_version)
+        .with_base_version(delta1.base_version.clone());
+    merged.registers = merged_registers;
+    merged
 }
 
 #[cfg(test)]
@@ -279,12 +516,14 @@ mod tests {
     fn test_apply_delta_new_field() {
         let mut doc = Document::new("doc1".to_string());
 
-        let mut delta_fields = HashMap::new();
+        let mut delta_fields = BTreeMap::new();
         delta_fields.insert(
             "title".to_string(),
             Field {
                 value: json!("Hello"),
                 timestamp: Timestamp::new(1, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
@@ -302,12 +541,14 @@ mod tests {
         doc.set_field("title".to_string(), json!("Old"), 1, "client1".to_string());
 
         // Delta with newer timestamp
-        let mut delta_fields = HashMap::new();
+        let mut delta_fields = BTreeMap::new();
         delta_fields.insert(
             "title".to_string(),
             Field {
                 value: json!("New"),
                 timestamp: Timestamp::new(2, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
@@ -325,12 +566,14 @@ mod tests {
         doc.set_field("title".to_string(), json!("New"), 2, "client1".to_string());
 
         // Delta with older timestamp
-        let mut delta_fields = HashMap::new();
+        let mut delta_fields = BTreeMap::new();
         delta_fields.insert(
             "title".to_string(),
             Field {
                 value: json!("Old"),
                 timestamp: Timestamp::new(1, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
@@ -345,21 +588,25 @@ mod tests {
 
     #[test]
     fn test_merge_deltas_non_overlapping() {
-        let mut fields1 = HashMap::new();
+        let mut fields1 = BTreeMap::new();
         fields1.insert(
             "title".to_string(),
             Field {
                 value: json!("Title"),
                 timestamp: Timestamp::new(1, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
-        let mut fields2 = HashMap::new();
+        let mut fields2 = BTreeMap::new();
         fields2.insert(
             "body".to_string(),
             Field {
                 value: json!("Body"),
                 timestamp: Timestamp::new(2, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
@@ -375,21 +622,25 @@ mod tests {
 
     #[test]
     fn test_merge_deltas_overlapping_field() {
-        let mut fields1 = HashMap::new();
+        let mut fields1 = BTreeMap::new();
         fields1.insert(
             "title".to_string(),
             Field {
                 value: json!("Old"),
                 timestamp: Timestamp::new(1, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
-        let mut fields2 = HashMap::new();
+        let mut fields2 = BTreeMap::new();
         fields2.insert(
             "title".to_string(),
             Field {
                 value: json!("New"),
                 timestamp: Timestamp::new(2, "client1".to_string()),
+                deleted: false,
+                redacted: false,
             },
         );
 
@@ -425,4 +676,231 @@ mod tests {
         assert_eq!(reconstructed.fields["title"], new.fields["title"]);
         assert_eq!(reconstructed.fields["body"], new.fields["body"]);
     }
+
+    #[test]
+    fn test_delta_propagates_delete() {
+        let mut old = Document::new("doc1".to_string());
+        old.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            1,
+            "client1".to_string(),
+        );
+
+        let mut new = old.clone();
+        new.delete_field("title".to_string(), 2, "client1".to_string());
+
+        let delta = compute_delta(&old, &new);
+        assert_eq!(delta.len(), 1);
+        assert!(delta.fields["title"].is_deleted());
+
+        // Applying to a replica that still has the value deletes it
+        let mut replica = old.clone();
+        apply_delta(&mut replica, &delta);
+        assert_eq!(replica.get_field(&"title".to_string()), None);
+
+        // Re-applying the stale state does not bring it back
+        let resurrect = compute_delta(&Document::new("doc1".to_string()), &old);
+        apply_delta(&mut replica, &resurrect);
+        assert_eq!(replica.get_field(&"title".to_string()), None);
+    }
+
+    #[test]
+    fn test_delta_carries_multi_value_fields() {
+        use crate::document::FieldMode;
+
+        let mut old = Document::new("doc1".to_string());
+        old.set_field_mode("assignee".to_string(), FieldMode::MultiValue)
+            .unwrap();
+
+        let mut alice = old.clone();
+        let mut bob = old.clone();
+        alice.set_field(
+            "assignee".to_string(),
+            json!("alice"),
+            1,
+            "alice".to_string(),
+        );
+        bob.set_field("assignee".to_string(), json!("bob"), 1, "bob".to_string());
+
+        let merged = merge_deltas(&compute_delta(&old, &alice), &compute_delta(&old, &bob));
+        assert_eq!(merged.len(), 1);
+
+        apply_delta(&mut old, &merged);
+        assert_eq!(
+            old.get_field_values(&"assignee".to_string()),
+            vec![&json!("alice"), &json!("bob")]
+        );
+    }
+
+    #[test]
+    fn test_apply_delta_with_resolver() {
+        use crate::sync::{MergeDecision, Winner};
+
+        let mut doc = Document::new("doc1".to_string());
+        doc.set_field(
+            "title".to_string(),
+            json!("Local"),
+            5,
+            "client1".to_string(),
+        );
+        doc.set_field(
+            "status".to_string(),
+            json!("done"),
+            5,
+            "client1".to_string(),
+        );
+
+        let mut remote = Document::new("doc1".to_string());
+        remote.set_field(
+            "title".to_string(),
+            json!("Remote"),
+            1,
+            "client2".to_string(),
+        );
+        remote.set_field(
+            "status".to_string(),
+            json!("open"),
+            9,
+            "client2".to_string(),
+        );
+        remote.set_field("body".to_string(), json!("New"), 1, "client2".to_string());
+        let delta = compute_delta(&Document::new("doc1".to_string()), &remote);
+
+        // Status never goes back from "done"; everything else is LWW
+        let mut keep_done = |path: &str, local: &Field, _: &Field| {
+            (path == "status" && local.value == json!("done")).then_some(Winner::Local)
+        };
+        let report = apply_delta_with_resolver(&mut doc, &delta, &mut keep_done);
+
+        let decisions: Vec<_> = report
+            .fields
+            .iter()
+            .map(|f| (f.path.as_str(), f.decision, f.took_remote))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("body", MergeDecision::TookRemote, true),
+                ("status", MergeDecision::Resolved, false),
+                ("title", MergeDecision::KeptLocal, false),
+            ]
+        );
+        assert_eq!(report.overwritten().count(), 0);
+        assert_eq!(doc.get_field(&"status".to_string()), Some(&json!("done")));
+    }
+
+    #[test]
+    fn test_policy_guards_writes_and_redacts_reads() {
+        use crate::sync::policy::tests::PathPolicy;
+
+        let policy = PathPolicy {
+            read_only: vec!["owner"],
+            hidden: vec!["user.ssn"],
+        };
+
+        let mut remote = Document::new("doc1".to_string());
+        remote.set_field("title".to_string(), json!("T"), 1, "c2".to_string());
+        remote.set_field(
+            "user".to_string(),
+            json!({ "name": "Ann", "ssn": "123" }),
+            1,
+            "c2".to_string(),
+        );
+        remote.delete_field("user.ssn".to_string(), 2, "c2".to_string());
+        remote.set_field("owner".to_string(), json!("c2"), 1, "c2".to_string());
+        let delta = compute_delta(&Document::new("doc1".to_string()), &remote);
+
+        // One forbidden field rejects the whole delta
+        let mut doc = Document::new("doc1".to_string());
+        let result = delta.apply_to_with_policy(&mut doc, &policy);
+        assert!(matches!(result, Err(SyncError::PermissionDenied(_))));
+        assert!(doc.is_empty());
+
+        let redacted = delta.redact(&policy);
+        assert_eq!(
+            redacted
+                .fields
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+            vec!["owner", "title", "user"]
+        );
+        assert_eq!(redacted.fields["user"].value, json!({ "name": "Ann" }));
+        assert_eq!(redacted.new_version, delta.new_version);
+
+        // Writing "user" would overwrite the hidden "user.ssn"
+        assert!(redacted.check_writes(&policy).is_err());
+        let mut writable = redacted.clone();
+        writable.fields.retain(|path, _| path == "title");
+        writable.apply_to_with_policy(&mut doc, &policy).unwrap();
+        assert_eq!(doc.to_json(), json!({ "title": "T" }));
+    }
+
+    #[test]
+    fn test_redacted_delta_merged_back_keeps_hidden_parts() {
+        use crate::sync::policy::tests::PathPolicy;
+
+        let policy = PathPolicy {
+            read_only: vec![],
+            hidden: vec!["user.ssn"],
+        };
+
+        let mut source = Document::new("doc1".to_string());
+        source.set_field(
+            "user".to_string(),
+            json!({ "name": "Ann", "ssn": "123" }),
+            1,
+            "c2".to_string(),
+        );
+        let delta = compute_delta(&Document::new("doc1".to_string()), &source);
+        let redacted = delta.redact(&policy);
+        assert!(redacted.fields["user"].is_redacted());
+        assert!(!delta.fields["user"].is_redacted());
+
+        // The reader's copy ties with the full write on timestamp
+        let mut reader = Document::new("doc1".to_string());
+        redacted.apply_to(&mut reader).unwrap();
+        assert_eq!(reader.to_json(), json!({ "user": { "name": "Ann" } }));
+
+        let full = json!({ "user": { "name": "Ann", "ssn": "123" } });
+        redacted.apply_to(&mut source).unwrap();
+        source.merge(&reader);
+        assert_eq!(source.to_json(), full);
+
+        // The full value replaces the redacted one
+        reader.merge(&source);
+        assert_eq!(reader.to_json(), full);
+    }
+
+    #[test]
+    fn test_delta_tombstones_missing_fields() {
+        let mut old = Document::new("doc1".to_string());
+        old.set_field(
+            "title".to_string(),
+            json!("Hello"),
+            1,
+            "client1".to_string(),
+        );
+        old.version.update(&"client1".to_string(), 1);
+
+        // A document that lost the field without recording a delete
+        let mut new = Document::new("doc1".to_string());
+        new.version.update(&"client1".to_string(), 2);
+
+        let delta = compute_delta(&old, &new);
+        assert_eq!(delta.deleted_paths().collect::<Vec<_>>(), vec!["title"]);
+        assert_eq!(delta.base_version, old.version);
+        assert_eq!(delta.new_version, new.version);
+    }
+
+    #[test]
+    fn test_delta_reads_single_version_json() {
+        // Deltas logged before base versions existed
+        let json = r#"{"document_id":"doc1","fields":{},"version":{"clocks":{"client1":3}}}"#;
+        let delta: Delta = serde_json::from_str(json).unwrap();
+
+        assert_eq!(delta.new_version.get(&"client1".to_string()), 3);
+        assert_eq!(delta.base_version, VectorClock::new());
+    }
 }
@@ -0,0 +1,366 @@
+//! Hybrid logical clock for LWW timestamps
+//!
+//! A plain counter per client loses writes: a client that has been offline
+//! or simply writes less often keeps a low counter, and every write it
+//! makes loses to whatever the busiest client wrote last. A hybrid logical
+//! clock (Kulkarni et al., 2014) instead follows wall-clock time, and falls
+//! back to a logical counter when the wall clock stands still or lags
+//! behind a timestamp already seen. Writes are then ordered by when they
+//! happened, while causally later writes still always win.
+//!
+//! # Encoding
+//!
+//! An HLC value fits in `Timestamp::clock` unchanged: the upper 48 bits
+//! hold milliseconds since the Unix epoch and the lower 16 bits the logical
+//! counter. Comparing two values as integers compares them as HLC values,
+//! and plain counters from older clients read as very old HLC values.
+
+use crate::error::{Result, SyncError};
+use crate::sync::Timestamp;
+use crate::ClientID;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+use js_sys::Date;
+
+/// Bits of `Timestamp::clock` holding the logical counter
+pub const LOGICAL_BITS: u32 = 16;
+
+/// Default limit on how far ahead of local time a remote timestamp may be
+pub const DEFAULT_MAX_DRIFT_MS: u64 = 60_000;
+
+/// Source of wall-clock time, in milliseconds since the Unix epoch
+pub trait TimeSource {
+    /// Current time in milliseconds
+    fn now_millis(&self) -> u64;
+}
+
+/// The system clock (`Date.now()` in the browser)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now_millis(&self) -> u64 {
+        #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+
+        #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+        let now = Date::now() as u64;
+
+        now
+    }
+}
+
+/// A clock that only moves when told to, for tests
+///
+/// Clones share the same time, so a test can keep one handle and move the
+/// time of a clock it has handed off.
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::sync::{ManualTimeSource, TimeSource};
+///
+/// let time = ManualTimeSource::new(1_000);
+/// let handle = time.clone();
+///
+/// handle.advance(500);
+/// assert_eq!(time.now_millis(), 1_500);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ManualTimeSource {
+    millis: Arc<AtomicU64>,
+}
+
+impl ManualTimeSource {
+    /// Create a source frozen at `millis`
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: Arc::new(AtomicU64::new(millis)),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Move the current time forward
+    pub fn advance(&self, millis: u64) {
+        self.millis.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+/// What to do with a remote timestamp too far ahead of local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DriftPolicy {
+    /// Refuse it with `SyncError::InvalidTimestamp`
+    #[default]
+    Reject,
+
+    /// Accept it with its timestamp moved back to the drift limit, so one
+    /// bad clock cannot drag every replica into the future
+    ///
+    /// The next local write then wins over it (see
+    /// `Document::merge_with_clock`). Replicas that clamp at other times,
+    /// or do not clamp, hold the write under other timestamps until a
+    /// newer write replaces it.
+    Clamp,
+}
+
+/// Hybrid logical clock generating `Timestamp`s for one client
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::sync::{HybridLogicalClock, ManualTimeSource, Timestamp};
+///
+/// let time = ManualTimeSource::new(1_000);
+/// let mut clock = HybridLogicalClock::with_source("client1".to_string(), time.clone());
+///
+/// // The wall clock stands still: the logical counter breaks the tie
+/// let first = clock.now();
+/// let second = clock.now();
+/// assert!(second.is_newer_than(&first));
+/// assert_eq!(second.physical_millis(), 1_000);
+///
+/// // A remote timestamp from slightly ahead pulls the clock along
+/// let remote = Timestamp::from_hlc(1_200, 0, "client2".to_string());
+/// clock.observe(&remote).unwrap();
+/// assert!(clock.now().is_newer_than(&remote));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HybridLogicalClock<S: TimeSource = SystemTimeSource> {
+    /// Client the timestamps are issued for
+    client_id: ClientID,
+
+    /// Latest value issued or observed
+    last: u64,
+
+    /// Limit on how far ahead of local time remote timestamps may be (ms)
+    max_drift: u64,
+
+    /// What to do with remote timestamps beyond the limit
+    policy: DriftPolicy,
+
+    /// Wall-clock time
+    source: S,
+}
+
+impl HybridLogicalClock<SystemTimeSource> {
+    /// Create a clock following the system time
+    pub fn new(client_id: ClientID) -> Self {
+        Self::with_source(client_id, SystemTimeSource)
+    }
+}
+
+impl<S: TimeSource> HybridLogicalClock<S> {
+    /// Create a clock following the given time source
+    pub fn with_source(client_id: ClientID, source: S) -> Self {
+        Self {
+            client_id,
+            last: 0,
+            max_drift: DEFAULT_MAX_DRIFT_MS,
+            policy: DriftPolicy::default(),
+            source,
+        }
+    }
+
+    /// Set how far ahead of local time a remote timestamp may be
+    pub fn with_max_drift(mut self, max_drift_ms: u64) -> Self {
+        self.max_drift = max_drift_ms;
+        self
+    }
+
+    /// Set what happens to remote timestamps beyond the drift limit
+    pub fn with_drift_policy(mut self, policy: DriftPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Client the timestamps are issued for
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Latest value issued or observed, as stored in `Timestamp::clock`
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+
+    /// Issue a timestamp for a local write
+    ///
+    /// Every timestamp is newer than all timestamps issued or observed
+    /// before it.
+    pub fn now(&mut self) -> Timestamp {
+        let physical = self.source.now_millis() << LOGICAL_BITS;
+        self.last = physical.max(self.last.saturating_add(1));
+
+        Timestamp::new(self.last, self.client_id.clone())
+    }
+
+    /// Check a remote timestamp against the drift limit, without observing it
+    ///
+    /// # Errors
+    ///
+    /// Returns `SyncError::InvalidTimestamp` if the timestamp is too far
+    /// ahead of local time and the policy is `DriftPolicy::Reject`
+    pub fn check(&self, remote: &Timestamp) -> Result<()> {
+        let now = self.source.now_millis();
+        let ahead = remote.physical_millis().saturating_sub(now);
+
+        if ahead > self.max_drift && self.policy == DriftPolicy::Reject {
+            return Err(SyncError::InvalidTimestamp(format!(
+                "{}@{} is {} ms ahead of local time (limit {} ms)",
+                remote.client_id, remote.clock, ahead, self.max_drift
+            )));
+        }
+        Ok(())
+    }
+
+    /// Observe a remote timestamp, so later local writes are newer
+    ///
+    /// # Errors
+    ///
+    /// Same as [`HybridLogicalClock::check`]; the clock is unchanged then.
+    pub fn observe(&mut self, remote: &Timestamp) -> Result<()> {
+        self.check(remote)?;
+
+        self.last = self.last.max(remote.clock.min(self.limit()));
+        Ok(())
+    }
+
+    /// Timestamp to merge a remote write with
+    ///
+    /// Under `DriftPolicy::Clamp`, a timestamp beyond the drift limit is
+    /// moved back to the limit. Otherwise it is returned unchanged.
+    pub fn clamp(&self, remote: &Timestamp) -> Timestamp {
+        let limit = self.limit();
+        if self.policy == DriftPolicy::Clamp && remote.clock > limit {
+            Timestamp::new(limit, remote.client_id.clone())
+        } else {
+            remote.clone()
+        }
+    }
+
+    /// Highest clock value within the drift limit
+    fn limit(&self) -> u64 {
+        let millis = self.source.now_millis().saturating_add(self.max_drift);
+        if millis >= u64::MAX >> LOGICAL_BITS {
+            return u64::MAX;
+        }
+        ((millis + 1) << LOGICAL_BITS) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_at(millis: u64) -> (HybridLogicalClock<ManualTimeSource>, ManualTimeSource) {
+        let time = ManualTimeSource::new(millis);
+        let clock = HybridLogicalClock::with_source("client1".to_string(), time.clone());
+        (clock, time)
+    }
+
+    #[test]
+    fn test_follows_wall_clock() {
+        let (mut clock, time) = clock_at(1_000);
+
+        let first = clock.now();
+        assert_eq!((first.physical_millis(), first.logical()), (1_000, 0));
+
+        let second = clock.now();
+        assert_eq!((second.physical_millis(), second.logical()), (1_000, 1));
+
+        // Time moves on: the counter starts over
+        time.advance(5);
+        let third = clock.now();
+        assert_eq!((third.physical_millis(), third.logical()), (1_005, 0));
+
+        // Time goes backwards: timestamps keep increasing
+        time.set(900);
+        assert!(clock.now().is_newer_than(&third));
+    }
+
+    #[test]
+    fn test_observe_pulls_clock_forward() {
+        let (mut clock, _) = clock_at(1_000);
+
+        let remote = Timestamp::from_hlc(1_500, 7, "client2".to_string());
+        clock.observe(&remote).unwrap();
+
+        let local = clock.now();
+        assert!(local.is_newer_than(&remote));
+        assert_eq!((local.physical_millis(), local.logical()), (1_500, 8));
+
+        // Plain counters from older clients read as ancient
+        clock
+            .observe(&Timestamp::new(42, "client3".to_string()))
+            .unwrap();
+        assert!(clock.now().is_newer_than(&local));
+    }
+
+    #[test]
+    fn test_rejects_timestamps_from_the_future() {
+        let (mut clock, time) = clock_at(1_000);
+        let clock_before = clock.last();
+
+        let remote = Timestamp::from_hlc(1_000 + DEFAULT_MAX_DRIFT_MS + 1, 0, "c2".to_string());
+        assert!(matches!(
+            clock.observe(&remote),
+            Err(SyncError::InvalidTimestamp(_))
+        ));
+        assert_eq!(clock.last(), clock_before);
+
+        // Once local time catches up it is fine
+        time.advance(1);
+        assert!(clock.observe(&remote).is_ok());
+    }
+
+    #[test]
+    fn test_clamps_timestamps_from_the_future() {
+        let (clock, _) = clock_at(1_000);
+        let mut clock = clock
+            .with_max_drift(100)
+            .with_drift_policy(DriftPolicy::Clamp);
+
+        let remote = Timestamp::from_hlc(1_000_000, 0, "client2".to_string());
+        clock.observe(&remote).unwrap();
+
+        // The local clock and the remote write both stay within the limit
+        let clamped = clock.clamp(&remote);
+        assert_eq!(clamped.physical_millis(), 1_100);
+        assert_eq!(clamped.client_id, "client2");
+        let local = clock.now();
+        assert!(local.physical_millis() <= 1_101);
+        assert!(local.is_newer_than(&clamped));
+
+        // Timestamps within the limit are left alone
+        let near = Timestamp::from_hlc(1_050, 0, "client2".to_string());
+        assert_eq!(clock.clamp(&near), near);
+    }
+
+    #[test]
+    fn test_unbounded_drift_does_not_overflow() {
+        let (clock, _) = clock_at(1_000);
+        let mut clock = clock.with_max_drift(u64::MAX);
+
+        let remote = Timestamp::new(u64::MAX, "client2".to_string());
+        clock.observe(&remote).unwrap();
+        assert_eq!(clock.clamp(&remote), remote);
+        assert_eq!(clock.now().clock, u64::MAX);
+    }
+}
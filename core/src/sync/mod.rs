@@ -3,15 +3,33 @@
 //! This module contains the core synchronization algorithms:
 //! - Vector clocks for causality tracking
 //! - Timestamps for LWW conflict resolution
+//! - Hybrid logical clocks generating those timestamps
 //! - LWW merge algorithm
+//! - Multi-value registers keeping concurrent writes
 //! - Delta computation
+//! - Causal delivery of out-of-order deltas
+//! - Merge reports and custom conflict resolvers
+//! - Field-level access policies for writes and redaction
 
+pub mod causal;
+pub mod conflict;
 pub mod delta;
+pub mod hlc;
 pub mod lww;
+pub mod mv_register;
+pub mod policy;
 pub mod vector_clock;
 
-pub use delta::{apply_delta, compute_delta, merge_deltas, Delta};
+pub use causal::{CausalBuffer, MissingRange};
+pub use conflict::{ConflictResolver, FieldMerge, MergeDecision, MergeReport, Winner};
+pub use delta::{
+    apply_delta, apply_delta_with_report, apply_delta_with_resolver, compute_delta, merge_deltas,
+    Delta,
+};
+pub use hlc::{DriftPolicy, HybridLogicalClock, ManualTimeSource, SystemTimeSource, TimeSource};
 pub use lww::LWWField;
+pub use mv_register::{Dot, MVEntry, MVRegister};
+pub use policy::{Access, FieldPolicy};
 pub use vector_clock::VectorClock;
 
 use crate::ClientID;
@@ -37,6 +55,28 @@ impl Timestamp {
         Self { clock, client_id }
     }
 
+    /// Create a hybrid logical clock timestamp
+    ///
+    /// See [`hlc`] for how the two parts are packed into `clock`.
+    pub fn from_hlc(physical_millis: u64, logical: u16, client_id: ClientID) -> Self {
+        Self::new(
+            (physical_millis << hlc::LOGICAL_BITS) | logical as u64,
+            client_id,
+        )
+    }
+
+    /// Wall-clock part of a hybrid logical clock timestamp (ms since epoch)
+    ///
+    /// Zero for timestamps from a plain counter.
+    pub fn physical_millis(&self) -> u64 {
+        self.clock >> hlc::LOGICAL_BITS
+    }
+
+    /// Logical counter part of a hybrid logical clock timestamp
+    pub fn logical(&self) -> u16 {
+        (self.clock & ((1 << hlc::LOGICAL_BITS) - 1)) as u16
+    }
+
     /// Compare two timestamps for LWW conflict resolution
     ///
     /// Returns:
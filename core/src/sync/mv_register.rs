@@ -0,0 +1,278 @@
+//! Multi-value register
+//!
+//! LWW picks one winner among concurrent writes and drops the rest without
+//! telling anyone. A multi-value register keeps every value written
+//! concurrently, so the application can show the conflict and let a user
+//! pick. A write that has seen all current values replaces them, which
+//! collapses the conflict again.
+//!
+//! Each write is tagged with a dot, the writing client plus a per-client
+//! counter. The register also keeps a causal context, a vector clock of all
+//! dots it has seen. When merging, a value is dropped if the other replica
+//! has seen its dot but no longer holds it: it was overwritten there.
+
+use crate::sync::{Timestamp, VectorClock};
+use crate::ClientID;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Unique tag of one write: the client plus a counter per client
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Dot {
+    /// Client that made the write
+    pub client_id: ClientID,
+
+    /// Number of writes the client made to the register, this one included
+    pub counter: u64,
+}
+
+impl Dot {
+    /// Create a new dot
+    pub fn new(client_id: ClientID, counter: u64) -> Self {
+        Self { client_id, counter }
+    }
+}
+
+/// A value held by the register, with the dot of the write
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MVEntry {
+    /// Write that produced the value
+    pub dot: Dot,
+
+    /// Value written
+    pub value: JsonValue,
+
+    /// Timestamp of the write, if it was made with one
+    ///
+    /// Not used for merging (dots decide), but lets a replica observe the
+    /// clocks of remote writes (see `Document::merge_with_clock`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+}
+
+/// Register keeping all concurrently written values
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::sync::MVRegister;
+/// use serde_json::json;
+///
+/// let mut alice = MVRegister::new();
+/// let mut bob = MVRegister::new();
+///
+/// // Concurrent writes: both survive the merge
+/// alice.set(json!("alice"), &"alice".to_string());
+/// bob.set(json!("bob"), &"bob".to_string());
+/// alice.merge(&bob);
+/// assert_eq!(alice.values().collect::<Vec<_>>(), vec![&json!("alice"), &json!("bob")]);
+///
+/// // A write that has seen both resolves the conflict
+/// alice.set(json!("carol"), &"alice".to_string());
+/// bob.merge(&alice);
+/// assert_eq!(bob.values().collect::<Vec<_>>(), vec![&json!("carol")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MVRegister {
+    /// Current values, sorted by dot
+    entries: Vec<MVEntry>,
+
+    /// All dots seen, including those of overwritten values
+    context: VectorClock,
+}
+
+impl MVRegister {
+    /// Create an empty register
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild a register from its values and causal context
+    pub fn from_parts(mut entries: Vec<MVEntry>, context: VectorClock) -> Self {
+        entries.sort_by(|a, b| a.dot.cmp(&b.dot));
+        entries.dedup_by(|a, b| a.dot == b.dot);
+        Self { entries, context }
+    }
+
+    /// Write a value, replacing all values seen so far
+    ///
+    /// Returns the dot of the write.
+    pub fn set(&mut self, value: JsonValue, client_id: &ClientID) -> Dot {
+        self.write(value, client_id, None)
+    }
+
+    /// Write a value stamped with `timestamp`, as its client
+    ///
+    /// Same as `set`; the timestamp is kept with the value.
+    pub fn set_at(&mut self, value: JsonValue, timestamp: Timestamp) -> Dot {
+        let client_id = timestamp.client_id.clone();
+        self.write(value, &client_id, Some(timestamp))
+    }
+
+    /// Remove all values seen so far
+    ///
+    /// Values written concurrently with the clear survive it.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Merge another replica of the register
+    ///
+    /// Keeps the values both replicas hold, plus those one replica holds
+    /// that the other has not seen yet. Returns true if the values changed.
+    pub fn merge(&mut self, other: &MVRegister) -> bool {
+        let mut entries: Vec<MVEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| other.holds(&entry.dot) || !other.has_seen(&entry.dot))
+            .cloned()
+            .collect();
+
+        for entry in &other.entries {
+            if !self.holds(&entry.dot) && !self.has_seen(&entry.dot) {
+                entries.push(entry.clone());
+            }
+        }
+        entries.sort_by(|a, b| a.dot.cmp(&b.dot));
+
+        self.context.merge(&other.context);
+
+        let changed = entries != self.entries;
+        self.entries = entries;
+        changed
+    }
+
+    /// Iterate over the current values, ordered by dot
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+        self.entries.iter().map(|entry| &entry.value)
+    }
+
+    /// Current values with their dots
+    pub fn entries(&self) -> &[MVEntry] {
+        &self.entries
+    }
+
+    /// One of the current values, picked the same way on every replica
+    ///
+    /// Takes the value with the highest counter, then the highest client
+    /// ID. Meant for views that can only show one value; use `values` to
+    /// see the conflict.
+    pub fn value(&self) -> Option<&JsonValue> {
+        self.entries
+            .iter()
+            .max_by(|a, b| {
+                (a.dot.counter, &a.dot.client_id).cmp(&(b.dot.counter, &b.dot.client_id))
+            })
+            .map(|entry| &entry.value)
+    }
+
+    /// Check if concurrent writes left more than one value
+    pub fn is_conflicted(&self) -> bool {
+        self.entries.len() > 1
+    }
+
+    /// Check if the register holds no value (never written, or cleared)
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All dots seen by this replica
+    pub fn context(&self) -> &VectorClock {
+        &self.context
+    }
+
+    fn write(
+        &mut self,
+        value: JsonValue,
+        client_id: &ClientID,
+        timestamp: Option<Timestamp>,
+    ) -> Dot {
+        self.context.tick(client_id);
+        let dot = Dot::new(client_id.clone(), self.context.get(client_id));
+
+        self.entries = vec![MVEntry {
+            dot: dot.clone(),
+            value,
+            timestamp,
+        }];
+        dot
+    }
+
+    fn holds(&self, dot: &Dot) -> bool {
+        self.entries.iter().any(|entry| &entry.dot == dot)
+    }
+
+    fn has_seen(&self, dot: &Dot) -> bool {
+        self.context.get(&dot.client_id) >= dot.counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(register: &MVRegister) -> Vec<JsonValue> {
+        register.values().cloned().collect()
+    }
+
+    #[test]
+    fn test_sequential_writes_overwrite() {
+        let mut a = MVRegister::new();
+        let mut b = MVRegister::new();
+
+        a.set(json!(1), &"a".to_string());
+        b.merge(&a);
+        b.set(json!(2), &"b".to_string());
+
+        assert!(a.merge(&b));
+        assert_eq!(values(&a), vec![json!(2)]);
+        assert!(!a.is_conflicted());
+
+        // Merging the stale replica back changes nothing
+        let stale = a.clone();
+        assert!(!b.merge(&stale));
+    }
+
+    #[test]
+    fn test_concurrent_writes_are_kept() {
+        let mut a = MVRegister::new();
+        let mut b = MVRegister::new();
+        let mut c = MVRegister::new();
+
+        a.set(json!("a"), &"a".to_string());
+        b.set(json!("b"), &"b".to_string());
+        c.set(json!("c"), &"c".to_string());
+
+        // Merge in different orders: all replicas agree
+        let mut abc = a.clone();
+        abc.merge(&b);
+        abc.merge(&c);
+        let mut cba = c.clone();
+        cba.merge(&b);
+        cba.merge(&a);
+
+        assert_eq!(abc, cba);
+        assert_eq!(values(&abc), vec![json!("a"), json!("b"), json!("c")]);
+        assert_eq!(abc.value(), cba.value());
+    }
+
+    #[test]
+    fn test_clear_keeps_concurrent_writes() {
+        let mut a = MVRegister::new();
+        a.set(json!("old"), &"a".to_string());
+
+        let mut b = a.clone();
+        a.clear();
+        b.set(json!("new"), &"b".to_string());
+
+        a.merge(&b);
+        assert_eq!(values(&a), vec![json!("new")]);
+
+        // The cleared value does not come back from a stale replica
+        let mut stale = MVRegister::new();
+        stale.set(json!("old"), &"a".to_string());
+        a.merge(&stale);
+        assert_eq!(values(&a), vec![json!("new")]);
+    }
+}
@@ -0,0 +1,160 @@
+//! Field-level access policies
+//!
+//! A [`FieldPolicy`] says which fields of a document a client may read or
+//! write. The sync path enforces it in two places:
+//! - incoming deltas are checked before anything is applied, and a write to
+//!   a forbidden field rejects the whole delta with
+//!   `SyncError::PermissionDenied` (see `Delta::apply_to_with_policy`);
+//! - outgoing deltas and JSON views are redacted: unreadable fields are
+//!   left out, and unreadable parts of readable objects are cut out of
+//!   their values (see `Delta::redact` and `Document::to_json_redacted`).
+//!   Cut-down values in deltas are marked redacted, so merging them back
+//!   never replaces the full value.
+//!
+//! The binary protocol implements the trait for the `Permissions` message
+//! of `auth.proto`.
+
+use crate::path::{self, PathSegment};
+use serde_json::Value as JsonValue;
+
+/// Kind of access to a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// Receive the field's value
+    Read,
+
+    /// Change or delete the field
+    Write,
+}
+
+/// Decides which fields a client may read or write
+///
+/// Paths are canonical. A field's value is its whole subtree, so `allows`
+/// answers for the subtree; `allows_node` only for the rule covering the
+/// field itself, and lets redaction keep the readable parts of a field
+/// whose subtree is partly off limits.
+pub trait FieldPolicy {
+    /// Check if `access` to the field and everything below it is allowed
+    fn allows(&self, document_id: &str, field_path: &str, access: Access) -> bool;
+
+    /// Check if the rule covering the field allows `access`, ignoring
+    /// rules on its descendants
+    fn allows_node(&self, document_id: &str, field_path: &str, access: Access) -> bool {
+        self.allows(document_id, field_path, access)
+    }
+}
+
+/// Cut the unreadable parts out of a field's value
+///
+/// Returns None if the field itself is unreadable. Unreadable object keys
+/// are removed; unreadable array items become `null`, so the readable ones
+/// keep their index.
+pub(crate) fn redact_value<P: FieldPolicy + ?Sized>(
+    policy: &P,
+    document_id: &str,
+    field_path: &str,
+    value: &JsonValue,
+) -> Option<JsonValue> {
+    if policy.allows(document_id, field_path, Access::Read) {
+        return Some(value.clone());
+    }
+    if !policy.allows_node(document_id, field_path, Access::Read) {
+        return None;
+    }
+
+    let child = |segment: PathSegment| {
+        let mut segments = path::parse(field_path);
+        segments.push(segment);
+        path::format(&segments)
+    };
+
+    let redacted = match value {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .filter_map(|(key, item)| {
+                    let item_path = child(PathSegment::Key(key.clone()));
+                    redact_value(policy, document_id, &item_path, item)
+                        .map(|item| (key.clone(), item))
+                })
+                .collect(),
+        ),
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let item_path = child(PathSegment::Index(index));
+                    redact_value(policy, document_id, &item_path, item).unwrap_or(JsonValue::Null)
+                })
+                .collect(),
+        ),
+        leaf => leaf.clone(),
+    };
+    Some(redacted)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Read-only paths and hidden paths, with their subtrees
+    pub(crate) struct PathPolicy {
+        pub read_only: Vec<&'static str>,
+        pub hidden: Vec<&'static str>,
+    }
+
+    impl PathPolicy {
+        fn rule(&self, field_path: &str, access: Access) -> bool {
+            let covers = |rule: &&str| *rule == field_path || path::is_ancestor(rule, field_path);
+            !self.hidden.iter().any(covers)
+                && (access == Access::Read || !self.read_only.iter().any(covers))
+        }
+
+        fn below(&self, field_path: &str, access: Access) -> bool {
+            let below = |rule: &&str| path::is_ancestor(field_path, rule);
+            self.hidden.iter().any(below)
+                || (access == Access::Write && self.read_only.iter().any(below))
+        }
+    }
+
+    impl FieldPolicy for PathPolicy {
+        fn allows(&self, _: &str, field_path: &str, access: Access) -> bool {
+            self.rule(field_path, access) && !self.below(field_path, access)
+        }
+
+        fn allows_node(&self, _: &str, field_path: &str, access: Access) -> bool {
+            self.rule(field_path, access)
+        }
+    }
+
+    #[test]
+    fn test_redact_value() {
+        let policy = PathPolicy {
+            read_only: vec![],
+            hidden: vec!["user.ssn", "user.cards[1]", "secret"],
+        };
+        let user = json!({
+            "name": "Ann",
+            "ssn": "123",
+            "cards": ["visa", "amex", "mc"]
+        });
+
+        assert_eq!(
+            redact_value(&policy, "doc-1", "user", &user),
+            Some(json!({ "name": "Ann", "cards": ["visa", null, "mc"] }))
+        );
+        assert_eq!(
+            redact_value(&policy, "doc-1", "user.ssn", &json!("123")),
+            None
+        );
+        assert_eq!(
+            redact_value(&policy, "doc-1", "secret", &json!({ "a": 1 })),
+            None
+        );
+        assert_eq!(
+            redact_value(&policy, "doc-1", "title", &json!("T")),
+            Some(json!("T"))
+        );
+    }
+}
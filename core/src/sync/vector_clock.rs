@@ -134,6 +134,16 @@ impl VectorClock {
     pub fn happened_before(&self, other: &VectorClock) -> bool {
         self.compare(other) == Ordering::Less
     }
+
+    /// Check if self has seen every event recorded in other (other <= self)
+    ///
+    /// Unlike `compare`, this never confuses concurrent clocks with equal ones.
+    pub fn dominates(&self, other: &VectorClock) -> bool {
+        other
+            .clocks
+            .iter()
+            .all(|(client_id, &clock)| self.get(client_id) >= clock)
+    }
 }
 
 impl Default for VectorClock {
@@ -240,4 +250,23 @@ mod tests {
         assert!(clock_merged.compare(&clock_a) != Ordering::Less);
         assert!(clock_merged.compare(&clock_b) != Ordering::Less);
     }
+
+    #[test]
+    fn test_dominates() {
+        let mut clock_a = VectorClock::new();
+        clock_a.tick(&"c1".to_string());
+
+        let mut clock_b = VectorClock::new();
+        clock_b.tick(&"c2".to_string());
+
+        // Concurrent clocks do not dominate each other
+        assert!(!clock_a.dominates(&clock_b));
+        assert!(!clock_b.dominates(&clock_a));
+
+        let mut merged = clock_a.clone();
+        merged.merge(&clock_b);
+        assert!(merged.dominates(&clock_a));
+        assert!(merged.dominates(&merged.clone()));
+        assert!(clock_a.dominates(&VectorClock::new()));
+    }
 }
@@ -0,0 +1,692 @@
+//! Undo/redo for local edits
+//!
+//! Undoing a CRDT edit cannot rewind history: other replicas may already
+//! have built on it. Instead every local edit is recorded together with
+//! what it replaced, and undoing it makes a new edit that puts that back.
+//! The new edit is synced like any other.
+//!
+//! Only this replica's own edits are recorded, and reverting one leaves
+//! remote edits alone:
+//! - A field write is only reverted while it is still the field's current
+//!   value. If another client has written the field, or a field below it,
+//!   since, the undo skips it: putting the old object back would drop the
+//!   remote edit to its child.
+//! - Inserted text is reverted by deleting exactly the characters that were
+//!   inserted, so text typed in between by others stays.
+//! - Deleted text is reverted by inserting it again right where its
+//!   tombstones are, however the text around them has changed.
+//!
+//! Edits made within a capture window of each other are grouped and undone
+//! as one step, so undo removes a burst of typing rather than a character.
+
+use crate::document::{descendants, Document, Field, FieldMode};
+use crate::path;
+use crate::sync::Timestamp;
+use crate::FieldPath;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+
+#[cfg(feature = "text-crdt")]
+use crate::crdt::text_fugue::{FugueOp, FugueText, NodeIdRange, TextError};
+
+/// Default capture window in milliseconds
+pub const DEFAULT_CAPTURE_WINDOW: u64 = 500;
+
+/// A replica whose local edits can be reverted
+pub trait Undoable {
+    /// A recorded local edit, holding what is needed to revert it
+    type Change: Clone + std::fmt::Debug;
+
+    /// Operation a revert produces, to be sent to other replicas
+    type Op;
+
+    /// Revert a change
+    ///
+    /// `clock` is the Lamport clock value for writes that need one and must
+    /// be newer than any write this replica has made; replicas with their
+    /// own clock ignore it.
+    ///
+    /// Returns the changes the revert made, so that they can be reverted in
+    /// turn on redo, plus its operation. Returns None if there is nothing
+    /// left to revert, for example because a remote edit replaced it.
+    fn revert(
+        &mut self,
+        change: &Self::Change,
+        clock: u64,
+    ) -> Option<(Vec<Self::Change>, Self::Op)>;
+
+    /// Update a recorded change after `reverted` was reverted by `made`
+    ///
+    /// Lets a replica follow content the revert re-created under new IDs.
+    fn rebase(_change: &mut Self::Change, _reverted: &Self::Change, _made: &[Self::Change]) {}
+
+    /// Fold a later change into an earlier one in the same undo step
+    ///
+    /// Returns true if `later` was folded in and need not be kept.
+    fn coalesce(_earlier: &mut Self::Change, _later: &Self::Change) -> bool {
+        false
+    }
+}
+
+/// Undo and redo stacks of local edits to one replica
+///
+/// # Example
+///
+/// ```rust
+/// use synckit_core::undo::UndoManager;
+/// use synckit_core::Document;
+/// use serde_json::json;
+///
+/// let mut doc = Document::new("doc-1".to_string());
+/// let mut undo = UndoManager::new(500);
+///
+/// undo.set_field(&mut doc, "title", json!("Draft"), 1, "client1", 0);
+/// undo.set_field(&mut doc, "title", json!("Final"), 2, "client1", 1000);
+///
+/// undo.undo(&mut doc, 3);
+/// assert_eq!(doc.get_value("title"), Some(json!("Draft")));
+///
+/// undo.redo(&mut doc, 4);
+/// assert_eq!(doc.get_value("title"), Some(json!("Final")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct UndoManager<T: Undoable> {
+    /// Steps to undo, most recent last
+    undo_stack: Vec<Vec<T::Change>>,
+
+    /// Steps to redo, most recently undone last
+    redo_stack: Vec<Vec<T::Change>>,
+
+    /// Edits closer together than this (in ms) form one step
+    capture_window: u64,
+
+    /// Time of the last recorded edit, while its step is still open
+    last_capture: Option<u64>,
+}
+
+impl<T: Undoable> UndoManager<T> {
+    /// Create a manager grouping edits within `capture_window` milliseconds
+    pub fn new(capture_window: u64) -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            capture_window,
+            last_capture: None,
+        }
+    }
+
+    /// Record a local edit made at `now` (milliseconds)
+    ///
+    /// Recording a new edit clears the redo stack.
+    pub fn record(&mut self, change: T::Change, now: u64) {
+        self.redo_stack.clear();
+
+        let open = self
+            .last_capture
+            .is_some_and(|last| now.saturating_sub(last) <= self.capture_window);
+        self.last_capture = Some(now);
+
+        match self.undo_stack.last_mut() {
+            Some(step) if open => {
+                if !step.iter_mut().any(|earlier| T::coalesce(earlier, &change)) {
+                    step.push(change);
+                }
+            }
+            _ => self.undo_stack.push(vec![change]),
+        }
+    }
+
+    /// Close the current step, so the next edit starts a new one
+    pub fn stop_capturing(&mut self) {
+        self.last_capture = None;
+    }
+
+    /// Undo the most recent step that still has an effect
+    ///
+    /// Steps that remote edits have completely replaced are dropped.
+    /// Returns the operations to send to other replicas, which are empty if
+    /// there was nothing to undo.
+    pub fn undo(&mut self, target: &mut T, clock: u64) -> Vec<T::Op> {
+        self.last_capture = None;
+        Self::revert_step(&mut self.undo_stack, &mut self.redo_stack, target, clock)
+    }
+
+    /// Redo the most recently undone step
+    ///
+    /// Returns the operations to send to other replicas.
+    pub fn redo(&mut self, target: &mut T, clock: u64) -> Vec<T::Op> {
+        self.last_capture = None;
+        Self::revert_step(&mut self.redo_stack, &mut self.undo_stack, target, clock)
+    }
+
+    /// Check whether there is a step to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Check whether there is a step to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Steps to undo, most recent last
+    pub fn undo_stack(&self) -> &[Vec<T::Change>] {
+        &self.undo_stack
+    }
+
+    /// Steps to redo, most recently undone last
+    pub fn redo_stack(&self) -> &[Vec<T::Change>] {
+        &self.redo_stack
+    }
+
+    /// Forget all recorded edits
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_capture = None;
+    }
+
+    /// Revert the top step of `from` and push what that did onto `to`
+    fn revert_step(
+        from: &mut Vec<Vec<T::Change>>,
+        to: &mut Vec<Vec<T::Change>>,
+        target: &mut T,
+        clock: u64,
+    ) -> Vec<T::Op> {
+        while let Some(mut step) = from.pop() {
+            let mut changes = Vec::new();
+            let mut ops = Vec::new();
+
+            // Latest change first, so each one sees the state it produced
+            while let Some(change) = step.pop() {
+                let Some((made, op)) = target.revert(&change, clock) else {
+                    continue;
+                };
+
+                for other in step
+                    .iter_mut()
+                    .chain(from.iter_mut().flatten())
+                    .chain(to.iter_mut().flatten())
+                {
+                    T::rebase(other, &change, &made);
+                }
+                changes.extend(made);
+                ops.push(op);
+            }
+
+            if !ops.is_empty() {
+                to.push(changes);
+                return ops;
+            }
+        }
+
+        Vec::new()
+    }
+}
+
+impl<T: Undoable> Default for UndoManager<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPTURE_WINDOW)
+    }
+}
+
+/// A local write to a document field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Canonical path of the field
+    pub path: FieldPath,
+
+    /// Value at the path before the write; None if it had none
+    pub previous: Option<JsonValue>,
+
+    /// Timestamp of the write
+    pub written: Timestamp,
+}
+
+impl Undoable for Document {
+    type Change = FieldChange;
+
+    /// The field write to send, as taken by `Document::merge_field`
+    type Op = (FieldPath, Field);
+
+    fn revert(&mut self, change: &FieldChange, clock: u64) -> Option<(Vec<FieldChange>, Self::Op)> {
+        // Leave the field alone once another client has written it or a
+        // field below it
+        let current = self.fields.get(&change.path)?;
+        if current.timestamp.client_id != change.written.client_id {
+            return None;
+        }
+        let remote_child = descendants(&self.fields, &change.path).any(|(_, field)| {
+            field.timestamp.client_id != change.written.client_id
+                && field.timestamp.compare_lww(&change.written) == Ordering::Greater
+        });
+        if remote_child {
+            return None;
+        }
+
+        let timestamp = Timestamp::new(clock, change.written.client_id.clone());
+        let field = match &change.previous {
+            Some(value) => Field::new(value.clone(), timestamp.clone()),
+            None => Field::tombstone(timestamp.clone()),
+        };
+
+        let previous = self.get_value(&change.path);
+        if !self.merge_field(change.path.clone(), field.clone()) {
+            return None;
+        }
+
+        let reverted = FieldChange {
+            path: change.path.clone(),
+            previous,
+            written: timestamp,
+        };
+        Some((vec![reverted], (change.path.clone(), field)))
+    }
+
+    fn coalesce(earlier: &mut FieldChange, later: &FieldChange) -> bool {
+        if earlier.path != later.path {
+            return false;
+        }
+
+        // Undoing both goes back to what was there before the first
+        earlier.written = later.written.clone();
+        true
+    }
+}
+
+impl UndoManager<Document> {
+    /// Set a field and record the write
+    ///
+    /// Takes the same arguments as `Document::set_field`, plus the time of
+    /// the edit in milliseconds. Nothing is recorded if the write loses to
+    /// a newer value, or if the field is multi-value.
+    pub fn set_field(
+        &mut self,
+        doc: &mut Document,
+        field_path: &str,
+        value: JsonValue,
+        clock: u64,
+        client_id: &str,
+        now: u64,
+    ) {
+        let timestamp = Timestamp::new(clock, client_id.to_string());
+        self.write(doc, field_path, Field::new(value, timestamp), now);
+    }
+
+    /// Delete a field and record the delete
+    ///
+    /// Takes the same arguments as `Document::delete_field`, plus the time
+    /// of the edit in milliseconds.
+    pub fn delete_field(
+        &mut self,
+        doc: &mut Document,
+        field_path: &str,
+        clock: u64,
+        client_id: &str,
+        now: u64,
+    ) {
+        let timestamp = Timestamp::new(clock, client_id.to_string());
+        self.write(doc, field_path, Field::tombstone(timestamp), now);
+    }
+
+    fn write(&mut self, doc: &mut Document, field_path: &str, field: Field, now: u64) {
+        let path = path::normalize(field_path);
+
+        // Multi-value fields resolve conflicts themselves, undo skips them
+        if doc.field_mode(&path) == FieldMode::MultiValue {
+            doc.write_field(path, field);
+            return;
+        }
+        let previous = doc.get_value(&path);
+        let written = field.timestamp.clone();
+
+        if doc.merge_field(path.clone(), field) {
+            self.record(
+                FieldChange {
+                    path,
+                    previous,
+                    written,
+                },
+                now,
+            );
+        }
+    }
+}
+
+/// A local edit to a FugueText
+#[cfg(feature = "text-crdt")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextChange {
+    /// Characters this replica inserted
+    Inserted(Vec<NodeIdRange>),
+
+    /// A run of characters this replica deleted
+    Deleted {
+        /// The deleted characters
+        range: NodeIdRange,
+
+        /// Their text
+        text: String,
+    },
+}
+
+#[cfg(feature = "text-crdt")]
+impl TextChange {
+    /// Describe a local operation already applied to `text`
+    ///
+    /// A delete becomes one change per deleted run, so each run is put
+    /// back where it was on undo. An operation that changed nothing gives
+    /// no changes.
+    pub fn from_op(text: &FugueText, op: &FugueOp) -> Vec<Self> {
+        if op.is_empty() {
+            return Vec::new();
+        }
+
+        match op {
+            FugueOp::Insert(block) => {
+                let end = block.id.clock;
+                let start = end + 1 - block.len() as u64;
+                vec![TextChange::Inserted(vec![NodeIdRange::new(
+                    block.id.client_id.clone(),
+                    start,
+                    end,
+                )])]
+            }
+            FugueOp::Delete { ranges, .. } => ranges
+                .iter()
+                .map(|range| TextChange::Deleted {
+                    range: range.clone(),
+                    text: text.text_of(range),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "text-crdt")]
+impl Undoable for FugueText {
+    type Change = TextChange;
+    type Op = FugueOp;
+
+    fn revert(&mut self, change: &TextChange, _clock: u64) -> Option<(Vec<TextChange>, FugueOp)> {
+        let op = match change {
+            TextChange::Inserted(ranges) => self.delete_ranges(ranges),
+            TextChange::Deleted { range, text } => {
+                self.insert_before_char(&range.first(), text).ok()?
+            }
+        };
+
+        let made = TextChange::from_op(self, &op);
+        (!made.is_empty()).then_some((made, op))
+    }
+
+    fn rebase(change: &mut TextChange, reverted: &TextChange, made: &[TextChange]) {
+        // Deleted text came back as new characters: an insert of the old
+        // ones now covers the new ones too
+        let (
+            TextChange::Inserted(inserted),
+            TextChange::Deleted { range: old, .. },
+            [TextChange::Inserted(new)],
+        ) = (change, reverted, made)
+        else {
+            return;
+        };
+
+        // Characters map one to one unless some text was missing (gc)
+        if old.len() != new[0].len() {
+            return;
+        }
+
+        let mapped: Vec<NodeIdRange> = inserted
+            .iter()
+            .filter(|range| range.client_id == old.client_id)
+            .filter_map(|range| {
+                let (start, end) = (range.start.max(old.start), range.end.min(old.end));
+                (start <= end).then(|| {
+                    let from = new[0].start + (start - old.start);
+                    NodeIdRange::new(new[0].client_id.clone(), from, from + (end - start))
+                })
+            })
+            .collect();
+        inserted.extend(mapped);
+    }
+}
+
+#[cfg(feature = "text-crdt")]
+impl UndoManager<FugueText> {
+    /// Insert text and record the insert
+    ///
+    /// Same as `FugueText::insert`, plus the time of the edit in
+    /// milliseconds.
+    pub fn insert(
+        &mut self,
+        text: &mut FugueText,
+        position: usize,
+        content: &str,
+        now: u64,
+    ) -> Result<FugueOp, TextError> {
+        let op = text.insert(position, content)?;
+        self.record_op(text, &op, now);
+        Ok(op)
+    }
+
+    /// Delete text and record the delete
+    ///
+    /// Same as `FugueText::delete`, plus the time of the edit in
+    /// milliseconds.
+    pub fn delete(
+        &mut self,
+        text: &mut FugueText,
+        position: usize,
+        length: usize,
+        now: u64,
+    ) -> Result<FugueOp, TextError> {
+        let op = text.delete(position, length)?;
+        self.record_op(text, &op, now);
+        Ok(op)
+    }
+
+    /// Record a local operation already applied to `text`
+    pub fn record_op(&mut self, text: &FugueText, op: &FugueOp, now: u64) {
+        for change in TextChange::from_op(text, op) {
+            self.record(change, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_undo_redo_field() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut undo = UndoManager::new(500);
+
+        undo.set_field(&mut doc, "title", json!("Draft"), 1, "client1", 0);
+        undo.delete_field(&mut doc, "title", 2, "client1", 1000);
+        assert_eq!(doc.get_value("title"), None);
+
+        let ops = undo.undo(&mut doc, 3);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(doc.get_value("title"), Some(json!("Draft")));
+
+        undo.undo(&mut doc, 4);
+        assert_eq!(doc.get_value("title"), None);
+        assert!(!undo.can_undo());
+
+        undo.redo(&mut doc, 5);
+        undo.redo(&mut doc, 6);
+        assert_eq!(doc.get_value("title"), None);
+        assert!(!undo.can_redo());
+    }
+
+    #[test]
+    fn test_undo_keeps_remote_writes() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut undo = UndoManager::new(500);
+
+        undo.set_field(&mut doc, "title", json!("Mine"), 1, "client1", 0);
+        undo.set_field(&mut doc, "body", json!("Text"), 2, "client1", 100);
+
+        // Another client overwrites the title
+        doc.set_field(
+            "title".to_string(),
+            json!("Theirs"),
+            3,
+            "client2".to_string(),
+        );
+
+        // Only the body is reverted
+        let ops = undo.undo(&mut doc, 4);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].0, "body");
+        assert_eq!(doc.get_value("title"), Some(json!("Theirs")));
+        assert_eq!(doc.get_value("body"), None);
+
+        // The reverting write syncs like any other
+        let mut remote = Document::new("doc-1".to_string());
+        remote.set_field("body".to_string(), json!("Text"), 2, "client1".to_string());
+        for (path, field) in ops {
+            remote.merge_field(path, field);
+        }
+        assert_eq!(remote.get_value("body"), None);
+    }
+
+    #[test]
+    fn test_undo_keeps_remote_writes_to_children() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut undo = UndoManager::new(500);
+
+        undo.set_field(
+            &mut doc,
+            "user",
+            json!({ "name": "Bo", "age": 1 }),
+            1,
+            "client1",
+            0,
+        );
+        undo.set_field(
+            &mut doc,
+            "user",
+            json!({ "name": "Ann", "age": 2 }),
+            2,
+            "client1",
+            1000,
+        );
+
+        // Another client edits a field inside the object
+        doc.set_field(
+            "user.name".to_string(),
+            json!("Remote"),
+            3,
+            "client2".to_string(),
+        );
+
+        // Putting the old object back would drop the remote name
+        assert!(undo.undo(&mut doc, 4).is_empty());
+        assert_eq!(
+            doc.get_value("user"),
+            Some(json!({ "name": "Remote", "age": 2 }))
+        );
+    }
+
+    #[test]
+    fn test_capture_window_groups_edits() {
+        let mut doc = Document::new("doc-1".to_string());
+        let mut undo = UndoManager::new(500);
+
+        undo.set_field(&mut doc, "a", json!(1), 1, "client1", 0);
+        undo.set_field(&mut doc, "a", json!(2), 2, "client1", 300);
+        undo.set_field(&mut doc, "b", json!(3), 3, "client1", 600);
+        undo.set_field(&mut doc, "c", json!(4), 4, "client1", 2000);
+        assert_eq!(undo.undo_stack().len(), 2);
+        assert_eq!(undo.undo_stack()[0].len(), 2);
+
+        undo.undo(&mut doc, 5);
+        assert_eq!(doc.get_value("c"), None);
+        undo.undo(&mut doc, 6);
+        assert_eq!(doc.to_json(), json!({}));
+
+        // A new edit drops the redo stack
+        undo.set_field(&mut doc, "d", json!(5), 7, "client1", 3000);
+        assert!(!undo.can_redo());
+    }
+
+    #[cfg(feature = "text-crdt")]
+    #[test]
+    fn test_undo_insert_keeps_concurrent_text() {
+        let mut alice = FugueText::new("alice".to_string());
+        let mut bob = FugueText::new("bob".to_string());
+        let mut undo = UndoManager::new(500);
+
+        alice.insert(0, "Hello").unwrap();
+        undo.stop_capturing();
+        undo.insert(&mut alice, 5, " world", 1000).unwrap();
+
+        // Bob types inside Alice's insert
+        bob.merge(&alice).unwrap();
+        bob.insert(8, "--").unwrap();
+        alice.merge(&bob).unwrap();
+        assert_eq!(alice.to_string(), "Hello wo--rld");
+
+        let ops = undo.undo(&mut alice, 0);
+        assert_eq!(alice.to_string(), "Hello--");
+        for op in &ops {
+            bob.apply_op(op).unwrap();
+        }
+        assert_eq!(bob.to_string(), "Hello--");
+
+        // Each run comes back on its own side of Bob's text
+        undo.redo(&mut alice, 0);
+        assert_eq!(alice.to_string(), "Hello wo--rld");
+    }
+
+    #[cfg(feature = "text-crdt")]
+    #[test]
+    fn test_undo_follows_restored_text() {
+        let mut text = FugueText::new("client1".to_string());
+        let mut undo = UndoManager::new(500);
+
+        undo.insert(&mut text, 0, "Hello", 0).unwrap();
+        undo.delete(&mut text, 1, 3, 1000).unwrap();
+        assert_eq!(text.to_string(), "Ho");
+
+        // The restored "ell" are new characters, yet undoing the insert
+        // still removes them
+        undo.undo(&mut text, 0);
+        assert_eq!(text.to_string(), "Hello");
+        undo.undo(&mut text, 0);
+        assert_eq!(text.to_string(), "");
+
+        undo.redo(&mut text, 0);
+        undo.redo(&mut text, 0);
+        assert_eq!(text.to_string(), "Ho");
+    }
+
+    #[cfg(feature = "text-crdt")]
+    #[test]
+    fn test_undo_delete_restores_text_in_place() {
+        let mut alice = FugueText::new("alice".to_string());
+        let mut bob = FugueText::new("bob".to_string());
+        let mut undo = UndoManager::new(500);
+
+        alice.insert(0, "Hello brave new world").unwrap();
+        undo.delete(&mut alice, 5, 6, 0).unwrap();
+        undo.delete(&mut alice, 5, 4, 100).unwrap();
+        assert_eq!(alice.to_string(), "Hello world");
+
+        // Bob edits before the deleted text in the meantime
+        bob.merge(&alice).unwrap();
+        bob.insert(0, ">> ").unwrap();
+        alice.merge(&bob).unwrap();
+
+        undo.undo(&mut alice, 0);
+        assert_eq!(alice.to_string(), ">> Hello brave new world");
+
+        undo.redo(&mut alice, 0);
+        assert_eq!(alice.to_string(), ">> Hello world");
+    }
+}
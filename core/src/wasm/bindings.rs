@@ -1,13 +1,9 @@
 //! JavaScript bindings for SyncKit core types
 
-use crate::document::Document;
-use crate::sync::VectorClock;
+use crate::document::{Document, FieldMode};
+use crate::sync::{Delta, VectorClock};
 use wasm_bindgen::prelude::*;
 
-// DocumentDelta is only available with protocol support
-#[cfg(feature = "prost")]
-use crate::protocol::delta::DocumentDelta;
-
 /// JavaScript-friendly wrapper for Document
 #[wasm_bindgen]
 pub struct WasmDocument {
@@ -41,17 +37,45 @@ impl WasmDocument {
     }
 
     /// Get a field value (returns JSON string)
+    ///
+    /// Nested paths (`user.name`, `items[0]`) are resolved against the
+    /// writes at, above and below the path (see `Document::get_value`).
     #[wasm_bindgen(js_name = getField)]
     pub fn get_field(&self, path: String) -> Option<String> {
         self.inner
-            .get_field(&path)
-            .map(|field| serde_json::to_string(&field).unwrap())
+            .get_value(&path)
+            .map(|value| serde_json::to_string(&value).unwrap())
     }
 
-    /// Delete a field
+    /// Delete a field (records a tombstone at the given clock)
     #[wasm_bindgen(js_name = deleteField)]
-    pub fn delete_field(&mut self, path: String) {
-        self.inner.delete_field(&path);
+    pub fn delete_field(&mut self, path: String, clock: u64, client_id: String) {
+        self.inner.delete_field(path, clock, client_id);
+    }
+
+    /// Choose how concurrent writes to a field are resolved
+    ///
+    /// `multi_value` keeps all concurrent values instead of the newest one.
+    /// A multi-value field cannot be switched back.
+    #[wasm_bindgen(js_name = setFieldMode)]
+    pub fn set_field_mode(&mut self, path: String, multi_value: bool) -> Result<(), JsValue> {
+        let mode = if multi_value {
+            FieldMode::MultiValue
+        } else {
+            FieldMode::Lww
+        };
+
+        self.inner
+            .set_field_mode(path, mode)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Get all current values of a field (returns a JSON array)
+    ///
+    /// More than one value means unresolved concurrent writes.
+    #[wasm_bindgen(js_name = getFieldValues)]
+    pub fn get_field_values(&self, path: String) -> String {
+        serde_json::to_string(&self.inner.get_field_values(&path)).unwrap()
     }
 
     /// Get document ID
@@ -132,30 +156,37 @@ impl WasmVectorClock {
     }
 }
 
-/// JavaScript-friendly wrapper for DocumentDelta
-/// Only available when protocol support is enabled (core variant, not core-lite)
-#[cfg(feature = "prost")]
+/// JavaScript-friendly wrapper for Delta
 #[wasm_bindgen]
 pub struct WasmDelta {
-    inner: DocumentDelta,
+    inner: Delta,
 }
 
-#[cfg(feature = "prost")]
 #[wasm_bindgen]
 impl WasmDelta {
     /// Compute delta between two documents
     #[wasm_bindgen(js_name = compute)]
     pub fn compute(from: &WasmDocument, to: &WasmDocument) -> Result<WasmDelta, JsValue> {
-        DocumentDelta::compute(&from.inner, &to.inner)
+        Delta::compute(&from.inner, &to.inner)
             .map(|delta| WasmDelta { inner: delta })
             .map_err(|e| JsValue::from_str(&format!("Delta computation failed: {}", e)))
     }
 
-    /// Apply delta to a document
+    /// Parse a delta exported with `toJSON`
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: &str) -> Result<WasmDelta, JsValue> {
+        serde_json::from_str(json)
+            .map(|delta| WasmDelta { inner: delta })
+            .map_err(|e| JsValue::from_str(&format!("Invalid delta JSON: {}", e)))
+    }
+
+    /// Apply delta to a document (LWW merge, like a remote update)
+    ///
+    /// `_client_id` is unused; every change carries its writer's ID.
     #[wasm_bindgen(js_name = applyTo)]
-    pub fn apply_to(&self, document: &mut WasmDocument, client_id: String) -> Result<(), JsValue> {
+    pub fn apply_to(&self, document: &mut WasmDocument, _client_id: String) -> Result<(), JsValue> {
         self.inner
-            .apply_to(&mut document.inner, &client_id)
+            .apply_to(&mut document.inner)
             .map_err(|e| JsValue::from_str(&format!("Delta application failed: {}", e)))
     }
 
@@ -168,7 +199,7 @@ impl WasmDelta {
     /// Get number of changes in this delta
     #[wasm_bindgen(js_name = changeCount)]
     pub fn change_count(&self) -> usize {
-        self.inner.changes.len()
+        self.inner.len()
     }
 
     /// Export as JSON string
@@ -205,15 +236,15 @@ impl WasmFugueText {
     /// * `text` - Text to insert
     ///
     /// # Returns
-    /// JSON string of NodeId for the created block
+    /// JSON string of the insert operation, to send to other replicas
     #[wasm_bindgen(js_name = insert)]
     pub fn insert(&mut self, position: usize, text: String) -> Result<String, JsValue> {
-        let node_id = self
+        let op = self
             .inner
             .insert(position, &text)
             .map_err(|e| JsValue::from_str(&format!("Insert failed: {}", e)))?;
 
-        serde_json::to_string(&node_id)
+        serde_json::to_string(&op)
             .map_err(|e| JsValue::from_str(&format!("JSON serialization failed: {}", e)))
     }
 
@@ -224,18 +255,98 @@ impl WasmFugueText {
     /// * `length` - Number of graphemes to delete
     ///
     /// # Returns
-    /// JSON string of array of deleted NodeIds
+    /// JSON string of the delete operation, to send to other replicas
     #[wasm_bindgen(js_name = delete)]
     pub fn delete(&mut self, position: usize, length: usize) -> Result<String, JsValue> {
-        let deleted_ids = self
+        let op = self
             .inner
             .delete(position, length)
             .map_err(|e| JsValue::from_str(&format!("Delete failed: {}", e)))?;
 
-        serde_json::to_string(&deleted_ids)
+        serde_json::to_string(&op)
             .map_err(|e| JsValue::from_str(&format!("JSON serialization failed: {}", e)))
     }
 
+    /// Apply an operation returned by `insert`/`delete` on another replica
+    ///
+    /// Operations that reference text not seen yet are buffered and applied
+    /// once their dependencies arrive.
+    ///
+    /// # Arguments
+    /// * `op_json` - JSON string of the operation
+    #[wasm_bindgen(js_name = applyOp)]
+    pub fn apply_op(&mut self, op_json: &str) -> Result<(), JsValue> {
+        let op: crate::crdt::FugueOp = serde_json::from_str(op_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parse failed: {}", e)))?;
+
+        self.inner
+            .apply_op(&op)
+            .map_err(|e| JsValue::from_str(&format!("Apply failed: {}", e)))
+    }
+
+    /// Get the number of buffered operations waiting for their dependencies
+    #[wasm_bindgen(js_name = pendingOps)]
+    pub fn pending_ops(&self) -> usize {
+        self.inner.pending_ops()
+    }
+
+    /// Get the state vector summarizing which operations have been seen
+    ///
+    /// Returns a JSON VectorClock to send to a peer, which answers with
+    /// `encodeDiff`.
+    #[wasm_bindgen(js_name = stateVector)]
+    pub fn state_vector(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner.state_vector())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Encode the operations a peer is missing
+    ///
+    /// # Arguments
+    /// * `state_vector_json` - JSON state vector received from the peer
+    ///
+    /// Returns a JSON array of FugueOps to pass to the peer's `applyDiff`.
+    #[wasm_bindgen(js_name = encodeDiff)]
+    pub fn encode_diff(&self, state_vector_json: &str) -> Result<String, JsValue> {
+        let since: crate::crdt::StateVector = serde_json::from_str(state_vector_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parse failed: {}", e)))?;
+
+        serde_json::to_string(&self.inner.encode_diff(&since))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Apply a diff produced by a peer's `encodeDiff`
+    ///
+    /// # Arguments
+    /// * `diff_json` - JSON array of operations
+    #[wasm_bindgen(js_name = applyDiff)]
+    pub fn apply_diff(&mut self, diff_json: &str) -> Result<(), JsValue> {
+        let ops: Vec<crate::crdt::FugueOp> = serde_json::from_str(diff_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parse failed: {}", e)))?;
+
+        for op in &ops {
+            self.inner
+                .apply_op(op)
+                .map_err(|e| JsValue::from_str(&format!("Apply failed: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Garbage-collect tombstones deleted at or below a stable frontier
+    ///
+    /// # Arguments
+    /// * `frontier_json` - JSON VectorClock every replica has reached
+    ///
+    /// Returns JSON GcStats, including the bytes reclaimed.
+    #[wasm_bindgen(js_name = gc)]
+    pub fn gc(&mut self, frontier_json: &str) -> Result<String, JsValue> {
+        let frontier: crate::sync::VectorClock = serde_json::from_str(frontier_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parse failed: {}", e)))?;
+
+        serde_json::to_string(&self.inner.gc(&frontier))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
     /// Get the NodeId of the character at the given position
     ///
     /// Returns a stable NodeId that identifies the character at the specified
@@ -256,7 +367,7 @@ impl WasmFugueText {
     /// // Returns: '{"client_id":"client1","clock":1,"offset":2}'
     /// ```
     #[wasm_bindgen(js_name = getNodeIdAtPosition)]
-    pub fn get_node_id_at_position(&mut self, position: usize) -> Result<String, JsValue> {
+    pub fn get_node_id_at_position(&self, position: usize) -> Result<String, JsValue> {
         let node_id = self
             .inner
             .get_node_id_at_position(position)
@@ -285,7 +396,7 @@ impl WasmFugueText {
     /// // Returns: 2 (or -1 if deleted)
     /// ```
     #[wasm_bindgen(js_name = getPositionOfNodeId)]
-    pub fn get_position_of_node_id(&mut self, node_id_json: &str) -> Result<i32, JsValue> {
+    pub fn get_position_of_node_id(&self, node_id_json: &str) -> Result<i32, JsValue> {
         let node_id: crate::crdt::text_fugue::NodeId = serde_json::from_str(node_id_json)
             .map_err(|e| JsValue::from_str(&format!("JSON parse failed: {}", e)))?;
 
@@ -295,6 +406,42 @@ impl WasmFugueText {
         }
     }
 
+    /// Create a stable cursor at the gap before a position
+    ///
+    /// # Arguments
+    /// * `position` - Grapheme index of the gap (0..=length)
+    /// * `stick_before` - Stick to the character before the gap instead of
+    ///   the one after it
+    ///
+    /// # Returns
+    /// Compact cursor string for awareness payloads, e.g. `">client1@3:0"`
+    #[wasm_bindgen(js_name = cursorAt)]
+    pub fn cursor_at(&self, position: usize, stick_before: bool) -> Result<String, JsValue> {
+        let assoc = if stick_before {
+            crate::crdt::text_fugue::Assoc::Before
+        } else {
+            crate::crdt::text_fugue::Assoc::After
+        };
+
+        self.inner
+            .cursor_at(position, assoc)
+            .map(|cursor| cursor.to_string())
+            .map_err(|e| JsValue::from_str(&format!("Cursor failed: {}", e)))
+    }
+
+    /// Resolve a cursor from `cursorAt` to its current position
+    ///
+    /// A cursor whose character was deleted resolves to where it was.
+    /// Returns undefined if the character has not arrived yet.
+    #[wasm_bindgen(js_name = resolveCursor)]
+    pub fn resolve_cursor(&self, cursor: &str) -> Result<Option<usize>, JsValue> {
+        let cursor: crate::crdt::text_fugue::Cursor = cursor
+            .parse()
+            .map_err(|e| JsValue::from_str(&format!("Cursor parse failed: {}", e)))?;
+
+        Ok(self.inner.resolve_cursor(&cursor))
+    }
+
     /// Get the text content as a string
     #[wasm_bindgen(js_name = toString)]
     #[allow(clippy::inherent_to_string)]
@@ -327,6 +474,8 @@ impl WasmFugueText {
     }
 
     /// Merge with another FugueText
+    ///
+    /// Ships the whole state; prefer `applyOp` for incremental updates.
     #[wasm_bindgen(js_name = merge)]
     pub fn merge(&mut self, other: &WasmFugueText) -> Result<(), JsValue> {
         self.inner
@@ -351,6 +500,228 @@ impl WasmFugueText {
     }
 }
 
+/// JavaScript-friendly undo/redo manager for a WasmDocument
+///
+/// Field writes made through the manager are recorded; `undo` and `redo`
+/// return the field writes they made, as JSON `[path, field]` pairs, to be
+/// synced like any other write.
+#[wasm_bindgen]
+pub struct WasmDocumentUndoManager {
+    inner: crate::undo::UndoManager<Document>,
+}
+
+#[wasm_bindgen]
+impl WasmDocumentUndoManager {
+    /// Create a manager grouping edits within `capture_window_ms`
+    #[wasm_bindgen(constructor)]
+    pub fn new(capture_window_ms: u64) -> Self {
+        Self {
+            inner: crate::undo::UndoManager::new(capture_window_ms),
+        }
+    }
+
+    /// Set a field (pass JSON string for value) and record the write
+    ///
+    /// `now` is the time of the edit in milliseconds, e.g. `Date.now()`.
+    #[wasm_bindgen(js_name = setField)]
+    pub fn set_field(
+        &mut self,
+        doc: &mut WasmDocument,
+        path: String,
+        value_json: String,
+        clock: u64,
+        client_id: String,
+        now: f64,
+    ) -> Result<(), JsValue> {
+        let value: serde_json::Value = serde_json::from_str(&value_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid JSON: {}", e)))?;
+
+        self.inner
+            .set_field(&mut doc.inner, &path, value, clock, &client_id, now as u64);
+        Ok(())
+    }
+
+    /// Delete a field and record the delete
+    #[wasm_bindgen(js_name = deleteField)]
+    pub fn delete_field(
+        &mut self,
+        doc: &mut WasmDocument,
+        path: String,
+        clock: u64,
+        client_id: String,
+        now: f64,
+    ) {
+        self.inner
+            .delete_field(&mut doc.inner, &path, clock, &client_id, now as u64);
+    }
+
+    /// Undo the last step, writing with the given clock
+    ///
+    /// Returns the writes made as JSON (empty array if nothing to undo).
+    #[wasm_bindgen(js_name = undo)]
+    pub fn undo(&mut self, doc: &mut WasmDocument, clock: u64) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner.undo(&mut doc.inner, clock))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Redo the last undone step, writing with the given clock
+    #[wasm_bindgen(js_name = redo)]
+    pub fn redo(&mut self, doc: &mut WasmDocument, clock: u64) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner.redo(&mut doc.inner, clock))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Close the current step, so the next edit starts a new one
+    #[wasm_bindgen(js_name = stopCapturing)]
+    pub fn stop_capturing(&mut self) {
+        self.inner.stop_capturing();
+    }
+
+    /// Check whether there is a step to undo
+    #[wasm_bindgen(js_name = canUndo)]
+    pub fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    /// Check whether there is a step to redo
+    #[wasm_bindgen(js_name = canRedo)]
+    pub fn can_redo(&self) -> bool {
+        self.inner.can_redo()
+    }
+
+    /// Undo stack as JSON (array of steps, most recent last)
+    #[wasm_bindgen(js_name = undoStack)]
+    pub fn undo_stack(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.inner.undo_stack())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Redo stack as JSON (array of steps, most recently undone last)
+    #[wasm_bindgen(js_name = redoStack)]
+    pub fn redo_stack(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.inner.redo_stack())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Forget all recorded edits
+    #[wasm_bindgen(js_name = clear)]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+/// JavaScript-friendly undo/redo manager for a WasmFugueText
+///
+/// Edits made through the manager are recorded; `undo` and `redo` return
+/// the FugueOps they made as JSON, to be sent to other replicas.
+#[cfg(feature = "text-crdt")]
+#[wasm_bindgen]
+pub struct WasmTextUndoManager {
+    inner: crate::undo::UndoManager<crate::crdt::FugueText>,
+}
+
+#[cfg(feature = "text-crdt")]
+#[wasm_bindgen]
+impl WasmTextUndoManager {
+    /// Create a manager grouping edits within `capture_window_ms`
+    #[wasm_bindgen(constructor)]
+    pub fn new(capture_window_ms: u64) -> Self {
+        Self {
+            inner: crate::undo::UndoManager::new(capture_window_ms),
+        }
+    }
+
+    /// Insert text and record the insert; returns the JSON FugueOp
+    ///
+    /// `now` is the time of the edit in milliseconds, e.g. `Date.now()`.
+    #[wasm_bindgen(js_name = insert)]
+    pub fn insert(
+        &mut self,
+        text: &mut WasmFugueText,
+        position: usize,
+        content: String,
+        now: f64,
+    ) -> Result<String, JsValue> {
+        let op = self
+            .inner
+            .insert(&mut text.inner, position, &content, now as u64)
+            .map_err(|e| JsValue::from_str(&format!("Insert failed: {}", e)))?;
+
+        serde_json::to_string(&op)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Delete text and record the delete; returns the JSON FugueOp
+    #[wasm_bindgen(js_name = delete)]
+    pub fn delete(
+        &mut self,
+        text: &mut WasmFugueText,
+        position: usize,
+        length: usize,
+        now: f64,
+    ) -> Result<String, JsValue> {
+        let op = self
+            .inner
+            .delete(&mut text.inner, position, length, now as u64)
+            .map_err(|e| JsValue::from_str(&format!("Delete failed: {}", e)))?;
+
+        serde_json::to_string(&op)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Undo the last step; returns the JSON FugueOps made
+    #[wasm_bindgen(js_name = undo)]
+    pub fn undo(&mut self, text: &mut WasmFugueText) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner.undo(&mut text.inner, 0))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Redo the last undone step; returns the JSON FugueOps made
+    #[wasm_bindgen(js_name = redo)]
+    pub fn redo(&mut self, text: &mut WasmFugueText) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner.redo(&mut text.inner, 0))
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Close the current step, so the next edit starts a new one
+    #[wasm_bindgen(js_name = stopCapturing)]
+    pub fn stop_capturing(&mut self) {
+        self.inner.stop_capturing();
+    }
+
+    /// Check whether there is a step to undo
+    #[wasm_bindgen(js_name = canUndo)]
+    pub fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    /// Check whether there is a step to redo
+    #[wasm_bindgen(js_name = canRedo)]
+    pub fn can_redo(&self) -> bool {
+        self.inner.can_redo()
+    }
+
+    /// Undo stack as JSON (array of steps, most recent last)
+    #[wasm_bindgen(js_name = undoStack)]
+    pub fn undo_stack(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.inner.undo_stack())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Redo stack as JSON (array of steps, most recently undone last)
+    #[wasm_bindgen(js_name = redoStack)]
+    pub fn redo_stack(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.inner.redo_stack())
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
+    /// Forget all recorded edits
+    #[wasm_bindgen(js_name = clear)]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
 /// JavaScript-friendly wrapper for PNCounter CRDT
 /// Only available when counters feature is enabled
 #[cfg(feature = "counters")]
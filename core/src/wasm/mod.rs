@@ -10,8 +10,6 @@ pub mod utils;
 
 // Re-export main types
 #[cfg(feature = "wasm")]
-pub use bindings::{WasmAwareness, WasmDocument, WasmVectorClock};
-
-// WasmDelta only available with protocol support
-#[cfg(all(feature = "wasm", feature = "prost"))]
-pub use bindings::WasmDelta;
+pub use bindings::{
+    WasmAwareness, WasmDelta, WasmDocument, WasmDocumentUndoManager, WasmVectorClock,
+};
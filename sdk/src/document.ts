@@ -179,7 +179,12 @@ export class SyncDocument<T extends Record<string, unknown> = Record<string, unk
       throw new DocumentError('Document not initialized')
     }
     
-    this.wasmDoc.deleteField(String(field))
+    // Deletes are timestamped like sets so they survive merges
+    const newCount = (this.vectorClock[this.clientId] || 0) + 1
+    this.vectorClock[this.clientId] = newCount
+    const clock = BigInt(newCount)
+
+    this.wasmDoc.deleteField(String(field), clock, this.clientId)
     this.updateLocalState()
     await this.persist()
     this.notifySubscribers()
@@ -15,10 +15,18 @@ import type { CrossTabSync } from './sync/cross-tab'
 import type { TextInsertMessage, TextDeleteMessage } from './sync/message-types'
 
 export interface WasmFugueText {
-  insert(position: number, text: string): string  // returns JSON NodeId
-  delete(position: number, length: number): string  // returns JSON NodeId[]
+  insert(position: number, text: string): string  // returns JSON FugueOp
+  delete(position: number, length: number): string  // returns JSON FugueOp
+  applyOp(opJson: string): void
+  pendingOps(): number
+  stateVector(): string  // returns JSON VectorClock
+  encodeDiff(stateVectorJson: string): string  // returns JSON FugueOp[]
+  applyDiff(diffJson: string): void
+  gc(frontierJson: string): string  // returns JSON GcStats
   getNodeIdAtPosition(position: number): string  // returns JSON NodeId
   getPositionOfNodeId(nodeIdJson: string): number  // returns position or -1 if deleted
+  cursorAt(position: number, stickBefore: boolean): string  // returns compact cursor
+  resolveCursor(cursor: string): number | undefined
   toString(): string
   length(): number
   isEmpty(): boolean
@@ -21,7 +21,9 @@ export interface WasmDocument {
   getId(): string
   setField(path: string, valueJson: string, clock: bigint, clientId: string): void
   getField(path: string): string | undefined
-  deleteField(path: string): void
+  deleteField(path: string, clock: bigint, clientId: string): void
+  setFieldMode(path: string, multiValue: boolean): void
+  getFieldValues(path: string): string
   fieldCount(): number
   toJSON(): string
   merge(other: WasmDocument): void
@@ -16,6 +16,7 @@ export interface WASMModule {
   }
   WasmDelta: {
     compute(from: WasmDocument, to: WasmDocument): WasmDelta
+    fromJSON(json: string): WasmDelta
   }
   WasmAwareness: {
     new (clientId: string): any // WasmAwareness instance
@@ -27,7 +28,9 @@ export interface WasmDocument {
   getId(): string
   setField(path: string, valueJson: string, clock: bigint, clientId: string): void
   getField(path: string): string | undefined
-  deleteField(path: string): void
+  deleteField(path: string, clock: bigint, clientId: string): void
+  setFieldMode(path: string, multiValue: boolean): void
+  getFieldValues(path: string): string
   fieldCount(): number
   toJSON(): string
   merge(other: WasmDocument): void